@@ -0,0 +1,128 @@
+//! Looping GIF/APNG previews of a mode, one flicker cycle long.
+//!
+//! Most modes move in whole multiples of the flicker frequency, so a single
+//! cycle loops without a seam; modes drifting at their own pace (plasma,
+//! noise, breathing) jump once per loop.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use egui::ColorImage;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+
+use crate::error::Result;
+use crate::flicker::Flicker;
+use crate::modes::{ModeParams, VisualMode};
+use crate::render::OfflineRenderer;
+
+/// Frame rate the cycle is sampled at. Browsers slow down GIF frames
+/// shorter than 20 ms, so going higher only helps APNG.
+const LOOP_FPS: f32 = 50.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LoopFormat {
+    Gif,
+    Apng,
+}
+
+impl LoopFormat {
+    pub const ALL: [LoopFormat; 2] = [LoopFormat::Gif, LoopFormat::Apng];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LoopFormat::Gif => "GIF",
+            LoopFormat::Apng => "Animated PNG",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            LoopFormat::Gif => "gif",
+            LoopFormat::Apng => "png",
+        }
+    }
+
+    /// The format a file name asks for.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|f| f.extension() == ext)
+    }
+}
+
+/// One flicker cycle of a looped mode.
+pub struct Cycle {
+    pub frames: Vec<ColorImage>,
+    pub fps: f32,
+}
+
+impl Cycle {
+    /// Render one cycle of `mode` at the flicker's frequency. The cycle
+    /// after the first is kept, as the first starts from a dark screen
+    /// rather than from the previous pulse.
+    pub fn render(
+        renderer: &mut OfflineRenderer,
+        mode: &mut dyn VisualMode,
+        flicker: &mut Flicker,
+        params: &ModeParams,
+    ) -> Self {
+        let hz = flicker.frequency_hz();
+        // a whole number of frames per cycle, at least a dark and a light one
+        let per_cycle = (LOOP_FPS / hz).round().max(2.0) as usize;
+        let fps = per_cycle as f32 * hz;
+        let mut frames = Vec::with_capacity(per_cycle);
+        renderer.render_mode(mode, flicker, params, fps, 2 * per_cycle, |n, image| {
+            if n >= per_cycle {
+                frames.push(image);
+            }
+        });
+        Self { frames, fps }
+    }
+
+    pub fn save(&self, path: &Path, format: LoopFormat) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        match format {
+            LoopFormat::Gif => self.write_gif(file),
+            LoopFormat::Apng => self.write_apng(file),
+        }
+    }
+
+    fn write_gif(&self, file: BufWriter<File>) -> Result<()> {
+        let mut encoder = GifEncoder::new(file);
+        encoder.set_repeat(Repeat::Infinite)?;
+        // in hundredths of a millisecond; GIF itself rounds to 10 ms
+        let delay = || Delay::from_numer_denom_ms(100_000, (self.fps * 100.0).round() as u32);
+        let frames = self.frames.iter().map(|image| {
+            let [w, h] = image.size;
+            let buffer = RgbaImage::from_raw(w as u32, h as u32, rgba(image))
+                .expect("buffer matches the image size");
+            Frame::from_parts(buffer, 0, 0, delay())
+        });
+        encoder.encode_frames(frames)?;
+        Ok(())
+    }
+
+    fn write_apng(&self, file: BufWriter<File>) -> Result<()> {
+        let Some([w, h]) = self.frames.first().map(|f| f.size) else {
+            return Ok(());
+        };
+        let mut encoder = png::Encoder::new(file, w as u32, h as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // zero plays loops forever
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+        encoder.set_frame_delay(100, (self.fps * 100.0).round() as u16)?;
+        let mut writer = encoder.write_header()?;
+        for image in &self.frames {
+            writer.write_image_data(&rgba(image))?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// The frames are opaque, so premultiplied is plain RGBA.
+fn rgba(image: &ColorImage) -> Vec<u8> {
+    image.pixels.iter().flat_map(|c| c.to_array()).collect()
+}
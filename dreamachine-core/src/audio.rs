@@ -0,0 +1,206 @@
+//! Audio entrainment signal generation.
+//!
+//! Only the sample math lives here; opening an output device is left to the
+//! frontend.
+
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+/// Settings shared between the UI and the audio callback.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Set by the frontend while a session is running.
+    #[serde(skip)]
+    pub playing: bool,
+    /// Overall gain on top of the per-generator volumes, used for session fades.
+    #[serde(skip)]
+    pub level: f32,
+    pub binaural: bool,
+    pub carrier_hz: f32,
+    /// Follows the flash frequency.
+    #[serde(skip)]
+    pub beat_hz: f32,
+    pub volume: f32, // 0..=1
+
+    pub isochronic: bool,
+    /// Mirrors the visual flash state; the isochronic tone sounds while set.
+    #[serde(skip)]
+    pub gate: bool,
+    pub iso_carrier_hz: f32,
+    /// Fraction of each flash during which the tone sounds.
+    pub iso_duty: f32,
+    pub iso_volume: f32, // 0..=1
+
+    pub noise: bool,
+    pub noise_volume: f32, // 0..=1
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            level: 1.0,
+            binaural: false,
+            carrier_hz: 200.0,
+            beat_hz: 10.0,
+            volume: 0.3,
+            isochronic: false,
+            gate: false,
+            iso_carrier_hz: 300.0,
+            iso_duty: 0.8,
+            iso_volume: 0.3,
+            noise: false,
+            noise_volume: 0.3,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Whether any generator is switched on.
+    pub fn any_enabled(&self) -> bool {
+        self.binaural || self.isochronic || self.noise
+    }
+}
+
+/// Every enabled generator summed into one stereo signal at the session
+/// level.
+#[derive(Default)]
+pub struct Mixer {
+    binaural: Binaural,
+    isochronic: Isochronic,
+    noise: Noise,
+}
+
+impl Mixer {
+    /// Produce the next `[left, right]` sample.
+    pub fn next(&mut self, settings: &AudioSettings, sample_rate: f32) -> [f32; 2] {
+        if !settings.playing {
+            return [0.0, 0.0];
+        }
+        let [mut left, mut right] = if settings.binaural {
+            self.binaural.next(settings, sample_rate)
+        } else {
+            [0.0, 0.0]
+        };
+        if settings.isochronic {
+            let tone = self.isochronic.next(settings, sample_rate);
+            left += tone;
+            right += tone;
+        }
+        if settings.noise {
+            let n = self.noise.next(settings);
+            left += n;
+            right += n;
+        }
+        [left * settings.level, right * settings.level]
+    }
+}
+
+/// Stereo oscillator pair whose frequencies differ by the beat frequency.
+#[derive(Default)]
+pub struct Binaural {
+    phase_left: f32,
+    phase_right: f32,
+}
+
+impl Binaural {
+    /// Produce the next `[left, right]` sample.
+    pub fn next(&mut self, settings: &AudioSettings, sample_rate: f32) -> [f32; 2] {
+        let half_beat = settings.beat_hz * 0.5;
+        let left = advance(
+            &mut self.phase_left,
+            settings.carrier_hz - half_beat,
+            sample_rate,
+        );
+        let right = advance(
+            &mut self.phase_right,
+            settings.carrier_hz + half_beat,
+            sample_rate,
+        );
+        [left * settings.volume, right * settings.volume]
+    }
+}
+
+/// Carrier tone switched on and off by [`AudioSettings::gate`].
+#[derive(Default)]
+pub struct Isochronic {
+    phase: f32,
+    last_gate: bool,
+    samples_since_rise: u32,
+    gain: f32,
+}
+
+impl Isochronic {
+    /// Attack/release time, long enough to avoid clicks at the gate edges.
+    const RAMP_SECS: f32 = 0.005;
+
+    /// Produce the next mono sample.
+    pub fn next(&mut self, settings: &AudioSettings, sample_rate: f32) -> f32 {
+        if settings.gate && !self.last_gate {
+            self.samples_since_rise = 0;
+        }
+        self.last_gate = settings.gate;
+        self.samples_since_rise = self.samples_since_rise.saturating_add(1);
+
+        // The flash is the bright half of the cycle, i.e. 0.5 / beat_hz seconds.
+        let on_secs = settings.iso_duty * 0.5 / settings.beat_hz.max(f32::EPSILON);
+        let elapsed = self.samples_since_rise as f32 / sample_rate;
+        let target = if settings.gate && elapsed < on_secs {
+            1.0
+        } else {
+            0.0
+        };
+        let step = 1.0 / (Self::RAMP_SECS * sample_rate);
+        self.gain = if target > self.gain {
+            (self.gain + step).min(target)
+        } else {
+            (self.gain - step).max(target)
+        };
+
+        advance(&mut self.phase, settings.iso_carrier_hz, sample_rate)
+            * self.gain
+            * settings.iso_volume
+    }
+}
+
+/// Pink noise, for Ganzfeld sessions.
+pub struct Noise {
+    seed: u32,
+    b: [f32; 3],
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            seed: 0x9E37_79B9,
+            b: [0.0; 3],
+        }
+    }
+}
+
+impl Noise {
+    /// Produce the next mono sample.
+    pub fn next(&mut self, settings: &AudioSettings) -> f32 {
+        // xorshift32 white noise in -1..1
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        let white = self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+
+        // Paul Kellet's economy pinking filter
+        self.b[0] = 0.99765 * self.b[0] + white * 0.0990460;
+        self.b[1] = 0.96300 * self.b[1] + white * 0.2965164;
+        self.b[2] = 0.57000 * self.b[2] + white * 1.0526913;
+        let pink = self.b[0] + self.b[1] + self.b[2] + white * 0.1848;
+        pink * 0.25 * settings.noise_volume
+    }
+}
+
+/// Step a phase accumulator and return the sine at the old phase.
+fn advance(phase: &mut f32, hz: f32, sample_rate: f32) -> f32 {
+    let out = phase.sin();
+    *phase = (*phase + TAU * hz / sample_rate) % TAU;
+    out
+}
@@ -0,0 +1,136 @@
+//! Stacking extra modes on top of the main one.
+
+use egui::epaint::shape_transform::adjust_colors;
+use egui::layers::ShapeIdx;
+use egui::{Color32, Painter, Rect, Shape, Ui};
+
+use crate::modes::{ModeParams, VisualMode};
+
+/// How a layer combines with what is already on screen.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Blend {
+    /// Paint over, weighted by the layer opacity.
+    Normal,
+    /// Add the layer's light to what is below, so dark areas leave it untouched.
+    Add,
+}
+
+impl Blend {
+    pub const ALL: [Blend; 2] = [Blend::Normal, Blend::Add];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Blend::Normal => "Normal",
+            Blend::Add => "Add",
+        }
+    }
+}
+
+pub struct Layer {
+    /// Index into the mode registry.
+    pub mode: usize,
+    pub opacity: f32,
+    pub blend: Blend,
+}
+
+/// Overlay layers painted above the main mode, bottom first.
+#[derive(Default)]
+pub struct Compositor {
+    pub layers: Vec<Layer>,
+}
+
+impl Compositor {
+    /// Paint every layer over whatever `painter` already holds. Layers using
+    /// the `base` mode are skipped, as it is already on screen.
+    pub fn paint(
+        &self,
+        modes: &mut [Box<dyn VisualMode>],
+        base: usize,
+        painter: &Painter,
+        rect: Rect,
+        t: f32,
+        params: &ModeParams,
+    ) {
+        for layer in &self.layers {
+            if layer.mode == base {
+                continue;
+            }
+            let Some(mode) = modes.get_mut(layer.mode) else {
+                continue;
+            };
+            paint_blended(painter, layer.blend, layer.opacity, |p| {
+                mode.paint(p, rect, t, params)
+            });
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut Ui, mode_names: &[&str]) {
+        let mut remove = None;
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt(("layer_mode", i))
+                    .selected_text(mode_names.get(layer.mode).copied().unwrap_or("?"))
+                    .show_ui(ui, |ui| {
+                        for (m, name) in mode_names.iter().enumerate() {
+                            ui.selectable_value(&mut layer.mode, m, *name);
+                        }
+                    });
+                for blend in Blend::ALL {
+                    ui.radio_value(&mut layer.blend, blend, blend.name());
+                }
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+            ui.add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0).text("Opacity"));
+        }
+        if let Some(i) = remove {
+            self.layers.remove(i);
+        }
+        if ui.button("Add Layer").clicked() {
+            self.layers.push(Layer {
+                mode: 0,
+                opacity: 0.5,
+                blend: Blend::Normal,
+            });
+        }
+    }
+}
+
+/// Run `paint` and blend the shapes it adds into `painter`'s layer.
+pub fn paint_blended(painter: &Painter, blend: Blend, opacity: f32, paint: impl FnOnce(&Painter)) {
+    let mut layer = painter.clone();
+    layer.multiply_opacity(opacity);
+    match blend {
+        Blend::Normal => paint(&layer),
+        // zero alpha with premultiplied colour is pure addition
+        Blend::Add => adjust_painted(
+            painter,
+            |_| paint(&layer),
+            |color| {
+                *color = Color32::from_rgba_premultiplied(color.r(), color.g(), color.b(), 0);
+            },
+        ),
+    }
+}
+
+/// Run `paint`, then pass the colour of every shape it added to `painter`'s
+/// layer through `adjust`.
+pub fn adjust_painted(
+    painter: &Painter,
+    paint: impl FnOnce(&Painter),
+    adjust: impl Fn(&mut Color32) + Send + Sync + Copy + 'static,
+) {
+    // bracket the new shapes so they can be found in the paint list afterwards
+    let first = painter.add(Shape::Noop);
+    paint(painter);
+    let end = painter.add(Shape::Noop);
+    painter.ctx().graphics_mut(|graphics| {
+        let list = graphics.entry(painter.layer_id());
+        for idx in first.0 + 1..end.0 {
+            list.mutate_shape(ShapeIdx(idx), |clipped| {
+                adjust_colors(&mut clipped.shape, adjust);
+            });
+        }
+    });
+}
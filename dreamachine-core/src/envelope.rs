@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::waveform::Waveform;
+
+/// Attack and decay ramps applied to the edges of square-wave pulses, so
+/// each flash fades in and out over a few milliseconds instead of snapping.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Envelope {
+    pub attack_ms: f32,
+    pub decay_ms: f32,
+}
+
+impl Envelope {
+    /// Brightness of `waveform` at `phase` with the envelope applied. Only
+    /// the square wave has edges to shape; the others pass straight through.
+    pub fn level(&self, waveform: Waveform, phase: f32, frequency_hz: f32) -> f32 {
+        if waveform != Waveform::Square || frequency_hz <= 0.0 {
+            return waveform.level(phase);
+        }
+        let ramp = |secs: f32, ms: f32| {
+            if ms > 0.0 {
+                (secs * 1000.0 / ms).min(1.0)
+            } else {
+                1.0
+            }
+        };
+        if phase >= 0.5 {
+            ramp((phase - 0.5) / frequency_hz, self.attack_ms)
+        } else {
+            // a pulse shorter than the attack never reaches full brightness
+            let peak = ramp(0.5 / frequency_hz, self.attack_ms);
+            peak * (1.0 - ramp(phase / frequency_hz, self.decay_ms))
+        }
+    }
+}
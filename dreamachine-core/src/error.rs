@@ -0,0 +1,23 @@
+//! Errors from reading and writing files.
+
+use std::io;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid TOML: {0}")]
+    TomlParse(#[from] toml::de::Error),
+    #[error("couldn't write TOML: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("couldn't encode the image: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("couldn't encode the PNG: {0}")]
+    Png(#[from] png::EncodingError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+/// Caps continuous stimulation and enforces a rest afterwards.
+///
+/// Stopping and starting again doesn't reset the count; only a pause at
+/// least as long as the rest does.
+pub struct Exposure {
+    /// Longest continuous stimulation, or `None` for no limit.
+    pub limit: Option<Duration>,
+    pub rest: Duration,
+    exposed: Duration,
+    last_update: Option<Instant>,
+    idle_since: Option<Instant>,
+    rest_until: Option<Instant>,
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Self {
+            limit: Some(Duration::from_secs(30 * 60)),
+            rest: Duration::from_secs(10 * 60),
+            exposed: Duration::ZERO,
+            last_update: None,
+            idle_since: None,
+            rest_until: None,
+        }
+    }
+}
+
+impl Exposure {
+    /// Account for the time since the last call. Returns `true` when the
+    /// limit has just been reached and a rest begins.
+    pub fn update(&mut self, now: Instant, stimulating: bool) -> bool {
+        let dt = self
+            .last_update
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last_update = Some(now);
+        let Some(limit) = self.limit else {
+            self.exposed = Duration::ZERO;
+            self.rest_until = None;
+            return false;
+        };
+        if !stimulating {
+            let idle_since = *self.idle_since.get_or_insert(now);
+            if now.saturating_duration_since(idle_since) >= self.rest {
+                self.exposed = Duration::ZERO;
+            }
+            return false;
+        }
+        self.idle_since = None;
+        self.exposed += dt;
+        if self.exposed < limit {
+            return false;
+        }
+        self.exposed = Duration::ZERO;
+        self.rest_until = Some(now + self.rest);
+        self.idle_since = Some(now);
+        true
+    }
+
+    /// Time left of an enforced rest, if one is running.
+    pub fn resting(&self, now: Instant) -> Option<Duration> {
+        self.rest_until
+            .filter(|_| self.limit.is_some())
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|left| !left.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    fn exposure() -> Exposure {
+        Exposure {
+            limit: Some(60 * SEC),
+            rest: 30 * SEC,
+            ..Exposure::default()
+        }
+    }
+
+    #[test]
+    fn rests_after_the_limit() {
+        let clock = ManualClock::default();
+        let mut exposure = exposure();
+        assert!(!exposure.update(clock.now(), true));
+        clock.advance(59 * SEC);
+        assert!(!exposure.update(clock.now(), true));
+        clock.advance(SEC);
+        assert!(exposure.update(clock.now(), true));
+        assert_eq!(exposure.resting(clock.now()), Some(30 * SEC));
+        clock.advance(10 * SEC);
+        assert_eq!(exposure.resting(clock.now()), Some(20 * SEC));
+        clock.advance(20 * SEC);
+        assert_eq!(exposure.resting(clock.now()), None);
+    }
+
+    #[test]
+    fn only_a_full_rest_resets_the_count() {
+        let clock = ManualClock::default();
+        let mut exposure = exposure();
+        exposure.update(clock.now(), true);
+        clock.advance(40 * SEC);
+        exposure.update(clock.now(), true);
+        // a short pause keeps the 40 s
+        exposure.update(clock.now(), false);
+        clock.advance(10 * SEC);
+        exposure.update(clock.now(), false);
+        clock.advance(SEC);
+        assert!(!exposure.update(clock.now(), true));
+        clock.advance(19 * SEC);
+        assert!(exposure.update(clock.now(), true));
+
+        // a pause as long as the rest starts over
+        let mut exposure = self::exposure();
+        exposure.update(clock.now(), true);
+        clock.advance(40 * SEC);
+        exposure.update(clock.now(), true);
+        exposure.update(clock.now(), false);
+        clock.advance(30 * SEC);
+        exposure.update(clock.now(), false);
+        clock.advance(SEC);
+        exposure.update(clock.now(), true);
+        clock.advance(58 * SEC);
+        assert!(!exposure.update(clock.now(), true));
+        clock.advance(SEC);
+        assert!(exposure.update(clock.now(), true));
+    }
+}
@@ -0,0 +1,65 @@
+//! Steady gaze anchor drawn over every mode.
+
+use egui::{Color32, Painter, Rect, Stroke, Ui, Vec2};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FixationShape {
+    Cross,
+    Dot,
+}
+
+/// Central fixation cross or dot for open-eye sessions.
+pub struct Fixation {
+    pub enabled: bool,
+    pub shape: FixationShape,
+    pub size: f32, // points
+    pub color: Color32,
+}
+
+impl Default for Fixation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shape: FixationShape::Cross,
+            size: 24.0,
+            color: Color32::from_rgb(255, 32, 32),
+        }
+    }
+}
+
+impl Fixation {
+    pub fn paint(&self, painter: &Painter, rect: Rect) {
+        if !self.enabled {
+            return;
+        }
+        let center = rect.center();
+        match self.shape {
+            FixationShape::Cross => {
+                let half = self.size * 0.5;
+                let stroke = Stroke::new((self.size * 0.15).max(1.0), self.color);
+                painter.line_segment([center - Vec2::X * half, center + Vec2::X * half], stroke);
+                painter.line_segment([center - Vec2::Y * half, center + Vec2::Y * half], stroke);
+            }
+            FixationShape::Dot => {
+                painter.circle_filled(center, self.size * 0.5, self.color);
+            }
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Show fixation point");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.shape, FixationShape::Cross, "Cross");
+            ui.radio_value(&mut self.shape, FixationShape::Dot, "Dot");
+        });
+        ui.add(
+            egui::Slider::new(&mut self.size, 4.0..=120.0)
+                .text("Size")
+                .suffix(" pt"),
+        );
+        ui.horizontal(|ui| {
+            ui.color_edit_button_srgba(&mut self.color);
+            ui.label("Color");
+        });
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Instant;
+
+use crate::envelope::Envelope;
+use crate::waveform::Waveform;
+
+/// Lowest frequency the UI will accept.
+pub const MIN_FREQUENCY_HZ: f32 = 0.5;
+/// Highest frequency the UI will accept.
+pub const MAX_FREQUENCY_HZ: f32 = 40.0;
+
+/// Waveform generator driving the stimulus brightness.
+///
+/// The phase is accumulated from frame to frame, so changing the frequency
+/// mid-session bends the wave instead of making it jump.
+#[derive(Clone)]
+pub struct Flicker {
+    frequency_hz: f32,
+    waveform: Waveform,
+    envelope: Envelope,
+    phase: f32, // cycles, 0..1
+    // no pulse has happened yet, so there is nothing to decay from
+    first_cycle: bool,
+    last_update: Instant,
+}
+
+impl Flicker {
+    pub fn new(frequency_hz: f32) -> Self {
+        Self {
+            frequency_hz,
+            waveform: Waveform::default(),
+            envelope: Envelope::default(),
+            phase: 0.0,
+            first_cycle: true,
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn frequency_hz(&self) -> f32 {
+        self.frequency_hz
+    }
+
+    pub fn set_frequency(&mut self, frequency_hz: f32) {
+        self.frequency_hz = frequency_hz;
+    }
+
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.waveform = waveform;
+    }
+
+    pub fn envelope(&self) -> Envelope {
+        self.envelope
+    }
+
+    pub fn set_envelope(&mut self, envelope: Envelope) {
+        self.envelope = envelope;
+    }
+
+    /// Restart from the dark start of the cycle.
+    pub fn reset(&mut self, now: Instant) {
+        self.last_update = now;
+        self.phase = 0.0;
+        self.first_cycle = true;
+    }
+
+    /// Advance the phase to `now`.
+    pub fn update(&mut self, now: Instant) {
+        let dt = now.duration_since(self.last_update).as_secs_f32();
+        let phase = self.phase + dt * self.frequency_hz;
+        if phase >= 1.0 {
+            self.first_cycle = false;
+        }
+        self.phase = phase.fract();
+        self.last_update = now;
+    }
+
+    /// A copy advanced to `when`.
+    pub fn at(&self, when: Instant) -> Self {
+        let mut flicker = self.clone();
+        flicker.update(when);
+        flicker
+    }
+
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Current brightness in `0..=1`.
+    pub fn level(&self) -> f32 {
+        if self.first_cycle && self.phase < 0.5 {
+            return 0.0;
+        }
+        self.envelope
+            .level(self.waveform, self.phase, self.frequency_hz)
+    }
+
+    /// Whether the stimulus is in the bright half of its cycle.
+    pub fn show_white(&self) -> bool {
+        self.phase >= 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    /// Step `flicker` along `clock` in 1 ms frames for `secs`, counting the
+    /// flashes: the moments it turns bright.
+    fn count_flashes(flicker: &mut Flicker, clock: &ManualClock, secs: u32) -> u32 {
+        let mut flashes = 0;
+        let mut was_white = flicker.show_white();
+        for _ in 0..secs * 1000 {
+            clock.advance(Duration::from_millis(1));
+            flicker.update(clock.now());
+            if flicker.show_white() && !was_white {
+                flashes += 1;
+            }
+            was_white = flicker.show_white();
+        }
+        flashes
+    }
+
+    #[test]
+    fn flashes_once_per_cycle() {
+        let clock = ManualClock::default();
+        let mut flicker = Flicker::new(10.0);
+        flicker.reset(clock.now());
+        assert_eq!(count_flashes(&mut flicker, &clock, 1), 10);
+        assert_eq!(count_flashes(&mut flicker, &clock, 2), 20);
+    }
+
+    #[test]
+    fn frequency_change_keeps_the_phase() {
+        let clock = ManualClock::default();
+        let mut flicker = Flicker::new(10.0);
+        flicker.reset(clock.now());
+        assert_eq!(count_flashes(&mut flicker, &clock, 1), 10);
+        flicker.set_frequency(20.0);
+        assert_eq!(count_flashes(&mut flicker, &clock, 1), 20);
+    }
+
+    #[test]
+    fn starts_dark() {
+        let clock = ManualClock::default();
+        let mut flicker = Flicker::new(10.0);
+        flicker.reset(clock.now());
+        clock.advance(Duration::from_millis(40));
+        flicker.update(clock.now());
+        assert_eq!(flicker.level(), 0.0);
+        assert!(!flicker.show_white());
+        clock.advance(Duration::from_millis(20));
+        flicker.update(clock.now());
+        assert!(flicker.show_white());
+    }
+}
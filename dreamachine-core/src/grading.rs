@@ -0,0 +1,97 @@
+//! Master colour adjustments applied to everything a mode draws.
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+/// Colour temperature of plain white; tinting to this changes nothing.
+pub const NEUTRAL_KELVIN: f32 = 6500.0;
+
+/// Global brightness, contrast and gamma, so intensity can be dialled to
+/// comfort without touching each mode's colours.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Grading {
+    /// Output multiplier, `0..=1`.
+    pub brightness: f32,
+    /// Scale around mid-grey; 1 leaves colours alone.
+    pub contrast: f32,
+    pub gamma: f32,
+    /// Warm the output towards `kelvin`, like a night-light filter.
+    pub warm_tint: bool,
+    pub kelvin: f32,
+}
+
+impl Default for Grading {
+    fn default() -> Self {
+        Self {
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            warm_tint: false,
+            kelvin: NEUTRAL_KELVIN,
+        }
+    }
+}
+
+impl Grading {
+    pub fn is_identity(&self) -> bool {
+        self.brightness == 1.0
+            && self.contrast == 1.0
+            && self.gamma == 1.0
+            && (!self.warm_tint || self.kelvin >= NEUTRAL_KELVIN)
+    }
+
+    /// Per-channel gains of the warm tint; all ones without it.
+    pub fn tint(&self) -> [f32; 3] {
+        if self.warm_tint {
+            kelvin_tint(self.kelvin)
+        } else {
+            [1.0; 3]
+        }
+    }
+
+    pub fn apply(&self, color: Color32) -> Color32 {
+        let [r, g, b, a] = color.to_array();
+        // colours are premultiplied; grade the underlying colour, not the
+        // alpha-scaled one (zero alpha means additive, so use it as-is)
+        let scale = if a == 0 { 255.0 } else { a as f32 };
+        let tint = self.tint();
+        let grade = |c: u8, tint: f32| {
+            let v = c as f32 / scale;
+            let v = ((v - 0.5) * self.contrast + 0.5).clamp(0.0, 1.0);
+            let v = v.powf(1.0 / self.gamma) * self.brightness * tint;
+            (v * scale).round().clamp(0.0, 255.0) as u8
+        };
+        Color32::from_rgba_premultiplied(grade(r, tint[0]), grade(g, tint[1]), grade(b, tint[2]), a)
+    }
+}
+
+/// Per-channel gains that turn white into the colour of a black body at
+/// `kelvin`, relative to [`NEUTRAL_KELVIN`]. Uses Tanner Helland's fit.
+fn kelvin_tint(kelvin: f32) -> [f32; 3] {
+    let white = black_body(NEUTRAL_KELVIN);
+    let warm = black_body(kelvin.min(NEUTRAL_KELVIN));
+    [0, 1, 2].map(|i| (warm[i] / white[i]).min(1.0))
+}
+
+fn black_body(kelvin: f32) -> [f32; 3] {
+    let t = kelvin / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    [r, g, b].map(|c| c.clamp(0.0, 255.0))
+}
@@ -0,0 +1,405 @@
+//! Log of past sessions, one JSON record per line, and statistics over it.
+//!
+//! Each record keeps what ran as a timeline of segments, a new one whenever
+//! the mode or the frequency changes.
+//!
+//! The history can be exported for analysis elsewhere: as JSON, the records
+//! as they are; as CSV, one row per session plus a second file with one row
+//! per timeline segment, joined on the session number.
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::program::is_json;
+
+/// Frequency change that starts a new segment.
+const FREQUENCY_STEP_HZ: f32 = 0.1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub mode: String,
+    pub frequency_hz: f32,
+    pub secs: f32,
+}
+
+/// How a session went, in the user's words.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Journal {
+    pub notes: String,
+    /// 1–5, `None` if not rated.
+    pub relaxation: Option<u8>,
+    /// Vividness of the imagery, 1–5.
+    pub imagery: Option<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub started: DateTime<Local>,
+    pub duration_secs: f32,
+    /// Name of the program that ran, if any.
+    #[serde(default)]
+    pub program: Option<String>,
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub journal: Option<Journal>,
+}
+
+impl SessionRecord {
+    /// The session's length; zero if `duration_secs` isn't one.
+    pub fn duration(&self) -> Duration {
+        Duration::try_from_secs_f32(self.duration_secs).unwrap_or_default()
+    }
+
+    /// Whether every length in the record is one a `Duration` can hold.
+    fn is_valid(&self) -> bool {
+        let fits = |secs: f32| Duration::try_from_secs_f32(secs).is_ok();
+        fits(self.duration_secs) && self.segments.iter().all(|s| fits(s.secs))
+    }
+}
+
+/// Builds a record while a session runs.
+pub struct Recorder {
+    pub program: Option<String>,
+    started: DateTime<Local>,
+    segments: Vec<Segment>,
+    last: Option<Instant>,
+}
+
+impl Recorder {
+    pub fn new(started: DateTime<Local>, program: Option<String>) -> Self {
+        Self {
+            program,
+            started,
+            segments: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// Count the time since the previous call towards `mode` at
+    /// `frequency_hz`.
+    pub fn track(&mut self, now: Instant, mode: &str, frequency_hz: f32) {
+        let secs = self.last.map_or(0.0, |last| {
+            now.saturating_duration_since(last).as_secs_f32()
+        });
+        self.last = Some(now);
+        match self.segments.last_mut() {
+            Some(segment)
+                if segment.mode == mode
+                    && (segment.frequency_hz - frequency_hz).abs() < FREQUENCY_STEP_HZ =>
+            {
+                segment.secs += secs;
+            }
+            _ => self.segments.push(Segment {
+                mode: mode.to_owned(),
+                frequency_hz,
+                secs,
+            }),
+        }
+    }
+
+    pub fn finish(self) -> SessionRecord {
+        SessionRecord {
+            started: self.started,
+            duration_secs: self.segments.iter().map(|s| s.secs).sum(),
+            program: self.program,
+            segments: self.segments,
+            journal: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    /// Oldest first.
+    pub records: Vec<SessionRecord>,
+    // lines that didn't parse or hold impossible lengths, kept so that
+    // saving doesn't lose them
+    unparsed: Vec<String>,
+}
+
+impl History {
+    /// Read the log at `path`. A missing file is an empty history, and lines
+    /// that don't parse or have negative or overflowing lengths are skipped
+    /// so one bad record doesn't hide the rest.
+    /// [`History::save`] writes them back as they were.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut history = Self::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<SessionRecord>(line) {
+                Ok(record) if record.is_valid() => history.records.push(record),
+                _ => history.unparsed.push(line.to_owned()),
+            }
+        }
+        Ok(history)
+    }
+
+    /// Add `record` to the end of the log at `path` and to this history.
+    pub fn append(&mut self, path: &Path, record: SessionRecord) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Rewrite the log at `path`, after records were edited. Lines that
+    /// didn't parse go first, untouched. The new log is written beside the
+    /// old one and then moved over it, so a failed write leaves the old one
+    /// whole.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut text = String::new();
+        for line in &self.unparsed {
+            text += line;
+            text.push('\n');
+        }
+        for record in &self.records {
+            text += &serde_json::to_string(record)?;
+            text.push('\n');
+        }
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        fs::write(&temp, text)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// Write the history as JSON, or CSV unless `path` ends in `.json`.
+    /// Returns the files written.
+    pub fn export(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        if is_json(path) {
+            fs::write(path, serde_json::to_string_pretty(&self.records)?)?;
+            return Ok(vec![path.to_owned()]);
+        }
+        let timeline_path = timeline_path(path);
+        let mut sessions =
+            String::from("session,started,duration_secs,program,relaxation,imagery,notes\n");
+        let mut timeline = String::from("session,offset_secs,secs,mode,frequency_hz\n");
+        for (i, record) in self.records.iter().enumerate() {
+            let n = i + 1;
+            let journal = record.journal.clone().unwrap_or_default();
+            let rating = |r: Option<u8>| r.map(|r| r.to_string()).unwrap_or_default();
+            sessions += &format!(
+                "{n},{},{:.1},{},{},{},{}\n",
+                record.started.to_rfc3339(),
+                record.duration_secs,
+                csv_field(record.program.as_deref().unwrap_or_default()),
+                rating(journal.relaxation),
+                rating(journal.imagery),
+                csv_field(&journal.notes),
+            );
+            let mut offset = 0.0;
+            for segment in &record.segments {
+                timeline += &format!(
+                    "{n},{offset:.1},{:.1},{},{:.2}\n",
+                    segment.secs,
+                    csv_field(&segment.mode),
+                    segment.frequency_hz,
+                );
+                offset += segment.secs;
+            }
+        }
+        fs::write(path, sessions)?;
+        fs::write(&timeline_path, timeline)?;
+        Ok(vec![path.to_owned(), timeline_path])
+    }
+
+    pub fn stats(&self, today: NaiveDate, weeks: usize) -> Stats {
+        Stats::new(&self.records, today, weeks)
+    }
+}
+
+/// Summary for the statistics view.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub sessions: usize,
+    pub total: Duration,
+    /// Monday and time of each of the last few weeks, this one last.
+    pub weeks: Vec<(NaiveDate, Duration)>,
+    /// Time per mode, most used first.
+    pub modes: Vec<(String, Duration)>,
+    /// Time per frequency rounded to the nearest hertz, most used first.
+    pub frequencies: Vec<(u32, Duration)>,
+    /// Days in a row with a session, up to today. A streak still counts
+    /// until a day without a session has fully passed.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+impl Stats {
+    pub fn new(records: &[SessionRecord], today: NaiveDate, weeks: usize) -> Self {
+        let this_monday = monday(today);
+        let mut week_times: Vec<(NaiveDate, Duration)> = (0..weeks as u64)
+            .rev()
+            .filter_map(|back| this_monday.checked_sub_days(Days::new(7 * back)))
+            .map(|monday| (monday, Duration::ZERO))
+            .collect();
+        let mut modes: HashMap<&str, Duration> = HashMap::new();
+        let mut frequencies: HashMap<u32, Duration> = HashMap::new();
+        let mut days = BTreeSet::new();
+        let mut total = Duration::ZERO;
+
+        for record in records {
+            let day = record.started.date_naive();
+            days.insert(day);
+            total = total.saturating_add(record.duration());
+            let week = monday(day);
+            if let Some((_, time)) = week_times.iter_mut().find(|(m, _)| *m == week) {
+                *time = time.saturating_add(record.duration());
+            }
+            for segment in &record.segments {
+                let time = Duration::try_from_secs_f32(segment.secs).unwrap_or_default();
+                let mode = modes.entry(&segment.mode).or_default();
+                *mode = mode.saturating_add(time);
+                let hz = frequencies
+                    .entry(segment.frequency_hz.round() as u32)
+                    .or_default();
+                *hz = hz.saturating_add(time);
+            }
+        }
+
+        let mut modes: Vec<_> = modes
+            .into_iter()
+            .map(|(mode, time)| (mode.to_owned(), time))
+            .collect();
+        modes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let mut frequencies: Vec<_> = frequencies.into_iter().collect();
+        frequencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Self {
+            sessions: records.len(),
+            total,
+            weeks: week_times,
+            modes,
+            frequencies,
+            current_streak: current_streak(&days, today),
+            longest_streak: longest_streak(&days),
+        }
+    }
+}
+
+/// `sessions.csv` → `sessions-timeline.csv`, next to it.
+fn timeline_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}-timeline.csv"))
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn monday(day: NaiveDate) -> NaiveDate {
+    let back = day.weekday().num_days_from_monday();
+    day.checked_sub_days(Days::new(back as u64)).unwrap_or(day)
+}
+
+fn current_streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> u32 {
+    // today may not have had its session yet
+    let mut day = if days.contains(&today) {
+        Some(today)
+    } else {
+        today.pred_opt()
+    };
+    let mut streak = 0;
+    while let Some(d) = day.filter(|d| days.contains(d)) {
+        streak += 1;
+        day = d.pred_opt();
+    }
+    streak
+}
+
+fn longest_streak(days: &BTreeSet<NaiveDate>) -> u32 {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = if previous.and_then(|p| p.succ_opt()) == Some(day) {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn saving_keeps_bad_lines() {
+        let dir = env::temp_dir().join(format!("dreamachine-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.jsonl");
+        let good = r#"{"started":"2024-05-01T20:00:00+02:00","duration_secs":600.0,"segments":[{"mode":"Flash","frequency_hz":10.0,"secs":600.0}]}"#;
+        let garbled = r#"{"started":"2024-05-02T20:00"#;
+        let negative =
+            r#"{"started":"2024-05-03T20:00:00+02:00","duration_secs":-5.0,"segments":[]}"#;
+        let huge = r#"{"started":"2024-05-04T20:00:00+02:00","duration_secs":60.0,"segments":[{"mode":"Flash","frequency_hz":10.0,"secs":1e30}]}"#;
+        fs::write(&path, [good, garbled, negative, huge].join("\n")).unwrap();
+
+        let mut history = History::load(&path).unwrap();
+        assert_eq!(history.records.len(), 1);
+        assert_eq!(history.records[0].duration(), Duration::from_secs(600));
+        history.records[0].journal = Some(Journal {
+            notes: "calm".into(),
+            ..Journal::default()
+        });
+        history.save(&path).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[..3], [garbled, negative, huge]);
+        let saved = History::load(&path).unwrap();
+        assert_eq!(saved.records, history.records);
+        // the temporary file was moved over the log
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stats_survive_overflowing_lengths() {
+        let record = |secs: f32| SessionRecord {
+            started: Local::now(),
+            duration_secs: secs,
+            program: None,
+            segments: vec![Segment {
+                mode: "Flash".into(),
+                frequency_hz: 10.0,
+                secs,
+            }],
+            journal: None,
+        };
+        let records = [record(f32::MAX), record(f32::MAX), record(-1.0)];
+        let stats = Stats::new(&records, Local::now().date_naive(), 4);
+        assert_eq!(stats.total, Duration::ZERO);
+        let records = [record(1e19), record(1e19)];
+        let stats = Stats::new(&records, Local::now().date_naive(), 4);
+        assert_eq!(stats.total, Duration::MAX);
+    }
+}
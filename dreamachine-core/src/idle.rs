@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant};
+
+/// Fades out and stops a session that nobody seems to be attending: one
+/// that has run past its planned length by the grace period with no input
+/// for as long.
+pub struct IdleStop {
+    /// `None` never stops.
+    pub grace: Option<Duration>,
+    pub fade: Duration,
+    last_input: Option<Instant>,
+    fading_since: Option<Instant>,
+}
+
+impl Default for IdleStop {
+    fn default() -> Self {
+        Self {
+            grace: Some(Duration::from_secs(10 * 60)),
+            fade: Duration::from_secs(30),
+            last_input: None,
+            fading_since: None,
+        }
+    }
+}
+
+impl IdleStop {
+    /// Note user input, cancelling a fade in progress.
+    pub fn input(&mut self, now: Instant) {
+        self.last_input = Some(now);
+        self.fading_since = None;
+    }
+
+    /// Start over for a new session.
+    pub fn reset(&mut self) {
+        self.fading_since = None;
+    }
+
+    /// Output level in `0..=1` at `elapsed` into a session planned to last
+    /// `planned`. Reaches 0 when the session should stop.
+    pub fn update(&mut self, now: Instant, elapsed: Duration, planned: Duration) -> f32 {
+        let Some(grace) = self.grace else {
+            self.fading_since = None;
+            return 1.0;
+        };
+        let idle = self
+            .last_input
+            .map_or(elapsed, |last| now.saturating_duration_since(last));
+        if self.fading_since.is_none() && elapsed >= planned + grace && idle >= grace {
+            self.fading_since = Some(now);
+        }
+        let Some(since) = self.fading_since else {
+            return 1.0;
+        };
+        if self.fade.is_zero() {
+            return 0.0;
+        }
+        let faded = now.saturating_duration_since(since).as_secs_f32() / self.fade.as_secs_f32();
+        1.0 - faded.min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn fades_out_when_nobody_is_there() {
+        let clock = ManualClock::default();
+        let start = clock.now();
+        let planned = 120 * SEC;
+        let mut idle = IdleStop {
+            grace: Some(60 * SEC),
+            fade: 30 * SEC,
+            ..IdleStop::default()
+        };
+        let level = |idle: &mut IdleStop| {
+            let now = clock.now();
+            idle.update(now, now - start, planned)
+        };
+        clock.advance(179 * SEC);
+        assert_eq!(level(&mut idle), 1.0);
+        clock.advance(SEC);
+        assert_eq!(level(&mut idle), 1.0);
+        clock.advance(15 * SEC);
+        assert_eq!(level(&mut idle), 0.5);
+
+        // input cancels the fade until another grace period has passed
+        idle.input(clock.now());
+        assert_eq!(level(&mut idle), 1.0);
+        clock.advance(59 * SEC);
+        assert_eq!(level(&mut idle), 1.0);
+        clock.advance(SEC);
+        assert_eq!(level(&mut idle), 1.0);
+        clock.advance(30 * SEC);
+        assert_eq!(level(&mut idle), 0.0);
+    }
+
+    #[test]
+    fn never_stops_without_a_grace_period() {
+        let clock = ManualClock::default();
+        let start = clock.now();
+        let mut idle = IdleStop {
+            grace: None,
+            ..IdleStop::default()
+        };
+        clock.advance(24 * 60 * 60 * SEC);
+        assert_eq!(idle.update(clock.now(), clock.now() - start, SEC), 1.0);
+    }
+}
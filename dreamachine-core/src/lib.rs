@@ -0,0 +1,55 @@
+//! Dreamachine entrainment engine.
+//!
+//! Flicker timing, sessions and programs, audio signal generation and the
+//! visual modes live here so that frontends other than the egui desktop app
+//! can drive the same stimulus.
+
+pub mod animation;
+pub mod audio;
+pub mod clock;
+pub mod compositor;
+pub mod envelope;
+pub mod error;
+pub mod exposure;
+pub mod fixation;
+pub mod flicker;
+pub mod grading;
+pub mod history;
+pub mod idle;
+pub mod lights;
+pub mod mask;
+pub mod midi;
+pub mod modes;
+pub mod presets;
+pub mod program;
+pub mod refresh;
+pub mod remote;
+pub mod render;
+pub mod risk;
+pub mod rng;
+pub mod session;
+pub mod shuffle;
+pub mod tap;
+pub mod timing;
+pub mod waveform;
+
+pub use animation::{Cycle, LoopFormat};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use compositor::Compositor;
+pub use envelope::Envelope;
+pub use error::{Error, Result};
+pub use exposure::Exposure;
+pub use fixation::Fixation;
+pub use flicker::Flicker;
+pub use grading::Grading;
+pub use history::{History, Journal, Recorder, SessionRecord};
+pub use idle::IdleStop;
+pub use mask::Mask;
+pub use modes::{ModeParams, VisualMode};
+pub use program::{Program, Stage, Transition};
+pub use render::OfflineRenderer;
+pub use session::SessionTimer;
+pub use shuffle::Shuffle;
+pub use tap::TapTempo;
+pub use timing::{FrameClock, Timing};
+pub use waveform::Waveform;
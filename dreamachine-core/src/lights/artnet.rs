@@ -0,0 +1,109 @@
+//! Art-Net, DMX512 over UDP, for stage fixtures and LED panels.
+
+use serde::{Deserialize, Serialize};
+
+use super::Sample;
+
+/// UDP port Art-Net nodes listen on.
+pub const PORT: u16 = 6454;
+/// Channels in a DMX universe.
+pub const UNIVERSE_SIZE: usize = 512;
+
+/// The channels one fixture takes, in order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Layout {
+    /// A single intensity channel, as on plain strobes and dimmers.
+    Dimmer,
+    #[default]
+    Rgb,
+    /// Master dimmer, then red, green and blue.
+    DimmerRgb,
+    /// Red, green, blue and white, the white following the level.
+    Rgbw,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 4] = [Layout::Dimmer, Layout::Rgb, Layout::DimmerRgb, Layout::Rgbw];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Dimmer => "Dimmer",
+            Layout::Rgb => "RGB",
+            Layout::DimmerRgb => "Dimmer + RGB",
+            Layout::Rgbw => "RGBW",
+        }
+    }
+
+    pub fn channels(self) -> usize {
+        match self {
+            Layout::Dimmer => 1,
+            Layout::Rgb => 3,
+            Layout::DimmerRgb | Layout::Rgbw => 4,
+        }
+    }
+
+    fn values(self, sample: &Sample) -> Vec<u8> {
+        let level = (sample.level.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let [r, g, b] = sample.rgb();
+        match self {
+            Layout::Dimmer => vec![level],
+            Layout::Rgb => vec![r, g, b],
+            Layout::DimmerRgb => vec![255, r, g, b],
+            Layout::Rgbw => vec![r, g, b, level],
+        }
+    }
+}
+
+/// Where the fixtures sit in a universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Patch {
+    pub layout: Layout,
+    /// DMX address of the first fixture, from 1.
+    pub address: u16,
+    /// Identical fixtures patched one after another.
+    pub fixtures: u16,
+}
+
+impl Default for Patch {
+    fn default() -> Self {
+        Self {
+            layout: Layout::default(),
+            address: 1,
+            fixtures: 1,
+        }
+    }
+}
+
+impl Patch {
+    /// The universe's channel values; anything past 512 is dropped.
+    pub fn universe(&self, sample: &Sample) -> Vec<u8> {
+        let values = self.layout.values(sample);
+        let start = (self.address.max(1) - 1) as usize;
+        let end = (start + values.len() * self.fixtures as usize).min(UNIVERSE_SIZE);
+        // DMX frames can be short, but Art-Net wants an even length
+        let mut data = vec![0; (end.max(2) + 1) & !1];
+        for (channel, value) in (start..end).zip(values.iter().cycle()) {
+            data[channel] = *value;
+        }
+        data
+    }
+}
+
+/// An ArtDmx packet carrying `data` to `universe` (15 bits: net, sub-net
+/// and universe). `sequence` should count up from 1 so nodes can reorder;
+/// 0 turns reordering off.
+pub fn dmx_packet(universe: u16, sequence: u8, data: &[u8]) -> Vec<u8> {
+    let data = &data[..data.len().min(UNIVERSE_SIZE)];
+    let mut packet = Vec::with_capacity(18 + data.len());
+    packet.extend_from_slice(b"Art-Net\0");
+    packet.extend_from_slice(&0x5000u16.to_le_bytes()); // OpDmx
+    packet.extend_from_slice(&14u16.to_be_bytes()); // protocol version
+    packet.push(sequence);
+    packet.push(0); // physical port
+    packet.push((universe & 0xff) as u8);
+    packet.push(((universe >> 8) & 0x7f) as u8);
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
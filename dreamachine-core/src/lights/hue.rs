@@ -0,0 +1,29 @@
+//! The Philips Hue Entertainment streaming format (API v2), sent over DTLS
+//! to the bridge.
+
+/// UDP port of the bridge's streaming endpoint.
+pub const PORT: u16 = 2100;
+/// Most channels a bridge takes in one message.
+pub const MAX_CHANNELS: usize = 20;
+
+/// A message setting every channel in `channels` of the entertainment
+/// configuration `area_id` to `rgb`. `sequence` is informational.
+pub fn message(area_id: &str, sequence: u8, channels: &[u8], rgb: [u8; 3]) -> Vec<u8> {
+    let channels = &channels[..channels.len().min(MAX_CHANNELS)];
+    let mut message = Vec::with_capacity(52 + channels.len() * 7);
+    message.extend_from_slice(b"HueStream");
+    message.extend_from_slice(&[2, 0]); // version 2.0
+    message.push(sequence);
+    message.extend_from_slice(&[0, 0]); // reserved
+    message.push(0); // RGB colour space
+    message.push(0); // reserved
+    message.extend_from_slice(area_id.as_bytes());
+    for &channel in channels {
+        message.push(channel);
+        for c in rgb {
+            // 8 bits stretched to the full 16
+            message.extend_from_slice(&(c as u16 * 257).to_be_bytes());
+        }
+    }
+    message
+}
@@ -0,0 +1,91 @@
+//! The LIFX LAN protocol, enough to find bulbs and set their colour.
+
+/// UDP port LIFX bulbs listen on.
+pub const PORT: u16 = 56700;
+
+const HEADER: usize = 36;
+const GET_LABEL: u16 = 23;
+const STATE_LABEL: u16 = 25;
+const SET_COLOR: u16 = 102;
+/// Colour temperature for whites; ignored for saturated colours.
+const KELVIN: u16 = 3500;
+
+/// Hue, saturation, brightness and kelvin, as LIFX takes a colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hsbk {
+    pub hue: u16,
+    pub saturation: u16,
+    pub brightness: u16,
+    pub kelvin: u16,
+}
+
+impl Hsbk {
+    /// `rgb` with its brightness scaled by `cap`, `0..=1`.
+    pub fn from_rgb(rgb: [u8; 3], cap: f32) -> Self {
+        let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / chroma + 2.0
+        } else {
+            (r - g) / chroma + 4.0
+        } / 6.0;
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+        let scale = |v: f32| (v.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        Self {
+            hue: scale(hue),
+            saturation: scale(saturation),
+            brightness: scale(max * cap),
+            kelvin: KELVIN,
+        }
+    }
+}
+
+/// A message to every bulb that receives it. `source` identifies this
+/// client in replies.
+fn message(kind: u16, source: u32, sequence: u8, payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(HEADER + payload.len());
+    message.extend_from_slice(&((HEADER + payload.len()) as u16).to_le_bytes());
+    // protocol 1024, addressable, tagged: for all targets
+    message.extend_from_slice(&(1024u16 | 1 << 12 | 1 << 13).to_le_bytes());
+    message.extend_from_slice(&source.to_le_bytes());
+    message.extend_from_slice(&[0; 8]); // target: all
+    message.extend_from_slice(&[0; 6]);
+    message.push(0); // no acknowledgement or response wanted
+    message.push(sequence);
+    message.extend_from_slice(&[0; 8]);
+    message.extend_from_slice(&kind.to_le_bytes());
+    message.extend_from_slice(&[0; 2]);
+    message.extend_from_slice(payload);
+    message
+}
+
+/// SetColor, taking effect at once.
+pub fn set_color(source: u32, sequence: u8, color: Hsbk) -> Vec<u8> {
+    let mut payload = vec![0];
+    for value in [color.hue, color.saturation, color.brightness, color.kelvin] {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    payload.extend_from_slice(&0u32.to_le_bytes()); // transition time
+    message(SET_COLOR, source, sequence, &payload)
+}
+
+/// GetLabel; broadcast, every bulb answers with [`parse_label`]'s packet.
+pub fn get_label(source: u32) -> Vec<u8> {
+    message(GET_LABEL, source, 0, &[])
+}
+
+/// The label in a StateLabel reply, or `None` for any other packet.
+pub fn parse_label(packet: &[u8]) -> Option<String> {
+    let kind = u16::from_le_bytes(packet.get(32..34)?.try_into().ok()?);
+    if kind != STATE_LABEL {
+        return None;
+    }
+    let label = packet.get(HEADER..HEADER + 32)?;
+    let end = label.iter().position(|&b| b == 0).unwrap_or(label.len());
+    Some(String::from_utf8_lossy(&label[..end]).into_owned())
+}
@@ -0,0 +1,140 @@
+//! The stimulus reduced to one colour, for lights other than the screen.
+//!
+//! The frontend keeps a [`LightFeed`] up to date and each output samples it
+//! on its own schedule. The submodules encode the wire formats; sockets and
+//! ports are left to the frontend.
+
+pub mod artnet;
+pub mod hue;
+pub mod lifx;
+pub mod serial;
+pub mod wled;
+
+use std::time::{Duration, Instant};
+
+use egui::Color32;
+
+use crate::flicker::Flicker;
+use crate::grading::Grading;
+use crate::modes;
+
+/// Points per cycle averaged for a steady level.
+const AVERAGE_POINTS: u32 = 32;
+
+/// What a light should show at one moment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// Graded stimulus colour, faded with the session.
+    pub color: Color32,
+    /// Brightness in `0..=1`, for lights with a single channel.
+    pub level: f32,
+}
+
+impl Sample {
+    pub const OFF: Sample = Sample {
+        color: Color32::BLACK,
+        level: 0.0,
+    };
+
+    pub fn rgb(&self) -> [u8; 3] {
+        [self.color.r(), self.color.g(), self.color.b()]
+    }
+}
+
+/// The state of the stimulus, enough to predict it a little way ahead.
+#[derive(Clone)]
+pub struct LightFeed {
+    pub flicker: Flicker,
+    /// False before the first flash and after the session; lights go dark.
+    pub running: bool,
+    pub on_color: Color32,
+    pub off_color: Color32,
+    pub grading: Grading,
+    /// Session fade in and out, `0..=1`.
+    pub fade: f32,
+}
+
+impl LightFeed {
+    pub fn new(flicker: Flicker) -> Self {
+        Self {
+            flicker,
+            running: false,
+            on_color: Color32::WHITE,
+            off_color: Color32::BLACK,
+            grading: Grading::default(),
+            fade: 1.0,
+        }
+    }
+
+    /// The light at `when`, which may be ahead of the last update.
+    pub fn sample(&self, when: Instant) -> Sample {
+        if !self.running {
+            return Sample::OFF;
+        }
+        self.light(self.flicker.at(when).level())
+    }
+
+    /// Whether a light updated every `interval` can show the flicker. At
+    /// half the frequency's rate or slower it would alias to a slower,
+    /// false flicker.
+    pub fn can_follow(&self, interval: Duration) -> bool {
+        self.flicker.frequency_hz() * 2.0 * interval.as_secs_f32() < 1.0
+    }
+
+    /// The light at `when` for a light updated every `interval`: the
+    /// flicker if it can follow it, otherwise the average level over a
+    /// cycle, held steady.
+    pub fn sample_every(&self, when: Instant, interval: Duration) -> Sample {
+        if !self.running || self.can_follow(interval) {
+            return self.sample(when);
+        }
+        let period = 1.0 / self.flicker.frequency_hz();
+        let total: f32 = (0..AVERAGE_POINTS)
+            .map(|i| {
+                let offset = period * i as f32 / AVERAGE_POINTS as f32;
+                self.flicker
+                    .at(when + Duration::from_secs_f32(offset))
+                    .level()
+            })
+            .sum();
+        self.light(total / AVERAGE_POINTS as f32)
+    }
+
+    /// The graded, faded light at flicker `level`.
+    fn light(&self, level: f32) -> Sample {
+        let color = self
+            .grading
+            .apply(modes::mix(self.off_color, self.on_color, level));
+        let fade = self.fade.clamp(0.0, 1.0);
+        let scale = |c: u8| (c as f32 * fade).round() as u8;
+        Sample {
+            color: Color32::from_rgb(scale(color.r()), scale(color.g()), scale(color.b())),
+            level: level * self.grading.brightness * fade,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_lights_hold_the_average() {
+        let mut feed = LightFeed::new(Flicker::new(10.0));
+        feed.running = true;
+        let start = Instant::now();
+        feed.flicker.reset(start);
+        // past the dark first half-cycle
+        let when = start + Duration::from_secs(1);
+        let fast = Duration::from_millis(20);
+        let slow = Duration::from_millis(50);
+        assert!(feed.can_follow(fast));
+        assert!(!feed.can_follow(slow));
+        assert_eq!(feed.sample_every(when, fast), feed.sample(when));
+
+        let steady = feed.sample_every(when, slow);
+        let later = feed.sample_every(when + Duration::from_millis(37), slow);
+        assert!((steady.level - later.level).abs() < 0.05);
+        assert!(steady.level > 0.2 && steady.level < 0.8, "{}", steady.level);
+    }
+}
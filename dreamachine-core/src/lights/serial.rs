@@ -0,0 +1,76 @@
+//! Frames for home-made LED goggles on a serial port, such as an Arduino
+//! or ESP32 driving a LED behind each eye.
+//!
+//! Each frame is the sync byte `0xFF` followed by the values for one
+//! [`Format`]. Values run `0..=254`, so `0xFF` only ever starts a frame and
+//! a receiver that joins mid-stream finds its place at the next one:
+//!
+//! - [`Format::OnOff`]: one byte, `0` or `254`.
+//! - [`Format::Level`]: one byte, the brightness, for PWM.
+//! - [`Format::Rgb`]: three bytes, red, green and blue.
+//!
+//! The port is 8 data bits, no parity, one stop bit. Frames come at a steady
+//! rate; the receiver shows each as soon as it's complete. A minimal
+//! Arduino receiver for `Level`:
+//!
+//! ```text
+//! int value = -1;
+//! void loop() {
+//!   if (!Serial.available()) return;
+//!   int b = Serial.read();
+//!   if (b == 0xFF) value = 0;
+//!   else if (value == 0) { analogWrite(LED_PIN, b); value = -1; }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use super::Sample;
+
+/// Starts every frame; never sent as a value.
+pub const SYNC: u8 = 0xFF;
+/// The largest value, standing in for full brightness.
+const MAX: u8 = SYNC - 1;
+
+/// What each frame carries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Format {
+    /// On past half brightness, for LEDs switched straight from a pin.
+    OnOff,
+    #[default]
+    Level,
+    Rgb,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::OnOff, Format::Level, Format::Rgb];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::OnOff => "On/Off",
+            Format::Level => "Level (PWM)",
+            Format::Rgb => "RGB",
+        }
+    }
+
+    /// Bytes in a frame, with the sync byte.
+    pub fn frame_len(self) -> usize {
+        match self {
+            Format::OnOff | Format::Level => 2,
+            Format::Rgb => 4,
+        }
+    }
+
+    pub fn frame(self, sample: &Sample) -> Vec<u8> {
+        let level = sample.level.clamp(0.0, 1.0);
+        let scale = |c: u8| (c as u16 * MAX as u16 / 255) as u8;
+        match self {
+            Format::OnOff => vec![SYNC, if level > 0.5 { MAX } else { 0 }],
+            Format::Level => vec![SYNC, (level * MAX as f32).round() as u8],
+            Format::Rgb => {
+                let [r, g, b] = sample.rgb();
+                vec![SYNC, scale(r), scale(g), scale(b)]
+            }
+        }
+    }
+}
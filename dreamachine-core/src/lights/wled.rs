@@ -0,0 +1,32 @@
+//! WLED's UDP realtime protocol, for addressable LED strips.
+
+/// UDP port WLED listens on for realtime data.
+pub const PORT: u16 = 21324;
+/// Most LEDs in one DRGB packet.
+const DRGB_MAX: usize = 490;
+/// Most LEDs in one DNRGB packet.
+const DNRGB_MAX: usize = 489;
+
+const DRGB: u8 = 2;
+const DNRGB: u8 = 4;
+
+/// Packets setting `leds` LEDs to `rgb`. The controller goes back to its
+/// own effects `timeout_secs` after the last packet; 255 never does.
+pub fn packets(rgb: [u8; 3], leds: usize, timeout_secs: u8) -> Vec<Vec<u8>> {
+    if leds <= DRGB_MAX {
+        let mut packet = vec![DRGB, timeout_secs];
+        packet.extend(rgb.iter().copied().cycle().take(leds * 3));
+        return vec![packet];
+    }
+    // longer strips take several packets, each with its start index
+    (0..leds)
+        .step_by(DNRGB_MAX)
+        .map(|start| {
+            let count = DNRGB_MAX.min(leds - start);
+            let mut packet = vec![DNRGB, timeout_secs];
+            packet.extend_from_slice(&(start as u16).to_be_bytes());
+            packet.extend(rgb.iter().copied().cycle().take(count * 3));
+            packet
+        })
+        .collect()
+}
@@ -0,0 +1,184 @@
+//! Masks that attenuate the stimulus towards the edges or into a shape.
+
+use std::path::Path;
+
+use egui::{Color32, ColorImage, Painter, Pos2, Rect, TextureHandle, TextureOptions, Ui};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MaskShape {
+    Off,
+    Circle,
+    Rectangle,
+    Image,
+}
+
+impl MaskShape {
+    pub const ALL: [MaskShape; 4] = [
+        MaskShape::Off,
+        MaskShape::Circle,
+        MaskShape::Rectangle,
+        MaskShape::Image,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MaskShape::Off => "Off",
+            MaskShape::Circle => "Circular vignette",
+            MaskShape::Rectangle => "Rectangle",
+            MaskShape::Image => "Mask image",
+        }
+    }
+}
+
+/// Rows in a generated mask texture; columns follow the screen aspect.
+const MASK_ROWS: usize = 256;
+
+/// Darkening overlay painted over the modes. Generated masks are rendered
+/// into a small texture and stretched over the screen.
+pub struct Mask {
+    pub shape: MaskShape,
+    /// Circle radius, or rectangle half-size, as a fraction of the screen.
+    pub size: f32,
+    pub softness: f32,
+    image: Option<ColorImage>,
+    path: String,
+    error: Option<String>,
+    texture: Option<TextureHandle>,
+    // what the texture was built from: shape, size, softness and resolution
+    texture_for: Option<(MaskShape, f32, f32, [usize; 2])>,
+}
+
+impl Default for Mask {
+    fn default() -> Self {
+        Self {
+            shape: MaskShape::Off,
+            size: 0.8,
+            softness: 0.3,
+            image: None,
+            path: String::new(),
+            error: None,
+            texture: None,
+            texture_for: None,
+        }
+    }
+}
+
+impl Mask {
+    pub fn paint(&mut self, painter: &Painter, rect: Rect) {
+        if self.shape == MaskShape::Off || (self.shape == MaskShape::Image && self.image.is_none())
+        {
+            return;
+        }
+        let cols = ((MASK_ROWS as f32 * rect.aspect_ratio()).round() as usize).max(1);
+        let key = (self.shape, self.size, self.softness, [cols, MASK_ROWS]);
+        if self.texture.is_none() || self.texture_for != Some(key) {
+            let image = match self.shape {
+                MaskShape::Image => self.image.clone().unwrap_or_default(),
+                _ => self.generate([cols, MASK_ROWS]),
+            };
+            self.texture = Some(
+                painter
+                    .ctx()
+                    .load_texture("mask", image, TextureOptions::LINEAR),
+            );
+            self.texture_for = Some(key);
+        }
+        if let Some(texture) = &self.texture {
+            let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+            painter.image(texture.id(), rect, uv, Color32::WHITE);
+        }
+    }
+
+    /// Fraction of a screen with aspect ratio `aspect` that the mask lets
+    /// through.
+    pub fn coverage(&self, aspect: f32) -> f32 {
+        let generated;
+        let pixels = match (self.shape, &self.image) {
+            (MaskShape::Off, _) | (MaskShape::Image, None) => return 1.0,
+            (MaskShape::Image, Some(image)) => &image.pixels,
+            _ => {
+                generated = self.generate([((32.0 * aspect).round() as usize).max(1), 32]);
+                &generated.pixels
+            }
+        };
+        let blocked: f32 = pixels.iter().map(|p| p.a() as f32 / 255.0).sum();
+        1.0 - blocked / pixels.len().max(1) as f32
+    }
+
+    /// Black overlay whose alpha is 1 minus the mask's transmission.
+    fn generate(&self, [w, h]: [usize; 2]) -> ColorImage {
+        let aspect = w as f32 / h as f32;
+        let mut pixels = Vec::with_capacity(w * h);
+        for y in 0..h {
+            for x in 0..w {
+                // -1..1 across each axis
+                let u = (x as f32 + 0.5) / w as f32 * 2.0 - 1.0;
+                let v = (y as f32 + 0.5) / h as f32 * 2.0 - 1.0;
+                let distance = match self.shape {
+                    // round on screen: measure in units of half the height
+                    MaskShape::Circle => (u * aspect.max(1.0)).hypot(v * (1.0 / aspect).max(1.0)),
+                    _ => u.abs().max(v.abs()),
+                };
+                let t = ((distance - self.size) / self.softness.max(1e-3)).clamp(0.0, 1.0);
+                let smooth = t * t * (3.0 - 2.0 * t);
+                pixels.push(Color32::from_black_alpha((smooth * 255.0).round() as u8));
+            }
+        }
+        ColorImage::new([w, h], pixels)
+    }
+
+    fn load(&mut self, path: &Path) {
+        match image::open(path) {
+            Ok(img) => {
+                let luma = img.to_luma8();
+                let (w, h) = luma.dimensions();
+                // white lets the stimulus through, black blocks it
+                let pixels = luma
+                    .as_raw()
+                    .iter()
+                    .map(|&l| Color32::from_black_alpha(255 - l))
+                    .collect();
+                self.image = Some(ColorImage::new([w as usize, h as usize], pixels));
+                self.texture = None;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{}: {e}", path.display())),
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut Ui) {
+        for shape in MaskShape::ALL {
+            ui.radio_value(&mut self.shape, shape, shape.name());
+        }
+        match self.shape {
+            MaskShape::Off => {}
+            MaskShape::Circle | MaskShape::Rectangle => {
+                ui.add(egui::Slider::new(&mut self.size, 0.0..=1.5).text("Size"));
+                ui.add(egui::Slider::new(&mut self.softness, 0.0..=1.0).text("Softness"));
+            }
+            MaskShape::Image => {
+                ui.label("White passes the flicker, black blocks it.");
+                let dropped: Vec<_> = ui.ctx().input(|i| {
+                    i.raw
+                        .dropped_files
+                        .iter()
+                        .filter_map(|f| f.path.clone())
+                        .collect()
+                });
+                if let Some(path) = dropped.last() {
+                    self.load(path);
+                }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.path);
+                    if ui.button("Load").clicked() && !self.path.is_empty() {
+                        let path = self.path.clone();
+                        self.load(Path::new(&path));
+                    }
+                });
+                if let Some(error) = &self.error {
+                    ui.colored_label(Color32::LIGHT_RED, error.as_str());
+                }
+            }
+        }
+    }
+}
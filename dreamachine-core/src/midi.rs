@@ -0,0 +1,121 @@
+//! MIDI controller mapping: which knob, fader or pad drives which setting.
+//!
+//! Only the message parsing and the map live here; opening ports is left to
+//! the frontend.
+
+use serde::{Deserialize, Serialize};
+
+/// A physical control on a MIDI device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Control {
+    /// Control change, usually a knob or fader.
+    Cc { channel: u8, number: u8 },
+    /// A key or pad.
+    Note { channel: u8, number: u8 },
+}
+
+impl Control {
+    pub fn label(self) -> String {
+        match self {
+            Control::Cc { channel, number } => format!("CC {number} (ch {})", channel + 1),
+            Control::Note { channel, number } => format!("Note {number} (ch {})", channel + 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MidiAction {
+    /// Sweeps the allowed frequency range.
+    Frequency,
+    Brightness,
+    /// A knob picks a mode along its travel; a pad steps to the next one.
+    Mode,
+    StartStop,
+}
+
+impl MidiAction {
+    pub const ALL: [MidiAction; 4] = [
+        MidiAction::Frequency,
+        MidiAction::Brightness,
+        MidiAction::Mode,
+        MidiAction::StartStop,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MidiAction::Frequency => "Frequency",
+            MidiAction::Brightness => "Brightness",
+            MidiAction::Mode => "Mode",
+            MidiAction::StartStop => "Start/Stop",
+        }
+    }
+}
+
+/// A control moving to `value` in `0..=1`. Notes are velocity while held
+/// and 0 once released.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    pub control: Control,
+    pub value: f32,
+}
+
+impl Event {
+    /// Parse a raw message. Anything but control change and note on/off is
+    /// ignored.
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let [status, number, value, ..] = *message else {
+            return None;
+        };
+        let channel = status & 0x0f;
+        let value = (value & 0x7f) as f32 / 127.0;
+        let (control, value) = match status & 0xf0 {
+            0x80 => (Control::Note { channel, number }, 0.0),
+            0x90 => (Control::Note { channel, number }, value),
+            0xb0 => (Control::Cc { channel, number }, value),
+            _ => return None,
+        };
+        Some(Self { control, value })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub action: MidiAction,
+    pub control: Control,
+}
+
+/// The input port and what each of its controls does.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiMap {
+    /// Name of the input port to open; `None` leaves MIDI off.
+    pub port: Option<String>,
+    pub bindings: Vec<Binding>,
+}
+
+impl MidiMap {
+    pub fn action(&self, control: Control) -> Option<MidiAction> {
+        self.bindings
+            .iter()
+            .find(|b| b.control == control)
+            .map(|b| b.action)
+    }
+
+    pub fn control(&self, action: MidiAction) -> Option<Control> {
+        self.bindings
+            .iter()
+            .find(|b| b.action == action)
+            .map(|b| b.control)
+    }
+
+    /// Make `control` drive `action`, and only that.
+    pub fn bind(&mut self, action: MidiAction, control: Control) {
+        self.bindings
+            .retain(|b| b.action != action && b.control != control);
+        self.bindings.push(Binding { action, control });
+    }
+
+    pub fn unbind(&mut self, action: MidiAction) {
+        self.bindings.retain(|b| b.action != action);
+    }
+}
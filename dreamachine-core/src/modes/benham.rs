@@ -0,0 +1,69 @@
+use std::f32::consts::{FRAC_PI_4, PI, TAU};
+
+use egui::{Painter, Pos2, Rect, Shape, Stroke, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Benham's top: half the disk black, the other half white with short black
+/// arcs. Spinning it makes the arcs appear faintly coloured (Fechner colours).
+///
+/// Every point on the disk sees one dark/light alternation per revolution, so
+/// the disk turns at the session frequency.
+pub struct Benham {
+    reverse: bool,
+    arc_width: f32, // fraction of the disk radius
+}
+
+impl Default for Benham {
+    fn default() -> Self {
+        Self {
+            reverse: false,
+            arc_width: 0.03,
+        }
+    }
+}
+
+impl VisualMode for Benham {
+    fn name(&self) -> &'static str {
+        "Benham's Disk"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let center = rect.center();
+        let radius = rect.width().min(rect.height()) * 0.45;
+        let direction = if self.reverse { -1.0 } else { 1.0 };
+        let rotation = direction * (t * params.frequency_hz).fract() * TAU;
+        let polar = |r: f32, a: f32| center + Vec2::angled(rotation + a) * r;
+
+        painter.circle_filled(center, radius, params.on_color);
+
+        let steps = 64;
+        let half: Vec<Pos2> = (0..=steps)
+            .map(|i| polar(radius, PI + PI * i as f32 / steps as f32))
+            .chain(std::iter::once(center))
+            .collect();
+        painter.add(Shape::convex_polygon(half, params.off_color, Stroke::NONE));
+
+        // the white half holds four 45° sectors of three arcs each, stepping
+        // outwards from sector to sector
+        let stroke = Stroke::new(self.arc_width * radius, params.off_color);
+        for sector in 0..4 {
+            let start = sector as f32 * FRAC_PI_4;
+            for arc in 0..3 {
+                let r = radius * (0.25 + (sector * 3 + arc) as f32 * 0.06);
+                let points = (0..=16)
+                    .map(|i| polar(r, start + FRAC_PI_4 * i as f32 / 16.0))
+                    .collect();
+                painter.add(Shape::line(points, stroke));
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.reverse, "Reverse spin")
+            .on_hover_text("Reversing the spin reverses the order of the perceived colours");
+        ui.add(egui::Slider::new(&mut self.arc_width, 0.01..=0.05).text("Arc width"));
+    }
+}
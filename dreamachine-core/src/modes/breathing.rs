@@ -0,0 +1,104 @@
+use std::f32::consts::PI;
+
+use egui::{Align2, FontId, Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+
+/// Common breathing patterns: inhale, hold, exhale, hold (seconds).
+const CADENCES: [(&str, [f32; 4]); 3] = [
+    ("4-7-8", [4.0, 7.0, 8.0, 0.0]),
+    ("Box 4-4-4-4", [4.0, 4.0, 4.0, 4.0]),
+    ("Coherent 5-5", [5.0, 0.0, 5.0, 0.0]),
+];
+
+const PHASE_LABELS: [&str; 4] = ["Inhale", "Hold", "Exhale", "Hold"];
+
+/// A circle that grows on the inhale and shrinks on the exhale, for paced
+/// breathing. The background can flicker gently at the session frequency.
+pub struct Breathing {
+    cadence: [f32; 4],
+    flicker_depth: f32,
+    show_labels: bool,
+}
+
+impl Default for Breathing {
+    fn default() -> Self {
+        Self {
+            cadence: CADENCES[0].1,
+            flicker_depth: 0.0,
+            show_labels: true,
+        }
+    }
+}
+
+impl Breathing {
+    /// Which part of the breath we are in and how far through it.
+    fn breath(&self, t: f32) -> (usize, f32) {
+        let cycle: f32 = self.cadence.iter().sum();
+        if cycle <= 0.0 {
+            return (1, 0.0);
+        }
+        let mut at = t % cycle;
+        for (i, &secs) in self.cadence.iter().enumerate() {
+            if at < secs {
+                return (i, at / secs);
+            }
+            at -= secs;
+        }
+        (3, 1.0)
+    }
+}
+
+impl VisualMode for Breathing {
+    fn name(&self) -> &'static str {
+        "Breathing"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let background = params
+            .off_color
+            .lerp_to_gamma(params.on_color, self.flicker_depth * params.level * 0.5);
+        painter.rect_filled(rect, 0.0, background);
+
+        let (part, progress) = self.breath(t);
+        let eased = 0.5 - 0.5 * (PI * progress).cos();
+        let size = match part {
+            0 => eased,
+            1 => 1.0,
+            2 => 1.0 - eased,
+            _ => 0.0,
+        };
+        let max_radius = rect.width().min(rect.height()) * 0.4;
+        let radius = max_radius * (0.25 + 0.75 * size);
+        painter.circle_filled(rect.center(), radius, params.on_color);
+
+        if self.show_labels {
+            painter.text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                PHASE_LABELS[part],
+                FontId::proportional(max_radius * 0.15),
+                params.off_color,
+            );
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            for (name, cadence) in CADENCES {
+                if ui.selectable_label(self.cadence == cadence, name).clicked() {
+                    self.cadence = cadence;
+                }
+            }
+        });
+        for (label, secs) in PHASE_LABELS.iter().zip(self.cadence.iter_mut()) {
+            ui.add(
+                egui::Slider::new(secs, 0.0..=12.0)
+                    .text(*label)
+                    .suffix(" s"),
+            );
+        }
+        ui.add(egui::Slider::new(&mut self.flicker_depth, 0.0..=1.0).text("Background flicker"));
+        ui.checkbox(&mut self.show_labels, "Show breath cues");
+    }
+}
@@ -0,0 +1,49 @@
+use egui::{Painter, Rect, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Pattern-reversal checkerboard: the two cell colours swap every half cycle.
+pub struct Checkerboard {
+    cell_size: f32, // points
+}
+
+impl Default for Checkerboard {
+    fn default() -> Self {
+        Self { cell_size: 80.0 }
+    }
+}
+
+impl VisualMode for Checkerboard {
+    fn name(&self) -> &'static str {
+        "Checkerboard"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, params: &ModeParams) {
+        let even = params.mix(params.level);
+        let odd = params.mix(params.antiphase_level());
+        painter.rect_filled(rect, 0.0, odd);
+
+        // centre the grid so the pattern is symmetric on screen
+        let cols = (rect.width() / self.cell_size).ceil() as i32 + 1;
+        let rows = (rect.height() / self.cell_size).ceil() as i32 + 1;
+        let origin = rect.center() - Vec2::new(cols as f32, rows as f32) * self.cell_size * 0.5;
+        let cell = Vec2::splat(self.cell_size);
+        for row in 0..rows {
+            for col in (row % 2..cols).step_by(2) {
+                let min = origin + Vec2::new(col as f32, row as f32) * self.cell_size;
+                let r = Rect::from_min_size(min, cell).intersect(rect);
+                if r.is_positive() {
+                    painter.rect_filled(r, 0.0, even);
+                }
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.cell_size, 10.0..=400.0)
+                .text("Cell size")
+                .suffix(" pt"),
+        );
+    }
+}
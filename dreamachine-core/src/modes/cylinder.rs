@@ -0,0 +1,87 @@
+use std::f32::consts::TAU;
+
+use egui::{Painter, Rect, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Turntable speeds the original device was built around.
+const RPM_CHOICES: [f32; 3] = [78.0, 45.0, 100.0 / 3.0];
+
+/// The original Dreamachine: a slotted cylinder turning around a lamp.
+///
+/// The hole count is picked from the nominal turntable speed, then the speed
+/// is trimmed so that slots pass the viewer at exactly the session frequency.
+pub struct Cylinder {
+    nominal_rpm: f32,
+    rows: u32,
+    // last computed values, shown in the settings
+    holes: u32,
+    rpm: f32,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self {
+            nominal_rpm: 78.0,
+            rows: 3,
+            holes: 0,
+            rpm: 0.0,
+        }
+    }
+}
+
+impl VisualMode for Cylinder {
+    fn name(&self) -> &'static str {
+        "Cylinder"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let nominal_rps = self.nominal_rpm / 60.0;
+        self.holes = ((params.frequency_hz / nominal_rps).round() as u32).max(1);
+        let rps = params.frequency_hz / self.holes as f32;
+        self.rpm = rps * 60.0;
+        let rotation = (t * rps).fract() * TAU;
+
+        let body = Rect::from_center_size(
+            rect.center(),
+            Vec2::new(rect.width() * 0.6, rect.height() * 0.9),
+        );
+        let radius = body.width() * 0.5;
+        let row_h = body.height() / self.rows as f32;
+        let slot_w = TAU * radius / self.holes as f32 * 0.5;
+
+        for row in 0..self.rows {
+            // stagger alternate rows by half a slot
+            let offset = if row % 2 == 0 { 0.0 } else { 0.5 };
+            let top = body.top() + row as f32 * row_h + row_h * 0.15;
+            let bottom = top + row_h * 0.7;
+            for i in 0..self.holes {
+                let a = rotation + (i as f32 + offset) * TAU / self.holes as f32;
+                let facing = a.cos();
+                if facing <= 0.0 {
+                    continue; // back of the cylinder
+                }
+                let x = body.center().x + radius * a.sin();
+                let w = slot_w * facing;
+                let slot = Rect::from_x_y_ranges(x - w * 0.5..=x + w * 0.5, top..=bottom);
+                painter.rect_filled(slot, 0.0, params.mix(facing));
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.label("Turntable speed");
+        for rpm in RPM_CHOICES {
+            ui.radio_value(&mut self.nominal_rpm, rpm, format!("{rpm:.0} RPM"));
+        }
+        ui.add(egui::Slider::new(&mut self.rows, 1..=8).text("Rows"));
+        if self.holes > 0 {
+            ui.label(format!(
+                "{} holes per row at {:.1} RPM",
+                self.holes, self.rpm
+            ));
+        }
+    }
+}
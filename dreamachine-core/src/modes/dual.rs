@@ -0,0 +1,61 @@
+use egui::{Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+use crate::flicker::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+
+struct Component {
+    frequency_hz: f32,
+    amplitude: f32,
+}
+
+/// Full-field flicker made of two frequencies added together.
+pub struct DualFrequency {
+    components: [Component; 2],
+}
+
+impl Default for DualFrequency {
+    fn default() -> Self {
+        Self {
+            components: [
+                Component {
+                    frequency_hz: 10.0,
+                    amplitude: 1.0,
+                },
+                Component {
+                    frequency_hz: 6.0,
+                    amplitude: 1.0,
+                },
+            ],
+        }
+    }
+}
+
+impl VisualMode for DualFrequency {
+    fn name(&self) -> &'static str {
+        "Dual Frequency"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let total: f32 = self.components.iter().map(|c| c.amplitude).sum();
+        let sum: f32 = self
+            .components
+            .iter()
+            .map(|c| c.amplitude * params.waveform.level((t * c.frequency_hz).fract()))
+            .sum();
+        // normalise so the brightest point of the sum is full on
+        let level = if total > 0.0 { sum / total } else { 0.0 };
+        painter.rect_filled(rect, 0.0, params.mix(level));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        for (label, c) in ["First", "Second"].iter().zip(&mut self.components) {
+            ui.label(*label);
+            ui.add(
+                egui::Slider::new(&mut c.frequency_hz, MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ)
+                    .step_by(0.1)
+                    .suffix(" Hz"),
+            );
+            ui.add(egui::Slider::new(&mut c.amplitude, 0.0..=1.0).text("Amplitude"));
+        }
+    }
+}
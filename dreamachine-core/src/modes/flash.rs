@@ -0,0 +1,16 @@
+use egui::{Painter, Rect};
+
+use super::{ModeParams, VisualMode};
+
+/// Full-screen blink following the selected waveform.
+pub struct Flash;
+
+impl VisualMode for Flash {
+    fn name(&self) -> &'static str {
+        "Flash"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.mix(params.level));
+    }
+}
@@ -0,0 +1,34 @@
+use egui::{Color32, Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+
+/// Steady uniform field with no flicker at all. Pair with noise audio for a
+/// classic Ganzfeld session.
+pub struct Ganzfeld {
+    color: Color32,
+}
+
+impl Default for Ganzfeld {
+    fn default() -> Self {
+        Self {
+            color: Color32::from_rgb(255, 64, 32),
+        }
+    }
+}
+
+impl VisualMode for Ganzfeld {
+    fn name(&self) -> &'static str {
+        "Ganzfeld"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, _params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, self.color);
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.color_edit_button_srgba(&mut self.color);
+            ui.label("Field color");
+        });
+    }
+}
@@ -0,0 +1,91 @@
+use std::f32::consts::TAU;
+
+use egui::{Mesh, Painter, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Sinusoidal luminance grating whose contrast reverses with the flicker,
+/// optionally windowed by a Gaussian to form a Gabor patch.
+pub struct Grating {
+    cycles: f32, // spatial cycles across the shorter side of the screen
+    orientation_deg: f32,
+    gabor: bool,
+    sigma: f32, // envelope width as a fraction of the shorter side
+}
+
+impl Default for Grating {
+    fn default() -> Self {
+        Self {
+            cycles: 6.0,
+            orientation_deg: 45.0,
+            gabor: false,
+            sigma: 0.2,
+        }
+    }
+}
+
+impl VisualMode for Grating {
+    fn name(&self) -> &'static str {
+        "Grating"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.mix(0.5));
+
+        // square wave gives classic pattern reversal, sine a smooth one
+        let contrast = 2.0 * params.level - 1.0;
+        let side = rect.width().min(rect.height());
+        let period = side / self.cycles;
+        let sigma = self.sigma * side;
+        let normal = Vec2::angled(self.orientation_deg.to_radians());
+        let along = normal.rot90();
+        let center = rect.center();
+        let reach = rect.size().length() * 0.5;
+
+        // mesh laid out in grating coordinates: columns across the stripes,
+        // rows along them (only the envelope varies along the stripes)
+        let du = period / 8.0;
+        let cols = (2.0 * reach / du).ceil() as u32;
+        let rows = if self.gabor { 32 } else { 1 };
+        let mut mesh = Mesh::default();
+        for row in 0..=rows {
+            let v = -reach + 2.0 * reach * row as f32 / rows as f32;
+            for col in 0..=cols {
+                let u = -reach + col as f32 * du;
+                let mut amplitude = contrast * (TAU * u / period).sin();
+                if self.gabor {
+                    amplitude *= (-(u * u + v * v) / (2.0 * sigma * sigma)).exp();
+                }
+                let pos = center + normal * u + along * v;
+                mesh.colored_vertex(pos, params.mix(0.5 + 0.5 * amplitude));
+            }
+        }
+        let stride = cols + 1;
+        for row in 0..rows {
+            for col in 0..cols {
+                let i = row * stride + col;
+                mesh.add_triangle(i, i + 1, i + stride);
+                mesh.add_triangle(i + 1, i + stride + 1, i + stride);
+            }
+        }
+        painter.with_clip_rect(rect).add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.cycles, 1.0..=40.0)
+                .logarithmic(true)
+                .text("Spatial frequency (cycles/screen)"),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.orientation_deg, 0.0..=180.0)
+                .text("Orientation")
+                .suffix("°"),
+        );
+        ui.checkbox(&mut self.gabor, "Gaussian window (Gabor patch)");
+        ui.add_enabled(
+            self.gabor,
+            egui::Slider::new(&mut self.sigma, 0.05..=0.5).text("Window width"),
+        );
+    }
+}
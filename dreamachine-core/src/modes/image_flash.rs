@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use egui::{Color32, ColorImage, Context, Painter, Pos2, Rect, TextureHandle, Ui};
+
+use super::{ModeParams, VisualMode};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Alternate {
+    Black,
+    EachOther,
+}
+
+/// Flashes user-supplied images, either against the off colour or against
+/// each other. Images are added by dropping files on the window or by path.
+pub struct ImageFlash {
+    images: Vec<(String, TextureHandle)>,
+    alternate: Alternate,
+    fill: bool,
+    path: String,
+    error: Option<String>,
+}
+
+impl Default for ImageFlash {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            alternate: Alternate::Black,
+            fill: false,
+            path: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl ImageFlash {
+    fn load(&mut self, ctx: &Context, path: &Path) {
+        match image::open(path) {
+            Ok(img) => {
+                let rgba = img.to_rgba8();
+                let (w, h) = rgba.dimensions();
+                let color =
+                    ColorImage::from_rgba_unmultiplied([w as usize, h as usize], rgba.as_raw());
+                let name = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |n| n.to_string_lossy().into_owned(),
+                );
+                let texture = ctx.load_texture(name.clone(), color, egui::TextureOptions::LINEAR);
+                self.images.push((name, texture));
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{}: {e}", path.display())),
+        }
+    }
+
+    /// Where to draw an image of `size` so it fits (or fills) `rect`.
+    fn placement(&self, rect: Rect, size: egui::Vec2) -> Rect {
+        let scale = (rect.width() / size.x, rect.height() / size.y);
+        let scale = if self.fill {
+            scale.0.max(scale.1)
+        } else {
+            scale.0.min(scale.1)
+        };
+        Rect::from_center_size(rect.center(), size * scale)
+    }
+}
+
+impl VisualMode for ImageFlash {
+    fn name(&self) -> &'static str {
+        "Image Flash"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+        if self.images.is_empty() {
+            return;
+        }
+
+        let (index, level) = match self.alternate {
+            // one image per flash, cycling through the list
+            Alternate::Black => {
+                let cycle = (t * params.frequency_hz) as usize;
+                (cycle % self.images.len(), params.level)
+            }
+            // a new image every half cycle
+            Alternate::EachOther => {
+                let half = (t * params.frequency_hz * 2.0) as usize;
+                (half % self.images.len(), 1.0)
+            }
+        };
+        let texture = &self.images[index].1;
+        let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        let tint = Color32::WHITE.gamma_multiply(level);
+        painter.with_clip_rect(rect).image(
+            texture.id(),
+            self.placement(rect, texture.size_vec2()),
+            uv,
+            tint,
+        );
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        let dropped: Vec<_> = ui.ctx().input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        for path in dropped {
+            self.load(ui.ctx(), &path);
+        }
+
+        ui.label("Drop image files on the window while this is open, or enter a path:");
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.path);
+            if ui.button("Add").clicked() && !self.path.is_empty() {
+                let path = std::mem::take(&mut self.path);
+                self.load(ui.ctx(), Path::new(&path));
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::LIGHT_RED, error.as_str());
+        }
+
+        let mut remove = None;
+        for (i, (name, _)) in self.images.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            drop(self.images.remove(i));
+        }
+
+        ui.separator();
+        ui.radio_value(
+            &mut self.alternate,
+            Alternate::Black,
+            "Alternate with black",
+        );
+        ui.radio_value(
+            &mut self.alternate,
+            Alternate::EachOther,
+            "Alternate images with each other",
+        );
+        ui.checkbox(&mut self.fill, "Fill screen (crop)");
+    }
+}
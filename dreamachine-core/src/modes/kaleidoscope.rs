@@ -0,0 +1,97 @@
+use std::f32::consts::{PI, TAU};
+
+use egui::{Mesh, Painter, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode, hsv};
+
+/// A procedural pattern in one wedge, mirrored around the centre.
+pub struct Kaleidoscope {
+    /// Number of mirrored pairs around the circle.
+    folds: u32,
+    /// Rotation per flicker cycle, in degrees.
+    spin_deg: f32,
+    rainbow: bool,
+}
+
+impl Default for Kaleidoscope {
+    fn default() -> Self {
+        Self {
+            folds: 6,
+            spin_deg: 1.0,
+            rainbow: true,
+        }
+    }
+}
+
+impl VisualMode for Kaleidoscope {
+    fn name(&self) -> &'static str {
+        "Kaleidoscope"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let cycles = t * params.frequency_hz;
+        let rotation = (cycles * self.spin_deg / 360.0).fract() * TAU;
+        let drift = cycles * 0.02;
+        let wedge = PI / self.folds as f32;
+        let center = rect.center();
+        let radius = rect.width().hypot(rect.height()) * 0.5;
+        let (rings, steps) = (24, 8);
+
+        // colour the base wedge once, then mirror it
+        let mut colors = Vec::with_capacity((rings + 1) * (steps + 1));
+        for i in 0..=rings {
+            let r = i as f32 / rings as f32;
+            for j in 0..=steps {
+                let a = j as f32 / steps as f32 * wedge;
+                let v = 0.5 + 0.5 * (10.0 * r - 6.0 * drift + 3.0 * (4.0 * a + drift).sin()).sin();
+                colors.push(if self.rainbow {
+                    hsv(
+                        (r * 0.5 + v * 0.3 + drift * 0.1).fract(),
+                        0.8,
+                        v * params.level,
+                    )
+                } else {
+                    params.mix(v * params.level)
+                });
+            }
+        }
+
+        let mut mesh = Mesh::default();
+        for k in 0..self.folds * 2 {
+            let base = rotation + k as f32 * wedge;
+            let mirrored = k % 2 == 1;
+            let first = mesh.vertices.len() as u32;
+            for i in 0..=rings {
+                let r = i as f32 / rings as f32 * radius;
+                for j in 0..=steps {
+                    let a = j as f32 / steps as f32 * wedge;
+                    let theta = if mirrored { base + wedge - a } else { base + a };
+                    let color = colors[i * (steps + 1) + j];
+                    mesh.colored_vertex(center + Vec2::angled(theta) * r, color);
+                }
+            }
+            let row = steps as u32 + 1;
+            for i in 0..rings as u32 {
+                for j in 0..steps as u32 {
+                    let a = first + i * row + j;
+                    let b = a + row;
+                    mesh.add_triangle(a, b, a + 1);
+                    mesh.add_triangle(a + 1, b, b + 1);
+                }
+            }
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.folds, 2..=12).text("Folds"));
+        ui.add(
+            egui::Slider::new(&mut self.spin_deg, 0.0..=10.0)
+                .text("Spin per cycle")
+                .suffix("°"),
+        );
+        ui.checkbox(&mut self.rainbow, "Rainbow colors");
+    }
+}
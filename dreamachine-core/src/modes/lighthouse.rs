@@ -0,0 +1,139 @@
+use std::f32::consts::TAU;
+
+use egui::{self, Mesh, Painter, Pos2, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Radial-wedge beams rotating around the centre of the screen, or swinging
+/// back and forth over a limited arc, optionally joined by a counter-rotating
+/// set.
+pub struct Lighthouse {
+    half_width: f32, // radians
+    softness: f32,   // fraction of the half-width that fades out
+    clockwise: bool,
+    arc_deg: f32, // 360 for full rotations
+    wedges: u32,
+    counter: bool, // second, counter-rotating set of wedges
+    counter_offset_deg: f32,
+}
+
+impl Default for Lighthouse {
+    fn default() -> Self {
+        Self {
+            half_width: 0.3,
+            softness: 0.5,
+            clockwise: true,
+            arc_deg: 360.0,
+            wedges: 1,
+            counter: false,
+            counter_offset_deg: 0.0,
+        }
+    }
+}
+
+impl Lighthouse {
+    /// Beam heading at time `t`, in radians.
+    fn heading(&self, t: f32, frequency_hz: f32) -> f32 {
+        let sign = if self.clockwise { 1.0 } else { -1.0 };
+        if self.arc_deg >= 360.0 {
+            return sign * (t * frequency_hz).fract() * TAU;
+        }
+        // swing across an arc centred on straight up; it passes each point
+        // twice per swing, so swing at half the rate to keep the frequency
+        let swing = (t * frequency_hz * 0.5).fract();
+        let tri = 1.0 - (2.0 * swing - 1.0).abs();
+        let arc = self.arc_deg.to_radians();
+        -TAU / 4.0 + sign * (tri - 0.5) * arc
+    }
+}
+
+impl VisualMode for Lighthouse {
+    fn name(&self) -> &'static str {
+        "Lighthouse"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        // with several wedges each one only needs to come round once per
+        // `wedges` flicker cycles
+        let angle = self.heading(t, params.frequency_hz / self.wedges as f32);
+        let center = rect.center();
+        let radius = (rect.width().hypot(rect.height())) * 0.6;
+        let mut headings = vec![angle];
+        if self.counter {
+            headings.push(-angle + self.counter_offset_deg.to_radians());
+        }
+        for heading in headings {
+            for w in 0..self.wedges {
+                let a = heading + w as f32 * TAU / self.wedges as f32;
+                paint_wedge(
+                    painter,
+                    center,
+                    radius,
+                    a,
+                    self.half_width,
+                    self.softness,
+                    params,
+                );
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.half_width, 0.02..=1.5)
+                .text("Beam half-width")
+                .suffix(" rad"),
+        );
+        ui.add(egui::Slider::new(&mut self.softness, 0.0..=1.0).text("Edge softness"));
+        ui.checkbox(&mut self.clockwise, "Clockwise");
+        ui.add(
+            egui::Slider::new(&mut self.arc_deg, 30.0..=360.0)
+                .text("Sweep arc")
+                .suffix("°"),
+        );
+        ui.add(egui::Slider::new(&mut self.wedges, 1..=6).text("Wedges"));
+        ui.checkbox(&mut self.counter, "Counter-rotating beams");
+        ui.add_enabled(
+            self.counter,
+            egui::Slider::new(&mut self.counter_offset_deg, 0.0..=360.0)
+                .text("Counter phase offset")
+                .suffix("°"),
+        );
+    }
+}
+
+/// A wedge from `center` out to `radius`, full brightness in the middle and
+/// fading to the off colour over the outer `softness` of its half-width.
+fn paint_wedge(
+    painter: &Painter,
+    center: Pos2,
+    radius: f32,
+    angle: f32,
+    half_width: f32,
+    softness: f32,
+    params: &ModeParams,
+) {
+    let slices = 48;
+    let mut mesh = Mesh::default();
+    for i in 0..slices {
+        let f0 = i as f32 / slices as f32;
+        let f1 = (i + 1) as f32 / slices as f32;
+        let dist = ((f0 + f1) - 1.0).abs(); // 0 at the centre line, 1 at the edge
+        let level = if softness > 0.0 {
+            ((1.0 - dist) / softness).min(1.0)
+        } else {
+            1.0
+        };
+        let color = params.mix(level);
+        let a0 = angle - half_width + f0 * 2.0 * half_width;
+        let a1 = angle - half_width + f1 * 2.0 * half_width;
+        let base = mesh.vertices.len() as u32;
+        mesh.colored_vertex(center, color);
+        mesh.colored_vertex(center + Vec2::angled(a0) * radius, color);
+        mesh.colored_vertex(center + Vec2::angled(a1) * radius, color);
+        mesh.add_triangle(base, base + 1, base + 2);
+    }
+    painter.add(Shape::mesh(mesh));
+}
@@ -0,0 +1,133 @@
+use std::f32::consts::{PI, TAU};
+
+use egui::{Color32, Mesh, Painter, Pos2, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode, hsv};
+use crate::rng::Rng;
+
+struct Ring {
+    petals: u32,
+    inner: f32, // fraction of the mandala radius
+    outer: f32,
+    width: f32, // fraction of the petal spacing
+    hue: f32,
+    spin: f32, // petals advanced per flicker cycle, signed
+}
+
+/// Seeded procedural mandala whose petal rings pulse and turn with the flicker.
+pub struct Mandala {
+    seed: u32,
+    rainbow: bool,
+    rings: Vec<Ring>,
+    generated_for: Option<u32>,
+}
+
+impl Default for Mandala {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            rainbow: true,
+            rings: Vec::new(),
+            generated_for: None,
+        }
+    }
+}
+
+impl Mandala {
+    fn generate(&mut self) {
+        let mut rng = Rng::new(u64::from(self.seed));
+        let count = rng.range_u32(3, 6);
+        let base_hue = rng.next_f32();
+        self.rings = (0..count)
+            .map(|i| {
+                let inner = i as f32 / count as f32;
+                let outer = inner + rng.range(1.2, 2.0) / count as f32;
+                let dir = if i % 2 == 0 { 1.0 } else { -1.0 };
+                Ring {
+                    petals: rng.range_u32(3, 8) * 2,
+                    inner,
+                    outer,
+                    width: rng.range(0.4, 0.9),
+                    hue: (base_hue + i as f32 * rng.range(0.05, 0.2)).fract(),
+                    spin: dir * rng.range(0.01, 0.05),
+                }
+            })
+            .collect();
+        self.generated_for = Some(self.seed);
+    }
+}
+
+impl VisualMode for Mandala {
+    fn name(&self) -> &'static str {
+        "Mandala"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        if self.generated_for != Some(self.seed) {
+            self.generate();
+        }
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let cycles = t * params.frequency_hz;
+        let center = rect.center();
+        let radius = rect.width().min(rect.height()) * 0.5;
+
+        let mut mesh = Mesh::default();
+        // outermost first so inner rings sit on top
+        for ring in self.rings.iter().rev() {
+            let spacing = TAU / ring.petals as f32;
+            let rotation = (cycles * ring.spin).fract() * spacing;
+            let color = if self.rainbow {
+                hsv(ring.hue, 0.7, params.level)
+            } else {
+                params.mix(params.level)
+            };
+            for p in 0..ring.petals {
+                let angle = rotation + p as f32 * spacing;
+                petal(
+                    &mut mesh,
+                    center,
+                    angle,
+                    ring.inner * radius,
+                    ring.outer * radius,
+                    ring.width * spacing * 0.5,
+                    color,
+                );
+            }
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Seed");
+            ui.add(egui::DragValue::new(&mut self.seed));
+            if ui.button("Next").clicked() {
+                self.seed = self.seed.wrapping_add(1);
+            }
+        });
+        ui.checkbox(&mut self.rainbow, "Rainbow colors");
+    }
+}
+
+/// Lens-shaped petal between radii `r0` and `r1`, as a fan around its middle.
+fn petal(mesh: &mut Mesh, center: Pos2, angle: f32, r0: f32, r1: f32, half: f32, color: Color32) {
+    let steps = 12;
+    let polar = |r: f32, a: f32| center + Vec2::angled(a) * r;
+    let hub = mesh.vertices.len() as u32;
+    mesh.colored_vertex(polar((r0 + r1) * 0.5, angle), color);
+    for side in [1.0, -1.0] {
+        for s in 0..=steps {
+            let f = s as f32 / steps as f32;
+            let f = if side > 0.0 { f } else { 1.0 - f };
+            let offset = side * half * (PI * f).sin();
+            mesh.colored_vertex(polar(r0 + (r1 - r0) * f, angle + offset), color);
+        }
+    }
+    let outline = 2 * (steps + 1);
+    for i in 0..outline {
+        let a = hub + 1 + i;
+        let b = hub + 1 + (i + 1) % outline;
+        mesh.add_triangle(hub, a, b);
+    }
+}
@@ -0,0 +1,181 @@
+//! Visual modes.
+//!
+//! Every mode implements [`VisualMode`] and is listed in [`all`]; the update
+//! loop only ever talks to the trait, so adding a mode means adding a file here
+//! and one line to the registry.
+
+use egui::{Color32, Painter, Rect, Ui};
+
+use crate::envelope::Envelope;
+use crate::grading::Grading;
+use crate::waveform::Waveform;
+
+mod benham;
+mod breathing;
+mod checkerboard;
+mod cylinder;
+mod dual;
+mod flash;
+mod ganzfeld;
+mod grating;
+mod image_flash;
+mod kaleidoscope;
+mod lighthouse;
+mod mandala;
+mod moire;
+mod noise;
+mod particles;
+mod peripheral;
+mod plasma;
+mod polygon;
+mod polyrhythm;
+mod rgb;
+mod sine;
+mod spiral;
+mod split_field;
+mod sweep;
+mod text_flash;
+mod tunnel;
+
+pub use benham::Benham;
+pub use breathing::Breathing;
+pub use checkerboard::Checkerboard;
+pub use cylinder::Cylinder;
+pub use dual::DualFrequency;
+pub use flash::Flash;
+pub use ganzfeld::Ganzfeld;
+pub use grating::Grating;
+pub use image_flash::ImageFlash;
+pub use kaleidoscope::Kaleidoscope;
+pub use lighthouse::Lighthouse;
+pub use mandala::Mandala;
+pub use moire::Moire;
+pub use noise::NoiseFlicker;
+pub use particles::Particles;
+pub use peripheral::Peripheral;
+pub use plasma::Plasma;
+pub use polygon::Polygon;
+pub use polyrhythm::Polyrhythm;
+pub use rgb::RgbChannels;
+pub use sine::Sine;
+pub use spiral::Spiral;
+pub use split_field::SplitField;
+pub use sweep::Sweep;
+pub use text_flash::TextFlash;
+pub use tunnel::Tunnel;
+
+/// Per-frame values shared by every mode.
+pub struct ModeParams {
+    pub frequency_hz: f32,
+    pub sweep_speed: f32,     // cycles per second
+    pub beam_width_norm: f32, // fraction of window width
+    /// Waveform brightness in `0..=1`, for modes that modulate intensity.
+    pub level: f32,
+    /// Position within the current flicker cycle, `0..1`.
+    pub phase: f32,
+    /// The selected waveform, for modes that run their own oscillators.
+    pub waveform: Waveform,
+    /// Pulse shaping applied on top of `waveform`.
+    pub envelope: Envelope,
+    /// Whether the waveform is in the bright half of its cycle.
+    pub show_white: bool,
+    pub on_color: Color32,
+    pub off_color: Color32,
+    /// The picture grading. Shapes are graded after they are painted, so
+    /// only modes that draw outside them, like the GPU shaders, need it.
+    pub grading: Grading,
+}
+
+impl ModeParams {
+    /// Waveform brightness half a cycle away from [`ModeParams::level`].
+    pub fn antiphase_level(&self) -> f32 {
+        self.envelope
+            .level(self.waveform, (self.phase + 0.5).fract(), self.frequency_hz)
+    }
+
+    /// Blend from `off_color` at 0 to `on_color` at 1.
+    pub fn mix(&self, level: f32) -> Color32 {
+        mix(self.off_color, self.on_color, level)
+    }
+}
+
+/// Blend from `off` at 0 to `on` at 1.
+pub fn mix(off: Color32, on: Color32, level: f32) -> Color32 {
+    let t = level.clamp(0.0, 1.0);
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgba_premultiplied(
+        lerp(off.r(), on.r()),
+        lerp(off.g(), on.g()),
+        lerp(off.b(), on.b()),
+        lerp(off.a(), on.a()),
+    )
+}
+
+pub trait VisualMode {
+    /// Name shown in the Mode menu.
+    fn name(&self) -> &'static str;
+
+    /// Paint one frame into `rect`. `t` is seconds since the mode was selected.
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams);
+
+    /// Whether [`OfflineRenderer`](crate::OfflineRenderer) can draw this
+    /// mode. Modes that paint on the GPU themselves would come out black.
+    fn renders_offline(&self) -> bool {
+        true
+    }
+
+    /// Mode-specific controls, shown under Edit → Mode Settings.
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.label("This mode has no settings.");
+    }
+}
+
+/// Every available mode, in menu order.
+pub fn all() -> Vec<Box<dyn VisualMode>> {
+    vec![
+        Box::new(Flash),
+        Box::new(Sweep::default()),
+        Box::new(Lighthouse::default()),
+        Box::new(Sine),
+        Box::new(RgbChannels::default()),
+        Box::new(SplitField),
+        Box::new(Spiral::default()),
+        Box::new(Cylinder::default()),
+        Box::new(Tunnel::default()),
+        Box::new(Checkerboard::default()),
+        Box::new(Ganzfeld::default()),
+        Box::new(Kaleidoscope::default()),
+        Box::new(Mandala::default()),
+        Box::new(Benham::default()),
+        Box::new(Plasma::default()),
+        Box::new(NoiseFlicker::default()),
+        Box::new(Moire::default()),
+        Box::new(Grating::default()),
+        Box::new(Breathing::default()),
+        Box::new(Polygon::default()),
+        Box::new(Particles::default()),
+        Box::new(ImageFlash::default()),
+        Box::new(TextFlash::default()),
+        Box::new(DualFrequency::default()),
+        Box::new(Polyrhythm::default()),
+        Box::new(Peripheral::default()),
+    ]
+}
+
+/// Opaque colour from hue, saturation and value, all in `0..=1`.
+pub(crate) fn hsv(h: f32, s: f32, v: f32) -> Color32 {
+    let h = h.rem_euclid(1.0) * 6.0;
+    let c = v.clamp(0.0, 1.0) * s.clamp(0.0, 1.0);
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = v.clamp(0.0, 1.0) - c;
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let to_u8 = |f: f32| ((f + m) * 255.0).round() as u8;
+    Color32::from_rgb(to_u8(r), to_u8(g), to_u8(b))
+}
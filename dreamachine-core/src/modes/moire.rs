@@ -0,0 +1,66 @@
+use egui::{Painter, Pos2, Rect, Stroke, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Two line gratings at a slight angle drifting against each other. Line
+/// contrast follows the flicker and the drift is locked to it as well.
+pub struct Moire {
+    period: f32, // points between lines
+    angle_deg: f32,
+    drift: f32, // grating periods travelled per flicker cycle
+}
+
+impl Default for Moire {
+    fn default() -> Self {
+        Self {
+            period: 16.0,
+            angle_deg: 5.0,
+            drift: 0.05,
+        }
+    }
+}
+
+impl VisualMode for Moire {
+    fn name(&self) -> &'static str {
+        "Moiré"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let painter = painter.with_clip_rect(rect);
+        let offset = (t * params.frequency_hz * self.drift).fract();
+        let stroke = Stroke::new(self.period * 0.5, params.mix(params.level));
+        let half = self.angle_deg.to_radians() * 0.5;
+        grating(&painter, rect, -half, self.period, offset, stroke);
+        grating(&painter, rect, half, self.period, -offset, stroke);
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.period, 4.0..=64.0)
+                .text("Line spacing")
+                .suffix(" pt"),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.angle_deg, 0.0..=30.0)
+                .text("Angle between gratings")
+                .suffix("°"),
+        );
+        ui.add(egui::Slider::new(&mut self.drift, 0.0..=0.5).text("Drift per cycle"));
+    }
+}
+
+/// Parallel lines covering `rect`, tilted by `angle` from vertical and shifted
+/// by `offset` periods.
+fn grating(painter: &Painter, rect: Rect, angle: f32, period: f32, offset: f32, stroke: Stroke) {
+    let normal = Vec2::angled(angle);
+    let along = normal.rot90();
+    let reach = rect.size().length() * 0.5;
+    let center: Pos2 = rect.center();
+    let count = (reach / period).ceil() as i32 + 1;
+    for k in -count..=count {
+        let mid = center + normal * (k as f32 + offset) * period;
+        painter.line_segment([mid - along * reach, mid + along * reach], stroke);
+    }
+}
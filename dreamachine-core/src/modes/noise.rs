@@ -0,0 +1,69 @@
+use std::f32::consts::TAU;
+
+use egui::{Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+use crate::rng::Rng;
+
+/// Full-field flicker driven by narrow-band noise around the session frequency.
+///
+/// A carrier at the target frequency is modulated in amplitude and phase by
+/// two independent Perlin noise signals, which spreads its energy over
+/// roughly `bandwidth` Hz. At zero bandwidth this is a plain sine flicker.
+pub struct NoiseFlicker {
+    bandwidth: f32, // Hz
+    seed: u32,
+}
+
+impl Default for NoiseFlicker {
+    fn default() -> Self {
+        Self {
+            bandwidth: 2.0,
+            seed: 1,
+        }
+    }
+}
+
+impl VisualMode for NoiseFlicker {
+    fn name(&self) -> &'static str {
+        "Noise Flicker"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let seed = u64::from(self.seed) << 32;
+        let (i, q) = if self.bandwidth > 0.0 {
+            let x = t * self.bandwidth;
+            (perlin(x, seed), perlin(x, seed | 0x8000_0000))
+        } else {
+            (1.0, 0.0)
+        };
+        // quadrature mix; starts dark like the other waveforms
+        let angle = TAU * params.frequency_hz * t;
+        let signal = -(i * angle.cos() + q * angle.sin());
+        let level = (0.5 + 0.5 * signal).clamp(0.0, 1.0);
+        painter.rect_filled(rect, 0.0, params.mix(level));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.bandwidth, 0.0..=10.0)
+                .text("Bandwidth")
+                .suffix(" Hz"),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Seed");
+            ui.add(egui::DragValue::new(&mut self.seed));
+        });
+    }
+}
+
+/// 1-D gradient noise in roughly `-1..1`, one lattice point per unit of `x`.
+fn perlin(x: f32, seed: u64) -> f32 {
+    let cell = x.floor();
+    let f = x - cell;
+    let gradient = |i: f32| Rng::new(seed ^ i as u64).range(-1.0, 1.0);
+    let a = gradient(cell) * f;
+    let b = gradient(cell + 1.0) * (f - 1.0);
+    let fade = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    2.0 * (a + (b - a) * fade)
+}
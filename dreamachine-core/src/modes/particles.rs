@@ -0,0 +1,89 @@
+use egui::{Mesh, Painter, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+use crate::rng::Rng;
+
+/// Nearest and farthest particle depth, in units of the screen half-size.
+const NEAR: f32 = 0.05;
+const FAR: f32 = 4.0;
+
+/// Starfield flying towards the viewer; particle brightness pulses with the
+/// flicker.
+pub struct Particles {
+    count: usize,
+    speed: f32, // depth units per second
+    size: f32,  // points at unit depth
+    rng: Rng,
+    particles: Vec<[f32; 3]>,
+    last_t: Option<f32>,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self {
+            count: 2000,
+            speed: 0.5,
+            size: 2.0,
+            rng: Rng::new(0),
+            particles: Vec::new(),
+            last_t: None,
+        }
+    }
+}
+
+impl Particles {
+    fn spawn(&mut self, z: f32) -> [f32; 3] {
+        [
+            self.rng.range(-1.0, 1.0) * FAR,
+            self.rng.range(-1.0, 1.0) * FAR,
+            z,
+        ]
+    }
+}
+
+impl VisualMode for Particles {
+    fn name(&self) -> &'static str {
+        "Particles"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        // t restarts when the mode is re-selected, so never step backwards
+        let dt = self.last_t.map_or(0.0, |last| (t - last).clamp(0.0, 0.1));
+        self.last_t = Some(t);
+
+        while self.particles.len() < self.count {
+            let z = self.rng.range(NEAR, FAR);
+            let p = self.spawn(z);
+            self.particles.push(p);
+        }
+        self.particles.truncate(self.count);
+
+        let center = rect.center();
+        let half = rect.width().max(rect.height()) * 0.5;
+        let color = params.mix(params.level);
+        let mut mesh = Mesh::default();
+        for i in 0..self.particles.len() {
+            self.particles[i][2] -= self.speed * dt;
+            if self.particles[i][2] < NEAR {
+                self.particles[i] = self.spawn(FAR);
+                continue;
+            }
+            let [x, y, z] = self.particles[i];
+            let pos = center + Vec2::new(x, y) / z * half;
+            if !rect.contains(pos) {
+                continue;
+            }
+            let s = (self.size / z).min(self.size * 8.0);
+            mesh.add_colored_rect(Rect::from_center_size(pos, Vec2::splat(s)), color);
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.count, 100..=10000).text("Density"));
+        ui.add(egui::Slider::new(&mut self.speed, 0.0..=3.0).text("Speed"));
+        ui.add(egui::Slider::new(&mut self.size, 1.0..=6.0).text("Particle size"));
+    }
+}
@@ -0,0 +1,59 @@
+use std::f32::consts::TAU;
+
+use egui::{Mesh, Painter, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Flicker only outside a steady dark centre, aimed at the peripheral retina,
+/// which is more sensitive to flicker than the fovea.
+pub struct Peripheral {
+    radius: f32,   // fraction of half the shorter screen side
+    softness: f32, // width of the blended edge, same units
+}
+
+impl Default for Peripheral {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            softness: 0.1,
+        }
+    }
+}
+
+impl VisualMode for Peripheral {
+    fn name(&self) -> &'static str {
+        "Peripheral"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, params: &ModeParams) {
+        let outer = params.mix(params.level);
+        painter.rect_filled(rect, 0.0, outer);
+
+        let center = rect.center();
+        let unit = rect.width().min(rect.height()) * 0.5;
+        let inner_r = self.radius * unit;
+        let edge_r = inner_r + self.softness * unit;
+
+        // dark disc, then a ring fading from the off colour out to the flicker
+        let segments = 96;
+        let mut mesh = Mesh::default();
+        mesh.colored_vertex(center, params.off_color);
+        for i in 0..segments {
+            let dir = Vec2::angled(i as f32 * TAU / segments as f32);
+            mesh.colored_vertex(center + dir * inner_r, params.off_color);
+            mesh.colored_vertex(center + dir * edge_r, outer);
+        }
+        for i in 0..segments {
+            let (a, b) = (1 + 2 * i, 1 + 2 * ((i + 1) % segments));
+            mesh.add_triangle(0, a, b);
+            mesh.add_triangle(a, a + 1, b);
+            mesh.add_triangle(a + 1, b + 1, b);
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.radius, 0.0..=1.5).text("Dark centre radius"));
+        ui.add(egui::Slider::new(&mut self.softness, 0.0..=0.5).text("Edge softness"));
+    }
+}
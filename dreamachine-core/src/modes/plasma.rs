@@ -0,0 +1,95 @@
+use std::f32::consts::TAU;
+
+use egui::{Color32, Mesh, Painter, Pos2, Rect, Shape, Ui};
+
+use super::{ModeParams, VisualMode, hsv};
+
+/// Slowly drifting plasma whose brightness is modulated by the flicker.
+///
+/// With a depth below 100% the field never goes fully dark, which is much
+/// softer on the eyes than a full-field strobe.
+pub struct Plasma {
+    depth: f32,
+    scale: f32, // pattern repeats per screen height
+    drift: f32, // pattern speed, independent of the flicker
+    rainbow: bool,
+}
+
+impl Default for Plasma {
+    fn default() -> Self {
+        Self {
+            depth: 0.6,
+            scale: 2.0,
+            drift: 0.2,
+            rainbow: true,
+        }
+    }
+}
+
+impl VisualMode for Plasma {
+    fn name(&self) -> &'static str {
+        "Plasma"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let gain = 1.0 - self.depth + self.depth * params.level;
+        let time = t * self.drift;
+
+        // coarse vertex grid; colours are interpolated between vertices
+        let rows = 36;
+        let cols = ((rows as f32 * rect.aspect_ratio()).ceil() as u32).max(1);
+        let mut mesh = Mesh::default();
+        for row in 0..=rows {
+            for col in 0..=cols {
+                let u = col as f32 / cols as f32;
+                let v = row as f32 / rows as f32;
+                let x = u * rect.aspect_ratio() * self.scale;
+                let y = v * self.scale;
+                let value = plasma(x, y, time);
+                let color = if self.rainbow {
+                    hsv((value + time * 0.1).fract(), 0.8, gain)
+                } else {
+                    scale(params.mix(value), gain)
+                };
+                let pos = Pos2::new(
+                    rect.left() + u * rect.width(),
+                    rect.top() + v * rect.height(),
+                );
+                mesh.colored_vertex(pos, color);
+            }
+        }
+        let stride = cols + 1;
+        for row in 0..rows {
+            for col in 0..cols {
+                let i = row * stride + col;
+                mesh.add_triangle(i, i + 1, i + stride);
+                mesh.add_triangle(i + 1, i + stride + 1, i + stride);
+            }
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.depth, 0.0..=1.0).text("Modulation depth"));
+        ui.add(egui::Slider::new(&mut self.scale, 0.5..=6.0).text("Scale"));
+        ui.add(egui::Slider::new(&mut self.drift, 0.0..=2.0).text("Drift speed"));
+        ui.checkbox(&mut self.rainbow, "Rainbow colors");
+    }
+}
+
+/// Classic sum-of-sines plasma, mapped to `0..1`.
+fn plasma(x: f32, y: f32, t: f32) -> f32 {
+    let cx = x + 0.5 * (t * 0.3 * TAU).sin();
+    let cy = y + 0.5 * (t * 0.2 * TAU).cos();
+    let v = (x * TAU * 0.5 + t * TAU).sin()
+        + ((y * 0.5 + t * 0.7) * TAU * 0.5).sin()
+        + ((x + y + t * 0.5) * TAU * 0.35).sin()
+        + ((cx * cx + cy * cy).sqrt() * TAU * 0.5 - t * TAU).sin();
+    (v / 4.0 + 1.0) * 0.5
+}
+
+fn scale(color: Color32, gain: f32) -> Color32 {
+    let [r, g, b, a] = color.to_array();
+    let f = |c: u8| (c as f32 * gain).round() as u8;
+    Color32::from_rgba_premultiplied(f(r), f(g), f(b), a)
+}
@@ -0,0 +1,56 @@
+use std::f32::consts::TAU;
+
+use egui::{Painter, Pos2, Rect, Shape, Stroke, Ui, Vec2};
+
+use super::{ModeParams, VisualMode, hsv};
+
+/// A spinning regular polygon that flashes, or cycles hue, with the flicker.
+pub struct Polygon {
+    sides: u32,
+    spin_rpm: f32, // signed, negative turns anticlockwise
+    hue_shift: bool,
+}
+
+impl Default for Polygon {
+    fn default() -> Self {
+        Self {
+            sides: 6,
+            spin_rpm: 10.0,
+            hue_shift: false,
+        }
+    }
+}
+
+impl VisualMode for Polygon {
+    fn name(&self) -> &'static str {
+        "Polygon"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let color = if self.hue_shift {
+            // one trip round the colour wheel per flicker cycle
+            hsv(params.phase, 1.0, 1.0)
+        } else {
+            params.mix(params.level)
+        };
+        let rotation = (t * self.spin_rpm / 60.0).fract() * TAU;
+        let center = rect.center();
+        let radius = rect.width().min(rect.height()) * 0.4;
+        let points: Vec<Pos2> = (0..self.sides)
+            .map(|i| center + Vec2::angled(rotation + i as f32 * TAU / self.sides as f32) * radius)
+            .collect();
+        painter.add(Shape::convex_polygon(points, color, Stroke::NONE));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.sides, 3..=12).text("Sides"));
+        ui.add(
+            egui::Slider::new(&mut self.spin_rpm, -120.0..=120.0)
+                .text("Spin")
+                .suffix(" RPM"),
+        );
+        ui.checkbox(&mut self.hue_shift, "Cycle colors instead of flashing");
+    }
+}
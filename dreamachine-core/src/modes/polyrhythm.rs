@@ -0,0 +1,76 @@
+use egui::{Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+use crate::flicker::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+
+const REGION_LABELS: [&str; 4] = ["Top left", "Top right", "Bottom left", "Bottom right"];
+
+struct Region {
+    frequency_hz: f32,
+    phase_deg: f32,
+}
+
+/// Screen split into halves or quarters, each flickering at its own rate.
+pub struct Polyrhythm {
+    quarters: bool,
+    regions: [Region; 4],
+}
+
+impl Default for Polyrhythm {
+    fn default() -> Self {
+        let region = |frequency_hz| Region {
+            frequency_hz,
+            phase_deg: 0.0,
+        };
+        Self {
+            quarters: false,
+            regions: [region(10.0), region(7.5), region(5.0), region(2.5)],
+        }
+    }
+}
+
+impl VisualMode for Polyrhythm {
+    fn name(&self) -> &'static str {
+        "Polyrhythm"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let (left, right) = rect.split_left_right_at_fraction(0.5);
+        let areas = if self.quarters {
+            let (tl, bl) = left.split_top_bottom_at_fraction(0.5);
+            let (tr, br) = right.split_top_bottom_at_fraction(0.5);
+            vec![tl, tr, bl, br]
+        } else {
+            vec![left, right]
+        };
+        for (area, region) in areas.into_iter().zip(&self.regions) {
+            let phase = (t * region.frequency_hz + region.phase_deg / 360.0).fract();
+            painter.rect_filled(area, 0.0, params.mix(params.waveform.level(phase)));
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.quarters, false, "Halves");
+            ui.radio_value(&mut self.quarters, true, "Quarters");
+        });
+        let count = if self.quarters { 4 } else { 2 };
+        let labels = if self.quarters {
+            REGION_LABELS
+        } else {
+            ["Left", "Right", "", ""]
+        };
+        for (label, region) in labels.iter().zip(&mut self.regions).take(count) {
+            ui.label(*label);
+            ui.add(
+                egui::Slider::new(
+                    &mut region.frequency_hz,
+                    MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ,
+                )
+                .step_by(0.1)
+                .suffix(" Hz"),
+            );
+            ui.add(egui::Slider::new(&mut region.phase_deg, 0.0..=360.0).suffix("°"));
+        }
+    }
+}
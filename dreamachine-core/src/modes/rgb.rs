@@ -0,0 +1,63 @@
+use egui::{Color32, Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+use crate::flicker::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+
+struct Channel {
+    frequency_hz: f32,
+    phase_deg: f32,
+}
+
+/// Red, green and blue flickering at independent rates.
+pub struct RgbChannels {
+    channels: [Channel; 3],
+}
+
+impl Default for RgbChannels {
+    fn default() -> Self {
+        Self {
+            channels: [
+                Channel {
+                    frequency_hz: 10.0,
+                    phase_deg: 0.0,
+                },
+                Channel {
+                    frequency_hz: 10.5,
+                    phase_deg: 0.0,
+                },
+                Channel {
+                    frequency_hz: 11.0,
+                    phase_deg: 0.0,
+                },
+            ],
+        }
+    }
+}
+
+impl VisualMode for RgbChannels {
+    fn name(&self) -> &'static str {
+        "RGB Channels"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let [r, g, b] = [0, 1, 2].map(|i| {
+            let ch = &self.channels[i];
+            let phase = (t * ch.frequency_hz + ch.phase_deg / 360.0).fract();
+            params.mix(params.waveform.level(phase))
+        });
+        let color = Color32::from_rgb(r.r(), g.g(), b.b());
+        painter.rect_filled(rect, 0.0, color);
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        for (label, ch) in ["Red", "Green", "Blue"].iter().zip(&mut self.channels) {
+            ui.label(*label);
+            ui.add(
+                egui::Slider::new(&mut ch.frequency_hz, MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ)
+                    .step_by(0.1)
+                    .suffix(" Hz"),
+            );
+            ui.add(egui::Slider::new(&mut ch.phase_deg, 0.0..=360.0).suffix("°"));
+        }
+    }
+}
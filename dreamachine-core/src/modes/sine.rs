@@ -0,0 +1,20 @@
+use std::f32::consts::TAU;
+
+use egui::{Painter, Rect};
+
+use super::{ModeParams, VisualMode};
+
+/// Full-screen brightness following a sine wave, a gentler alternative to Flash.
+pub struct Sine;
+
+impl VisualMode for Sine {
+    fn name(&self) -> &'static str {
+        "Sine"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        // starts dark, peaks half a cycle later
+        let level = 0.5 - 0.5 * (TAU * params.frequency_hz * t).cos();
+        painter.rect_filled(rect, 0.0, params.mix(level));
+    }
+}
@@ -0,0 +1,73 @@
+use std::f32::consts::TAU;
+
+use egui::{Mesh, Painter, Pos2, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Rotating spiral arms, after Brion Gysin's painted Dreamachine cylinders.
+pub struct Spiral {
+    arms: u32,
+    turns: f32,
+    clockwise: bool,
+}
+
+impl Default for Spiral {
+    fn default() -> Self {
+        Self {
+            arms: 4,
+            turns: 1.5,
+            clockwise: true,
+        }
+    }
+}
+
+impl VisualMode for Spiral {
+    fn name(&self) -> &'static str {
+        "Spiral"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        // A fixed point sees `arms` edges pass per rotation, so this makes the
+        // flicker at any point equal the session frequency.
+        let rps = params.frequency_hz / self.arms as f32;
+        let dir = if self.clockwise { 1.0 } else { -1.0 };
+        let rotation = dir * (t * rps).fract() * TAU;
+
+        let center = rect.center();
+        let radius = rect.width().hypot(rect.height()) * 0.5;
+        let half_width = TAU / self.arms as f32 * 0.25;
+        let color = params.on_color;
+        let steps = 120;
+
+        let mut mesh = Mesh::default();
+        for arm in 0..self.arms {
+            let base = rotation + arm as f32 * TAU / self.arms as f32;
+            let first = mesh.vertices.len() as u32;
+            for s in 0..=steps {
+                let f = s as f32 / steps as f32;
+                let theta = base + f * self.turns * TAU;
+                let r = f * radius;
+                mesh.colored_vertex(polar(center, r, theta - half_width), color);
+                mesh.colored_vertex(polar(center, r, theta + half_width), color);
+            }
+            for s in 0..steps {
+                let i = first + s * 2;
+                mesh.add_triangle(i, i + 1, i + 2);
+                mesh.add_triangle(i + 1, i + 3, i + 2);
+            }
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.arms, 1..=12).text("Arms"));
+        ui.add(egui::Slider::new(&mut self.turns, 0.0..=4.0).text("Turns"));
+        ui.checkbox(&mut self.clockwise, "Clockwise");
+    }
+}
+
+fn polar(center: Pos2, r: f32, theta: f32) -> Pos2 {
+    center + Vec2::angled(theta) * r
+}
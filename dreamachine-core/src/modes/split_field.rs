@@ -0,0 +1,18 @@
+use egui::{Painter, Rect};
+
+use super::{ModeParams, VisualMode};
+
+/// Left and right halves of the screen flickering in antiphase.
+pub struct SplitField;
+
+impl VisualMode for SplitField {
+    fn name(&self) -> &'static str {
+        "Split Field"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, params: &ModeParams) {
+        let (left, right) = rect.split_left_right_at_fraction(0.5);
+        painter.rect_filled(left, 0.0, params.mix(params.level));
+        painter.rect_filled(right, 0.0, params.mix(params.antiphase_level()));
+    }
+}
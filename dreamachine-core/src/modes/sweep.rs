@@ -0,0 +1,106 @@
+use egui::{Mesh, Painter, Rect, Shape, Ui, Vec2};
+
+use super::{ModeParams, VisualMode};
+
+/// Named sweep angles, in degrees clockwise from left → right.
+const DIRECTIONS: [(&str, f32); 6] = [
+    ("Left → right", 0.0),
+    ("Right → left", 180.0),
+    ("Top → bottom", 90.0),
+    ("Bottom → top", 270.0),
+    ("Diagonal ↘", 45.0),
+    ("Diagonal ↗", 315.0),
+];
+
+/// Sweep beams travelling at any angle, evenly spaced along the sweep cycle.
+/// Beams either wrap around or bounce back at the edges.
+pub struct Sweep {
+    beams: u32,
+    angle_deg: f32, // direction of travel, clockwise from left → right
+    bounce: bool,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self {
+            beams: 1,
+            angle_deg: 0.0,
+            bounce: false,
+        }
+    }
+}
+
+impl VisualMode for Sweep {
+    fn name(&self) -> &'static str {
+        "Sweep"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+
+        let painter = painter.with_clip_rect(rect);
+        let direction = Vec2::angled(self.angle_deg.to_radians());
+        let period = 1.0 + params.beam_width_norm;
+        // bouncing beams cover the same distance there and back
+        let cycle = if self.bounce { 2.0 * period } else { period };
+        for beam in 0..self.beams {
+            let offset = beam as f32 * cycle / self.beams as f32;
+            let mut tmod = (t * params.sweep_speed + offset) % cycle;
+            if tmod > period {
+                tmod = cycle - tmod;
+            }
+            let center_norm = tmod - params.beam_width_norm * 0.5;
+            paint_beam(&painter, rect, center_norm, direction, params);
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.beams, 1..=8).text("Beams"));
+        ui.add(
+            egui::Slider::new(&mut self.angle_deg, 0.0..=359.0)
+                .text("Direction")
+                .suffix("°"),
+        );
+        ui.horizontal_wrapped(|ui| {
+            for (name, angle) in DIRECTIONS {
+                if ui.selectable_label(self.angle_deg == angle, name).clicked() {
+                    self.angle_deg = angle;
+                }
+            }
+        });
+        ui.checkbox(&mut self.bounce, "Bounce at the edges");
+    }
+}
+
+/// One soft-edged beam crossing `rect` along `direction`, centred
+/// `center_norm` of the way across the screen's extent in that direction.
+fn paint_beam(
+    painter: &Painter,
+    rect: Rect,
+    center_norm: f32,
+    direction: Vec2,
+    params: &ModeParams,
+) {
+    let across = direction.rot90();
+    // length of the screen measured along the direction of travel
+    let extent = rect.width() * direction.x.abs() + rect.height() * direction.y.abs();
+    let reach = rect.size().length() * 0.5;
+    let c = rect.center() + direction * (center_norm - 0.5) * extent;
+
+    let beam_w = extent * params.beam_width_norm;
+    let slices = 60;
+    let mut mesh = Mesh::default();
+    for i in 0..=slices {
+        let f = i as f32 / slices as f32;
+        let level = 1.0 - (f - 0.5).abs() * 2.0;
+        let mid = c + direction * (f - 0.5) * beam_w;
+        mesh.colored_vertex(mid - across * reach, params.mix(level));
+        mesh.colored_vertex(mid + across * reach, params.mix(level));
+    }
+    for i in 0..slices {
+        let k = 2 * i;
+        mesh.add_triangle(k, k + 1, k + 2);
+        mesh.add_triangle(k + 1, k + 3, k + 2);
+    }
+    painter.add(Shape::mesh(mesh));
+}
@@ -0,0 +1,55 @@
+use egui::{Align2, Color32, FontId, Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+
+/// Flashes a word or glyph on the off colour at the session frequency.
+pub struct TextFlash {
+    text: String,
+    size: f32, // fraction of the screen height
+    color: Color32,
+    position: [f32; 2], // 0..1 across and down the screen
+}
+
+impl Default for TextFlash {
+    fn default() -> Self {
+        Self {
+            text: "☀".to_owned(),
+            size: 0.3,
+            color: Color32::WHITE,
+            position: [0.5, 0.5],
+        }
+    }
+}
+
+impl VisualMode for TextFlash {
+    fn name(&self) -> &'static str {
+        "Text Flash"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, _t: f32, params: &ModeParams) {
+        painter.rect_filled(rect, 0.0, params.off_color);
+        let color = params.off_color.lerp_to_gamma(self.color, params.level);
+        let pos = rect.lerp_inside(self.position.into());
+        painter.with_clip_rect(rect).text(
+            pos,
+            Align2::CENTER_CENTER,
+            &self.text,
+            FontId::proportional(self.size * rect.height()),
+            color,
+        );
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Text");
+            ui.text_edit_singleline(&mut self.text);
+        });
+        ui.add(egui::Slider::new(&mut self.size, 0.02..=1.0).text("Size"));
+        ui.horizontal(|ui| {
+            ui.color_edit_button_srgba(&mut self.color);
+            ui.label("Text color");
+        });
+        ui.add(egui::Slider::new(&mut self.position[0], 0.0..=1.0).text("Horizontal position"));
+        ui.add(egui::Slider::new(&mut self.position[1], 0.0..=1.0).text("Vertical position"));
+    }
+}
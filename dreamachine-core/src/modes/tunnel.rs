@@ -0,0 +1,55 @@
+use egui::{Painter, Rect, Ui};
+
+use super::{ModeParams, VisualMode};
+
+/// Nested rings rushing out of the centre, one new ring per flicker cycle.
+pub struct Tunnel {
+    rings: u32,
+    circles: bool,
+}
+
+impl Default for Tunnel {
+    fn default() -> Self {
+        Self {
+            rings: 12,
+            circles: true,
+        }
+    }
+}
+
+impl VisualMode for Tunnel {
+    fn name(&self) -> &'static str {
+        "Tunnel"
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        let cycles = t * params.frequency_hz;
+        let spawned = cycles.floor() as i64;
+        let age = cycles.fract();
+        let center = rect.center();
+        let max_r = rect.width().hypot(rect.height()) * 0.5;
+
+        painter.rect_filled(rect, 0.0, params.off_color);
+        // outermost (oldest) first so younger rings paint over it
+        for j in (0..self.rings).rev() {
+            let u = (j as f32 + age) / self.rings as f32;
+            let size = max_r * u * u; // quadratic growth reads as perspective
+            let on = (spawned - j as i64).rem_euclid(2) == 0;
+            let color = params.mix(if on { 1.0 } else { 0.0 });
+            if self.circles {
+                painter.circle_filled(center, size, color);
+            } else {
+                let half = rect.size() / max_r * size;
+                painter.rect_filled(Rect::from_center_size(center, half * 2.0), 0.0, color);
+            }
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.rings, 2..=40).text("Rings"));
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.circles, true, "Circles");
+            ui.radio_value(&mut self.circles, false, "Rectangles");
+        });
+    }
+}
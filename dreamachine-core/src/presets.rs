@@ -0,0 +1,54 @@
+//! Built-in programs for the classic brainwave bands.
+
+use crate::program::{Program, Stage, Transition};
+
+/// Every bundled preset, from slowest to fastest band.
+pub fn all() -> Vec<Program> {
+    vec![
+        Program {
+            name: "Delta Sleep".into(),
+            stages: vec![
+                stage("Flash", 10.0, 3.0, 10.0, Transition::Smooth),
+                stage("Flash", 3.0, 2.0, 20.0, Transition::Linear),
+            ],
+        },
+        Program {
+            name: "Theta Meditation".into(),
+            stages: vec![
+                stage("Sweep", 10.0, 6.0, 5.0, Transition::Smooth),
+                stage("Sweep", 6.0, 6.0, 20.0, Transition::Hold),
+            ],
+        },
+        Program {
+            name: "Alpha Relaxation".into(),
+            stages: vec![
+                stage("Lighthouse", 12.0, 10.0, 3.0, Transition::Linear),
+                stage("Lighthouse", 10.0, 10.0, 15.0, Transition::Hold),
+            ],
+        },
+        Program {
+            name: "Beta Focus".into(),
+            stages: vec![
+                stage("Flash", 10.0, 18.0, 5.0, Transition::Linear),
+                stage("Flash", 18.0, 18.0, 15.0, Transition::Hold),
+            ],
+        },
+        Program {
+            name: "Gamma".into(),
+            stages: vec![
+                stage("Flash", 30.0, 40.0, 2.0, Transition::Linear),
+                stage("Flash", 40.0, 40.0, 10.0, Transition::Hold),
+            ],
+        },
+    ]
+}
+
+fn stage(mode: &str, start_hz: f32, end_hz: f32, minutes: f32, transition: Transition) -> Stage {
+    Stage {
+        mode: mode.into(),
+        start_hz,
+        end_hz,
+        duration_secs: minutes * 60.0,
+        transition,
+    }
+}
@@ -0,0 +1,278 @@
+//! Multi-stage session programs.
+//!
+//! A program is a list of stages played back to back. Each stage picks a mode
+//! and moves the frequency from `start_hz` to `end_hz` over its duration.
+//!
+//! Programs are stored as TOML or JSON, chosen by file extension:
+//!
+//! ```toml
+//! name = "Descent"
+//!
+//! [[stages]]
+//! mode = "Flash"
+//! start_hz = 13.0
+//! end_hz = 8.0
+//! duration_secs = 900.0
+//! transition = "linear"
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::flicker::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+
+/// The longest a stage, or the whole program, may run: a day.
+pub const MAX_DURATION_SECS: f32 = 24.0 * 60.0 * 60.0;
+
+/// How the frequency moves from `start_hz` to `end_hz` within a stage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transition {
+    /// Stay at `start_hz` for the whole stage.
+    Hold,
+    #[default]
+    Linear,
+    /// Ease in and out of the ramp.
+    Smooth,
+}
+
+impl Transition {
+    pub const ALL: [Transition; 3] = [Transition::Hold, Transition::Linear, Transition::Smooth];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Transition::Hold => "Hold",
+            Transition::Linear => "Linear",
+            Transition::Smooth => "Smooth",
+        }
+    }
+
+    /// Map stage progress `t` in `0..=1` to ramp progress in `0..=1`.
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Transition::Hold => 0.0,
+            Transition::Linear => t,
+            Transition::Smooth => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stage {
+    /// Name of the visual mode, as returned by `VisualMode::name`.
+    pub mode: String,
+    pub start_hz: f32,
+    pub end_hz: f32,
+    pub duration_secs: f32,
+    #[serde(default)]
+    pub transition: Transition,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Program {
+    #[serde(default)]
+    pub name: String,
+    pub stages: Vec<Stage>,
+}
+
+/// Where a program is at a given moment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgramPoint<'a> {
+    pub stage: usize,
+    pub mode: &'a str,
+    pub frequency_hz: f32,
+}
+
+impl Program {
+    /// Read a program from a `.toml` or `.json` file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let program = if is_json(path) {
+            serde_json::from_str(&text)?
+        } else {
+            toml::from_str(&text)?
+        };
+        Ok(program)
+    }
+
+    /// Write the program as TOML, or JSON if `path` ends in `.json`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = if is_json(path) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            toml::to_string_pretty(self)?
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Everything that would make the program misbehave, one message per
+    /// problem. Empty if the program is fine. `modes` names the modes there
+    /// are to run it with.
+    pub fn validate(&self, modes: &[&str]) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.stages.is_empty() {
+            problems.push("the program has no stages".to_string());
+        }
+        let hz_range = MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ;
+        for (i, stage) in self.stages.iter().enumerate() {
+            let n = i + 1;
+            if !modes.contains(&stage.mode.as_str()) {
+                match modes
+                    .iter()
+                    .find(|name| name.eq_ignore_ascii_case(&stage.mode))
+                {
+                    Some(name) => problems.push(format!(
+                        "stage {n}: unknown mode \"{}\"; did you mean \"{name}\"?",
+                        stage.mode
+                    )),
+                    None => problems.push(format!("stage {n}: unknown mode \"{}\"", stage.mode)),
+                }
+            }
+            for (field, hz) in [("start_hz", stage.start_hz), ("end_hz", stage.end_hz)] {
+                if !hz_range.contains(&hz) {
+                    problems.push(format!(
+                        "stage {n}: {field} = {hz} is outside {MIN_FREQUENCY_HZ}..={MAX_FREQUENCY_HZ} Hz"
+                    ));
+                }
+            }
+            if !(stage.duration_secs.is_finite() && stage.duration_secs > 0.0) {
+                problems.push(format!(
+                    "stage {n}: duration_secs = {} must be a positive number of seconds",
+                    stage.duration_secs
+                ));
+            } else if stage.duration_secs > MAX_DURATION_SECS {
+                problems.push(format!(
+                    "stage {n}: duration_secs = {} is longer than {MAX_DURATION_SECS} seconds",
+                    stage.duration_secs
+                ));
+            }
+        }
+        let total: f32 = self.stages.iter().map(|s| s.duration_secs.max(0.0)).sum();
+        if total > MAX_DURATION_SECS {
+            problems.push(format!(
+                "the stages add up to {total} seconds, longer than {MAX_DURATION_SECS}"
+            ));
+        }
+        problems
+    }
+
+    /// How long the program runs; `Duration::MAX` if the stages add up to
+    /// more than a `Duration` can hold.
+    pub fn total_duration(&self) -> Duration {
+        let secs = self.stages.iter().map(|s| s.duration_secs.max(0.0)).sum();
+        Duration::try_from_secs_f32(secs).unwrap_or(Duration::MAX)
+    }
+
+    /// Evaluate the program `elapsed` into playback, or `None` once it is over.
+    pub fn sample(&self, elapsed: Duration) -> Option<ProgramPoint<'_>> {
+        let elapsed = elapsed.as_secs_f32();
+        let mut start = 0.0;
+        for (i, stage) in self.stages.iter().enumerate() {
+            let end = start + stage.duration_secs.max(0.0);
+            if elapsed < end {
+                let t = (elapsed - start) / stage.duration_secs;
+                let k = stage.transition.apply(t);
+                return Some(ProgramPoint {
+                    stage: i,
+                    mode: &stage.mode,
+                    frequency_hz: stage.start_hz + (stage.end_hz - stage.start_hz) * k,
+                });
+            }
+            start = end;
+        }
+        None
+    }
+}
+
+pub(crate) fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    fn program() -> Program {
+        Program {
+            name: "Test".into(),
+            stages: vec![
+                Stage {
+                    mode: "Flash".into(),
+                    start_hz: 10.0,
+                    end_hz: 20.0,
+                    duration_secs: 10.0,
+                    transition: Transition::Linear,
+                },
+                Stage {
+                    mode: "Sweep".into(),
+                    start_hz: 8.0,
+                    end_hz: 4.0,
+                    duration_secs: 5.0,
+                    transition: Transition::Hold,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn steps_through_the_stages() {
+        let program = program();
+        let clock = ManualClock::default();
+        let start = clock.now();
+        let mut stages = Vec::new();
+        while let Some(point) = program.sample(clock.now().duration_since(start)) {
+            if stages.last() != Some(&point.stage) {
+                stages.push(point.stage);
+            }
+            clock.advance(Duration::from_millis(100));
+        }
+        assert_eq!(stages, [0, 1]);
+        let ended = clock.now().duration_since(start);
+        assert!(ended >= program.total_duration());
+        assert!(ended < program.total_duration() + Duration::from_millis(100));
+    }
+
+    #[test]
+    fn ramps_within_a_stage() {
+        let program = program();
+        let at = |secs: f32| program.sample(Duration::from_secs_f32(secs)).unwrap();
+        assert_eq!(at(0.0).frequency_hz, 10.0);
+        assert!((at(5.0).frequency_hz - 15.0).abs() < 1e-4);
+        assert_eq!(at(5.0).mode, "Flash");
+        assert_eq!(at(10.0).stage, 1);
+        assert_eq!(at(10.0).frequency_hz, 8.0);
+        assert_eq!(at(14.9).frequency_hz, 8.0);
+        assert!(program.sample(Duration::from_secs(15)).is_none());
+    }
+
+    #[test]
+    fn validates_against_the_given_modes() {
+        let program = program();
+        assert!(program.validate(&["Flash", "Sweep"]).is_empty());
+        let problems = program.validate(&["Flash", "sweep"]);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("did you mean \"sweep\""));
+    }
+
+    #[test]
+    fn rejects_overlong_durations() {
+        let mut program = program();
+        program.stages[0].duration_secs = 1e30;
+        assert_eq!(program.total_duration(), Duration::MAX);
+        let problems = program.validate(&["Flash", "Sweep"]);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("stage 1:"));
+
+        program.stages[0].duration_secs = MAX_DURATION_SECS;
+        let problems = program.validate(&["Flash", "Sweep"]);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("the stages add up"));
+    }
+}
@@ -0,0 +1,46 @@
+//! Flicker frequencies a display can actually show.
+//!
+//! At a refresh rate of `refresh_hz` every cycle lasts a whole number of
+//! frames, so only `refresh_hz / n` comes out evenly: 60 / 6 = 10 Hz,
+//! 60 / 7 ≈ 8.57 Hz. Anything in between alternates between neighbouring
+//! cycle lengths and beats.
+
+use std::ops::RangeInclusive;
+
+/// How far from a whole number of frames per cycle still counts as even.
+const TOLERANCE: f32 = 1e-3;
+
+fn frames_per_cycle(hz: f32, refresh_hz: f32) -> f32 {
+    refresh_hz / hz.max(f32::EPSILON)
+}
+
+pub fn is_representable(hz: f32, refresh_hz: f32) -> bool {
+    let n = frames_per_cycle(hz, refresh_hz);
+    (n - n.round()).abs() < TOLERANCE
+}
+
+/// The nearest representable frequencies at or below and at or above `hz`.
+pub fn neighbours(hz: f32, refresh_hz: f32) -> (f32, f32) {
+    let n = frames_per_cycle(hz, refresh_hz);
+    if (n - n.round()).abs() < TOLERANCE {
+        let even = refresh_hz / n.round().max(1.0);
+        return (even, even);
+    }
+    (refresh_hz / n.ceil(), refresh_hz / n.floor().max(1.0))
+}
+
+/// The representable frequency within `range` nearest to `hz`, or `hz`
+/// itself if neither neighbour is in range. Depends only on `hz`, so asking
+/// for the same frequency again gives the same answer.
+pub fn snap(hz: f32, refresh_hz: f32, range: RangeInclusive<f32>) -> f32 {
+    let (below, above) = neighbours(hz, refresh_hz);
+    let nearest = if hz - below <= above - hz {
+        [below, above]
+    } else {
+        [above, below]
+    };
+    nearest
+        .into_iter()
+        .find(|even| range.contains(even))
+        .unwrap_or(hz)
+}
@@ -0,0 +1,83 @@
+//! The JSON protocol for controlling a session from another device.
+//!
+//! Each request is an object tagged by `command`, e.g.
+//! `{"command": "set_frequency", "hz": 10}` or `{"command": "start"}`. The
+//! reply is the status after the request was handled, or an error:
+//! `{"ok": true, "status": {...}}`, `{"ok": false, "error": "..."}`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::program::Program;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Start,
+    Stop,
+    SetFrequency {
+        hz: f32,
+    },
+    /// Mode by name, ignoring case and punctuation.
+    SetMode {
+        mode: String,
+    },
+    /// Run `program` from its first stage, starting a session if needed.
+    LoadProgram {
+        program: Program,
+    },
+    /// Start a built-in program or a saved preset by name, ignoring case
+    /// and punctuation.
+    StartPreset {
+        preset: String,
+    },
+}
+
+impl Request {
+    /// Parse a request, with serde's message on failure.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub flashing: bool,
+    /// True during the countdown before the first flash.
+    pub counting_down: bool,
+    pub mode: String,
+    pub frequency_hz: f32,
+    /// Name of the running program, if any.
+    pub program: Option<String>,
+    pub elapsed_secs: f32,
+    /// Seconds left in a timed session or program.
+    pub remaining_secs: Option<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Response {
+    Status { ok: bool, status: Status },
+    Error { ok: bool, error: String },
+}
+
+impl Response {
+    pub fn status(status: Status) -> Self {
+        Response::Status { ok: true, status }
+    }
+
+    pub fn error(error: impl Into<String>) -> Self {
+        Response::Error {
+            ok: false,
+            error: error.into(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Response::Status { .. })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("responses serialize")
+    }
+}
@@ -0,0 +1,339 @@
+//! Headless rendering: painting into an image instead of a window, with
+//! time stepped by hand, for exports and for checking modes frame by frame.
+//!
+//! Shapes go through egui's own tessellator and are then rasterized on the
+//! CPU, so a frame looks like it does on screen, anti-aliasing included.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use egui::epaint::{ClippedPrimitive, Primitive, Vertex};
+use egui::{
+    Color32, ColorImage, Context, ImageData, LayerId, Painter, Pos2, RawInput, Rect, TextureId,
+    Vec2,
+};
+
+use crate::flicker::Flicker;
+use crate::modes::{ModeParams, VisualMode};
+
+/// Paints frames of a fixed size, one point per pixel.
+pub struct OfflineRenderer {
+    ctx: Context,
+    size: [usize; 2],
+    textures: HashMap<TextureId, ColorImage>,
+}
+
+impl OfflineRenderer {
+    pub fn new(size: [usize; 2]) -> Self {
+        Self {
+            ctx: Context::default(),
+            size,
+            textures: HashMap::new(),
+        }
+    }
+
+    pub fn size(&self) -> [usize; 2] {
+        self.size
+    }
+
+    /// Run `paint` over the whole frame and rasterize the result onto
+    /// black.
+    pub fn render(&mut self, mut paint: impl FnMut(&Painter, Rect)) -> ColorImage {
+        let [w, h] = self.size;
+        let rect = Rect::from_min_size(Pos2::ZERO, Vec2::new(w as f32, h as f32));
+        let input = RawInput {
+            screen_rect: Some(rect),
+            ..Default::default()
+        };
+        let output = self.ctx.run(input, |ctx| {
+            paint(&ctx.layer_painter(LayerId::background()), rect);
+        });
+        for (id, delta) in output.textures_delta.set {
+            let ImageData::Color(image) = delta.image;
+            match (delta.pos, self.textures.get_mut(&id)) {
+                (Some([x0, y0]), Some(texture)) => {
+                    for y in 0..image.size[1] {
+                        for x in 0..image.size[0] {
+                            texture.pixels[(y0 + y) * texture.size[0] + x0 + x] =
+                                image.pixels[y * image.size[0] + x];
+                        }
+                    }
+                }
+                _ => {
+                    self.textures.insert(id, (*image).clone());
+                }
+            }
+        }
+
+        let mut frame = ColorImage::new(self.size, vec![Color32::BLACK; w * h]);
+        let primitives = self.ctx.tessellate(output.shapes, 1.0);
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &primitives
+        {
+            if let Primitive::Mesh(mesh) = primitive {
+                // textures loaded into another context, such as the window's,
+                // don't exist here
+                let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                    continue;
+                };
+                for tri in mesh.indices.chunks_exact(3) {
+                    let v = [tri[0], tri[1], tri[2]].map(|i| &mesh.vertices[i as usize]);
+                    triangle(&mut frame, *clip_rect, texture, v);
+                }
+            }
+        }
+        for id in output.textures_delta.free {
+            self.textures.remove(&id);
+        }
+        frame
+    }
+
+    /// Render `frames` frames of `mode` at `fps`, starting from the dark
+    /// start of the cycle. The flicker is stepped by exactly one frame
+    /// period each time, so the same inputs always give the same frames.
+    /// Everything in `params` besides the flicker state is used as is.
+    pub fn render_mode(
+        &mut self,
+        mode: &mut dyn VisualMode,
+        flicker: &mut Flicker,
+        params: &ModeParams,
+        fps: f32,
+        frames: usize,
+        mut frame: impl FnMut(usize, ColorImage),
+    ) {
+        let start = Instant::now();
+        flicker.reset(start);
+        for n in 0..frames {
+            let t = n as f64 / fps as f64;
+            flicker.update(start + Duration::from_secs_f64(t));
+            let params = ModeParams {
+                frequency_hz: flicker.frequency_hz(),
+                level: flicker.level(),
+                phase: flicker.phase(),
+                waveform: flicker.waveform(),
+                envelope: flicker.envelope(),
+                show_white: flicker.show_white(),
+                ..*params
+            };
+            let image = self.render(|painter, rect| mode.paint(painter, rect, t as f32, &params));
+            frame(n, image);
+        }
+    }
+}
+
+/// Twice the signed area of `a b p`.
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Fill one triangle, blending premultiplied colours over `frame` in gamma
+/// space the way egui's own renderers do.
+fn triangle(frame: &mut ColorImage, clip: Rect, texture: &ColorImage, v: [&Vertex; 3]) {
+    let area = edge(v[0].pos, v[1].pos, v[2].pos);
+    if area == 0.0 {
+        return;
+    }
+    let [w, h] = frame.size;
+    // bounding box, within the clip rect and the frame
+    let (xs, ys) = (v.map(|v| v.pos.x), v.map(|v| v.pos.y));
+    let min = |c: [f32; 3]| c.into_iter().fold(f32::INFINITY, f32::min);
+    let max = |c: [f32; 3]| c.into_iter().fold(f32::NEG_INFINITY, f32::max);
+    let x0 = min(xs).max(clip.min.x).max(0.0).floor() as usize;
+    let y0 = min(ys).max(clip.min.y).max(0.0).floor() as usize;
+    let x1 = max(xs).min(clip.max.x).min(w as f32).ceil() as usize;
+    let y1 = max(ys).min(clip.max.y).min(h as f32).ceil() as usize;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let b0 = edge(v[1].pos, v[2].pos, p) / area;
+            let b1 = edge(v[2].pos, v[0].pos, p) / area;
+            let b2 = 1.0 - b0 - b1;
+            if b0 < 0.0 || b1 < 0.0 || b2 < 0.0 {
+                continue;
+            }
+            let mix = |c: fn(&Vertex) -> f32| b0 * c(v[0]) + b1 * c(v[1]) + b2 * c(v[2]);
+            let mut src = [
+                mix(|v| v.color.r() as f32),
+                mix(|v| v.color.g() as f32),
+                mix(|v| v.color.b() as f32),
+                mix(|v| v.color.a() as f32),
+            ];
+            let texel = sample(texture, Pos2::new(mix(|v| v.uv.x), mix(|v| v.uv.y)));
+            for (s, t) in src.iter_mut().zip(texel.to_array()) {
+                *s *= t as f32 / 255.0;
+            }
+            let dst = &mut frame.pixels[y * w + x];
+            let keep = 1.0 - src[3] / 255.0;
+            let [r, g, b, a] = dst.to_array().map(|c| c as f32 * keep);
+            let out = |s: f32, d: f32| (s + d).round().clamp(0.0, 255.0) as u8;
+            *dst = Color32::from_rgba_premultiplied(
+                out(src[0], r),
+                out(src[1], g),
+                out(src[2], b),
+                out(src[3], a),
+            );
+        }
+    }
+}
+
+/// Nearest texel at normalized `uv`.
+fn sample(texture: &ColorImage, uv: Pos2) -> Color32 {
+    let [w, h] = texture.size;
+    let x = ((uv.x * w as f32) as usize).min(w.saturating_sub(1));
+    let y = ((uv.y * h as f32) as usize).min(h.saturating_sub(1));
+    texture
+        .pixels
+        .get(y * w + x)
+        .copied()
+        .unwrap_or(Color32::WHITE)
+}
+
+// Golden images: every mode rendered at a fixed moment and size, compared
+// with the references in `tests/golden`. After a deliberate change to a
+// mode, run with `DREAMACHINE_BLESS=1` to write new references and check
+// them in.
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::BufWriter;
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::envelope::Envelope;
+    use crate::grading::Grading;
+    use crate::modes;
+    use crate::waveform::Waveform;
+
+    const SIZE: [usize; 2] = [64, 48];
+    const FPS: f32 = 60.0;
+    /// At 10 Hz this is two thirds into a cycle, in the bright half.
+    const FRAME: usize = 22;
+    /// Per-channel difference allowed, for float rounding in the
+    /// tessellator.
+    const TOLERANCE: u8 = 2;
+
+    fn reference(name: &str) -> PathBuf {
+        let slug: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{slug}.png"))
+    }
+
+    fn render(mode: &mut dyn VisualMode) -> ColorImage {
+        let params = ModeParams {
+            frequency_hz: 10.0,
+            sweep_speed: 10.0,
+            beam_width_norm: 0.2,
+            level: 0.0,
+            phase: 0.0,
+            waveform: Waveform::default(),
+            envelope: Envelope::default(),
+            show_white: false,
+            on_color: Color32::WHITE,
+            off_color: Color32::BLACK,
+            grading: Grading::default(),
+        };
+        let mut renderer = OfflineRenderer::new(SIZE);
+        let mut flicker = Flicker::new(params.frequency_hz);
+        let mut last = None;
+        renderer.render_mode(mode, &mut flicker, &params, FPS, FRAME + 1, |_, image| {
+            last = Some(image);
+        });
+        last.expect("rendered at least one frame")
+    }
+
+    fn load(path: &Path) -> Option<ColorImage> {
+        let mut reader = png::Decoder::new(File::open(path).ok()?).read_info().ok()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).ok()?;
+        let pixels = buf[..info.line_size * info.height as usize]
+            .chunks_exact(4)
+            .map(|c| Color32::from_rgba_premultiplied(c[0], c[1], c[2], c[3]))
+            .collect();
+        Some(ColorImage::new(
+            [info.width as usize, info.height as usize],
+            pixels,
+        ))
+    }
+
+    fn save(path: &Path, image: &ColorImage) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = BufWriter::new(File::create(path).unwrap());
+        let [w, h] = image.size;
+        let mut encoder = png::Encoder::new(file, w as u32, h as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        let data: Vec<u8> = image.pixels.iter().flat_map(|c| c.to_array()).collect();
+        writer.write_image_data(&data).unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn differing_pixels(a: &ColorImage, b: &ColorImage) -> usize {
+        a.pixels
+            .iter()
+            .zip(&b.pixels)
+            .filter(|(a, b)| {
+                let (a, b) = (a.to_array(), b.to_array());
+                a.iter().zip(b).any(|(x, y)| x.abs_diff(y) > TOLERANCE)
+            })
+            .count()
+    }
+
+    #[test]
+    fn renders_are_repeatable() {
+        for (mut first, mut second) in modes::all().into_iter().zip(modes::all()) {
+            let (a, b) = (render(first.as_mut()), render(second.as_mut()));
+            assert!(
+                a.pixels == b.pixels,
+                "{} differs between runs",
+                first.name()
+            );
+        }
+    }
+
+    #[test]
+    fn modes_match_golden_images() {
+        let bless = env::var_os("DREAMACHINE_BLESS").is_some();
+        let mut failures = Vec::new();
+        for mut mode in modes::all() {
+            let image = render(mode.as_mut());
+            let path = reference(mode.name());
+            if bless {
+                save(&path, &image);
+                continue;
+            }
+            let name = mode.name();
+            match load(&path) {
+                None => failures.push(format!("{name}: no reference at {}", path.display())),
+                Some(expected) if expected.size != image.size => failures.push(format!(
+                    "{name}: reference is {:?}, rendered {:?}",
+                    expected.size, image.size
+                )),
+                Some(expected) => {
+                    let differ = differing_pixels(&expected, &image);
+                    if differ > 0 {
+                        failures.push(format!("{name}: {differ} pixels differ"));
+                    }
+                }
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "{}\nrun with DREAMACHINE_BLESS=1 to accept the new images",
+            failures.join("\n")
+        );
+    }
+}
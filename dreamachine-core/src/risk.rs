@@ -0,0 +1,162 @@
+//! Flash-risk analysis against the broadcast photosensitivity guidelines
+//! (ITU-R BT.1702, Ofcom, WCAG 2.x "three flashes").
+//!
+//! A stimulus is treated as harmful when it flashes more than three times a
+//! second, each flash is a large enough luminance change or a saturated red
+//! transition, and it covers more than a quarter of the screen. All three
+//! have to hold; any one of them alone is only a caution.
+
+use egui::Color32;
+
+/// More flashes per second than this is over the limit.
+pub const MAX_FLASHES_PER_SEC: f32 = 3.0;
+/// Relative luminance change that counts as a flash.
+pub const MIN_LUMINANCE_DELTA: f32 = 0.1;
+/// A change only counts if the darker state is below this luminance.
+pub const DARK_LUMINANCE: f32 = 0.8;
+/// Largest fraction of the screen that may flash.
+pub const MAX_AREA: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Risk {
+    Ok,
+    Caution,
+    Exceeds,
+}
+
+impl Risk {
+    pub fn name(self) -> &'static str {
+        match self {
+            Risk::Ok => "Within guidelines",
+            Risk::Caution => "Caution",
+            Risk::Exceeds => "Exceeds guidelines",
+        }
+    }
+}
+
+/// What is about to be shown.
+pub struct Stimulus {
+    pub frequency_hz: f32,
+    pub on_color: Color32,
+    pub off_color: Color32,
+    /// Fraction of the screen that flashes, `0..=1`.
+    pub area: f32,
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub value: String,
+    pub risk: Risk,
+}
+
+pub struct Report {
+    pub checks: Vec<Check>,
+    pub risk: Risk,
+}
+
+pub fn analyze(stimulus: &Stimulus) -> Report {
+    let (on, off) = (luminance(stimulus.on_color), luminance(stimulus.off_color));
+    let (dark, delta) = (on.min(off), (on - off).abs());
+    let luminance_risk = if delta >= MIN_LUMINANCE_DELTA && dark < DARK_LUMINANCE {
+        Risk::Exceeds
+    } else if delta >= MIN_LUMINANCE_DELTA / 2.0 {
+        Risk::Caution
+    } else {
+        Risk::Ok
+    };
+    let red_risk = if red_flash(stimulus.on_color, stimulus.off_color) {
+        Risk::Exceeds
+    } else {
+        Risk::Ok
+    };
+    let rate_risk = if stimulus.frequency_hz > MAX_FLASHES_PER_SEC {
+        Risk::Exceeds
+    } else if stimulus.frequency_hz > MAX_FLASHES_PER_SEC * 0.8 {
+        Risk::Caution
+    } else {
+        Risk::Ok
+    };
+    let area_risk = if stimulus.area > MAX_AREA {
+        Risk::Exceeds
+    } else if stimulus.area > MAX_AREA * 0.5 {
+        Risk::Caution
+    } else {
+        Risk::Ok
+    };
+
+    let flashes = luminance_risk.max(red_risk) == Risk::Exceeds;
+    let harmful = flashes && rate_risk == Risk::Exceeds && area_risk == Risk::Exceeds;
+    let checks = vec![
+        Check {
+            name: "Flash rate",
+            value: format!(
+                "{:.1} per second (limit {MAX_FLASHES_PER_SEC})",
+                stimulus.frequency_hz
+            ),
+            risk: rate_risk,
+        },
+        Check {
+            name: "Luminance change",
+            value: format!(
+                "{:.0}% (limit {:.0}%)",
+                delta * 100.0,
+                MIN_LUMINANCE_DELTA * 100.0
+            ),
+            risk: luminance_risk,
+        },
+        Check {
+            name: "Saturated red",
+            value: if red_risk == Risk::Exceeds {
+                "Yes"
+            } else {
+                "No"
+            }
+            .to_string(),
+            risk: red_risk,
+        },
+        Check {
+            name: "Flashing area",
+            value: format!(
+                "{:.0}% of the screen (limit {:.0}%)",
+                stimulus.area * 100.0,
+                MAX_AREA * 100.0
+            ),
+            risk: area_risk,
+        },
+    ];
+    let worst = checks.iter().map(|c| c.risk).max().unwrap_or(Risk::Ok);
+    Report {
+        checks,
+        risk: if harmful {
+            Risk::Exceeds
+        } else {
+            worst.min(Risk::Caution)
+        },
+    }
+}
+
+/// Linear-light value of an sRGB channel.
+fn linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// WCAG relative luminance, `0..=1`.
+fn luminance(c: Color32) -> f32 {
+    0.2126 * linear(c.r()) + 0.7152 * linear(c.g()) + 0.0722 * linear(c.b())
+}
+
+/// WCAG's red flash test: one side is saturated red and the red excess
+/// changes by more than 20.
+fn red_flash(a: Color32, b: Color32) -> bool {
+    let saturated = |c: Color32| {
+        let sum = c.r() as f32 + c.g() as f32 + c.b() as f32;
+        sum > 0.0 && c.r() as f32 / sum >= 0.8
+    };
+    let excess = |c: Color32| ((linear(c.r()) - linear(c.g()) - linear(c.b())) * 320.0).max(0.0);
+    (saturated(a) || saturated(b)) && (excess(a) - excess(b)).abs() > 20.0
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+/// Optional session length with a soft start from black and a fade-to-black
+/// over its final stretch.
+#[derive(Clone, Debug)]
+pub struct SessionTimer {
+    /// `None` runs until stopped by hand.
+    pub duration: Option<Duration>,
+    pub fade: Duration,
+    /// Time to ramp up from black at the start.
+    pub ramp: Duration,
+}
+
+impl Default for SessionTimer {
+    fn default() -> Self {
+        Self {
+            duration: None,
+            fade: Duration::from_secs(30),
+            ramp: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SessionTimer {
+    /// Output level in `0..=1` at `elapsed`: linearly up from black over the
+    /// ramp, full until the fade starts, then linearly down to black at the
+    /// end of the session.
+    pub fn intensity(&self, elapsed: Duration) -> f32 {
+        let ramp = if self.ramp.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f32() / self.ramp.as_secs_f32()).min(1.0)
+        };
+        let Some(remaining) = self.remaining(elapsed) else {
+            return ramp;
+        };
+        let fade = if self.fade.is_zero() {
+            if remaining.is_zero() { 0.0 } else { 1.0 }
+        } else {
+            (remaining.as_secs_f32() / self.fade.as_secs_f32()).min(1.0)
+        };
+        ramp.min(fade)
+    }
+
+    pub fn remaining(&self, elapsed: Duration) -> Option<Duration> {
+        self.duration.map(|d| d.saturating_sub(elapsed))
+    }
+
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        self.remaining(elapsed).is_some_and(|r| r.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    fn elapsed(clock: &ManualClock, start: Instant) -> Duration {
+        clock.now().duration_since(start)
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn ramps_up_and_fades_out() {
+        let timer = SessionTimer {
+            duration: Some(Duration::from_secs(600)),
+            fade: Duration::from_secs(30),
+            ramp: Duration::from_secs(30),
+        };
+        let clock = ManualClock::default();
+        let start = clock.now();
+        assert!(close(timer.intensity(elapsed(&clock, start)), 0.0));
+        clock.advance(Duration::from_secs(15));
+        assert!(close(timer.intensity(elapsed(&clock, start)), 0.5));
+        clock.advance(Duration::from_secs(15));
+        assert!(close(timer.intensity(elapsed(&clock, start)), 1.0));
+        clock.advance(Duration::from_secs(555));
+        assert!(close(timer.intensity(elapsed(&clock, start)), 0.5));
+        assert!(!timer.is_finished(elapsed(&clock, start)));
+        clock.advance(Duration::from_secs(15));
+        assert!(close(timer.intensity(elapsed(&clock, start)), 0.0));
+        assert!(timer.is_finished(elapsed(&clock, start)));
+        assert_eq!(
+            timer.remaining(elapsed(&clock, start)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn open_ended_never_finishes() {
+        let timer = SessionTimer::default();
+        let clock = ManualClock::default();
+        let start = clock.now();
+        clock.advance(Duration::from_secs(24 * 60 * 60));
+        assert!(close(timer.intensity(elapsed(&clock, start)), 1.0));
+        assert!(!timer.is_finished(elapsed(&clock, start)));
+        assert_eq!(timer.remaining(elapsed(&clock, start)), None);
+    }
+}
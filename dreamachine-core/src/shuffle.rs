@@ -0,0 +1,56 @@
+//! Switching between modes at random during a session.
+
+use std::time::Duration;
+
+use crate::rng::Rng;
+
+/// Picks a new mode from a pool every `interval`. The same seed always gives
+/// the same sequence of modes.
+pub struct Shuffle {
+    pub interval: Duration,
+    pub seed: u64,
+    /// Registry indices of the modes to choose from.
+    pub pool: Vec<usize>,
+    rng: Rng,
+    next_at: Duration,
+}
+
+impl Shuffle {
+    pub fn new(pool: Vec<usize>) -> Self {
+        let seed = 1;
+        let interval = Duration::from_secs(60);
+        Self {
+            interval,
+            seed,
+            pool,
+            rng: Rng::new(seed),
+            next_at: interval,
+        }
+    }
+
+    /// Start the sequence again from the seed.
+    pub fn restart(&mut self) {
+        self.rng = Rng::new(self.seed);
+        self.next_at = self.interval;
+    }
+
+    /// The mode to switch to, if `elapsed` has reached the next switch.
+    /// Never repeats `current` unless it is the only mode in the pool.
+    pub fn poll(&mut self, elapsed: Duration, current: usize) -> Option<usize> {
+        if elapsed < self.next_at || self.pool.is_empty() {
+            return None;
+        }
+        self.next_at = elapsed + self.interval;
+        let choices: Vec<usize> = self
+            .pool
+            .iter()
+            .copied()
+            .filter(|&m| m != current)
+            .collect();
+        if choices.is_empty() {
+            return None;
+        }
+        let pick = self.rng.range_u32(0, choices.len() as u32 - 1) as usize;
+        Some(choices[pick])
+    }
+}
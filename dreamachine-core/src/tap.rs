@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+/// A pause longer than this starts a new run of taps.
+const RESET_AFTER: Duration = Duration::from_secs(2);
+/// Only the most recent taps count, so the tempo can drift with the music.
+const MAX_TAPS: usize = 8;
+
+/// Frequency from the spacing of repeated taps.
+#[derive(Default)]
+pub struct TapTempo {
+    taps: Vec<Instant>,
+}
+
+impl TapTempo {
+    /// Register a tap at `now` and return the frequency implied by the mean
+    /// interval so far, once there are at least two taps.
+    pub fn tap(&mut self, now: Instant) -> Option<f32> {
+        if self
+            .taps
+            .last()
+            .is_some_and(|&last| now.duration_since(last) > RESET_AFTER)
+        {
+            self.taps.clear();
+        }
+        self.taps.push(now);
+        if self.taps.len() > MAX_TAPS {
+            self.taps.remove(0);
+        }
+        let (&first, &last) = (self.taps.first()?, self.taps.last()?);
+        let intervals = self.taps.len() - 1;
+        if intervals == 0 {
+            return None;
+        }
+        let mean = last.duration_since(first).as_secs_f32() / intervals as f32;
+        (mean > 0.0).then(|| 1.0 / mean)
+    }
+
+    /// Taps in the current run.
+    pub fn count(&self) -> usize {
+        self.taps.len()
+    }
+}
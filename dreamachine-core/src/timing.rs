@@ -0,0 +1,271 @@
+//! The flicker clock, run on its own thread so that a stalled UI (an open
+//! menu, a window being dragged) can't stall the entrainment signal.
+//!
+//! The thread advances the [`Flicker`] and drives anything that has to
+//! follow it exactly, such as the isochronic audio gate. The render loop
+//! only takes snapshots of it, at the time the frame will be on screen.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use thread_priority::{ThreadPriority, set_current_thread_priority};
+
+use crate::clock::Clock;
+use crate::envelope::Envelope;
+use crate::flicker::Flicker;
+use crate::waveform::Waveform;
+
+/// How often the thread advances the flicker while it is running; paused,
+/// it sleeps until woken.
+const TICK: Duration = Duration::from_millis(1);
+
+struct Shared {
+    flicker: Mutex<Flicker>,
+    running: AtomicBool,
+    quit: AtomicBool,
+}
+
+/// Owns the timing thread; dropping it stops the thread.
+pub struct Timing {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Timing {
+    /// Start the thread, reading the time from `clock`. `on_tick` is called
+    /// on it after every step with the flicker and whether it is running.
+    pub fn spawn(
+        clock: Arc<dyn Clock>,
+        flicker: Flicker,
+        mut on_tick: impl FnMut(&Flicker, bool) + Send + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            flicker: Mutex::new(flicker),
+            running: AtomicBool::new(false),
+            quit: AtomicBool::new(false),
+        });
+        let thread_shared = shared.clone();
+        let thread = thread::Builder::new()
+            .name("flicker".into())
+            .spawn(move || {
+                // best effort: raising the priority needs privileges on some
+                // platforms
+                let _ = set_current_thread_priority(ThreadPriority::Max);
+                while !thread_shared.quit.load(Ordering::Relaxed) {
+                    let running = thread_shared.running.load(Ordering::Relaxed);
+                    {
+                        let mut flicker = thread_shared.flicker.lock().unwrap();
+                        if running {
+                            flicker.update(clock.now());
+                        }
+                        on_tick(&flicker, running);
+                    }
+                    if running {
+                        thread::sleep(TICK);
+                    } else {
+                        // unparked by `set_running` and on drop
+                        thread::park();
+                    }
+                }
+            })
+            .expect("failed to spawn the timing thread");
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Start or pause advancing the flicker.
+    pub fn set_running(&self, running: bool) {
+        self.shared.running.store(running, Ordering::Relaxed);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+
+    /// The flicker as it will be at `when`.
+    pub fn snapshot(&self, when: Instant) -> Flicker {
+        self.shared.flicker.lock().unwrap().at(when)
+    }
+
+    /// Restart from the dark start of the cycle.
+    pub fn reset(&self, now: Instant) {
+        self.shared.flicker.lock().unwrap().reset(now);
+    }
+
+    pub fn frequency_hz(&self) -> f32 {
+        self.shared.flicker.lock().unwrap().frequency_hz()
+    }
+
+    pub fn set_frequency(&self, frequency_hz: f32) {
+        self.shared
+            .flicker
+            .lock()
+            .unwrap()
+            .set_frequency(frequency_hz);
+    }
+
+    pub fn waveform(&self) -> Waveform {
+        self.shared.flicker.lock().unwrap().waveform()
+    }
+
+    pub fn set_waveform(&self, waveform: Waveform) {
+        self.shared.flicker.lock().unwrap().set_waveform(waveform);
+    }
+
+    pub fn envelope(&self) -> Envelope {
+        self.shared.flicker.lock().unwrap().envelope()
+    }
+
+    pub fn set_envelope(&self, envelope: Envelope) {
+        self.shared.flicker.lock().unwrap().set_envelope(envelope);
+    }
+}
+
+impl Drop for Timing {
+    fn drop(&mut self) {
+        self.shared.quit.store(true, Ordering::Relaxed);
+        self.wake();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Predicts when the frame being drawn will be on screen.
+///
+/// The prediction steps in whole frames, so a frequency that divides the
+/// refresh rate gets the same number of frames in every cycle instead of
+/// aliasing with the jitter of the wall clock. It falls back to the wall
+/// clock when frames are dropped or the period changes.
+#[derive(Default)]
+pub struct FrameClock {
+    last: Option<Instant>,
+}
+
+impl FrameClock {
+    /// Presentation time of the frame drawn at `now`, `frame` being the
+    /// display's frame period.
+    pub fn present(&mut self, now: Instant, frame: Duration) -> Instant {
+        let predicted = now + frame;
+        let present = match self.last {
+            Some(last) => {
+                let scheduled = last + frame;
+                let drift = predicted
+                    .saturating_duration_since(scheduled)
+                    .max(scheduled.saturating_duration_since(predicted));
+                if drift > frame / 2 {
+                    predicted
+                } else {
+                    scheduled
+                }
+            }
+            None => predicted,
+        };
+        self.last = Some(present);
+        present
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn snapshots_follow_the_clock() {
+        let clock = Arc::new(ManualClock::default());
+        let timing = Timing::spawn(clock.clone(), Flicker::new(10.0), |_, _| {});
+        timing.reset(clock.now());
+        timing.set_running(true);
+        // the thread may or may not have caught up; snapshots don't depend
+        // on it
+        let mut flashes = 0;
+        let mut was_white = false;
+        for _ in 0..1000 {
+            clock.advance(Duration::from_millis(1));
+            let white = timing.snapshot(clock.now()).show_white();
+            if white && !was_white {
+                flashes += 1;
+            }
+            was_white = white;
+        }
+        assert_eq!(flashes, 10);
+    }
+
+    #[test]
+    fn the_thread_flickers_on_the_clock() {
+        let clock = Arc::new(ManualClock::default());
+        let (tx, rx) = std::sync::mpsc::channel();
+        let timing = Timing::spawn(
+            clock.clone(),
+            Flicker::new(10.0),
+            move |flicker, running| {
+                if running {
+                    let _ = tx.send(flicker.show_white());
+                }
+            },
+        );
+        timing.reset(clock.now());
+        timing.set_running(true);
+        let reaches = |white: bool| {
+            while let Ok(shown) = rx.recv_timeout(Duration::from_secs(1)) {
+                if shown == white {
+                    return true;
+                }
+            }
+            false
+        };
+        // dark for the first half of each 100 ms cycle
+        assert!(reaches(false));
+        clock.advance(Duration::from_millis(75));
+        assert!(reaches(true));
+        clock.advance(Duration::from_millis(50));
+        assert!(reaches(false));
+        clock.advance(Duration::from_millis(50));
+        assert!(reaches(true));
+    }
+
+    #[test]
+    fn ticks_only_while_running() {
+        let clock = Arc::new(ManualClock::default());
+        let (tx, rx) = std::sync::mpsc::channel();
+        let timing = Timing::spawn(clock, Flicker::new(10.0), move |_, running| {
+            let _ = tx.send(running);
+        });
+        thread::sleep(Duration::from_millis(50));
+        // one tick to report the pause, then asleep
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [false]);
+        timing.set_running(true);
+        thread::sleep(Duration::from_millis(50));
+        assert!(rx.try_iter().filter(|&running| running).count() > 1);
+        timing.set_running(false);
+        thread::sleep(Duration::from_millis(20));
+        rx.try_iter().count();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(rx.try_iter().count(), 0);
+        // dropping wakes the thread to quit rather than hanging
+        drop(timing);
+    }
+
+    #[test]
+    fn frame_clock_steps_in_whole_frames() {
+        let clock = ManualClock::default();
+        let frame = Duration::from_micros(16_667);
+        let mut frames = FrameClock::default();
+        let first = frames.present(clock.now(), frame);
+        // a little late, but within half a frame: stays on the grid
+        clock.advance(frame + Duration::from_millis(2));
+        assert_eq!(frames.present(clock.now(), frame), first + frame);
+        // a dropped frame: back to the wall clock
+        clock.advance(frame * 3);
+        let now = clock.now();
+        assert_eq!(frames.present(now, frame), now + frame);
+    }
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+/// Shape of the brightness curve over one flicker cycle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Waveform {
+    #[default]
+    Square,
+    Sine,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [
+        Waveform::Square,
+        Waveform::Sine,
+        Waveform::Triangle,
+        Waveform::Sawtooth,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Waveform::Square => "Square",
+            Waveform::Sine => "Sine",
+            Waveform::Triangle => "Triangle",
+            Waveform::Sawtooth => "Sawtooth",
+        }
+    }
+
+    /// Brightness in `0..=1` at `phase` (in cycles, `0..1`). Every waveform
+    /// starts dark at phase 0.
+    pub fn level(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => {
+                if phase >= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Waveform::Sine => 0.5 - 0.5 * (TAU * phase).cos(),
+            Waveform::Triangle => 1.0 - (2.0 * phase - 1.0).abs(),
+            Waveform::Sawtooth => phase,
+        }
+    }
+}
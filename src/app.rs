@@ -0,0 +1,2616 @@
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use dreamachine_core::audio::AudioSettings;
+use dreamachine_core::lights::LightFeed;
+use dreamachine_core::remote::{Request, Response, Status};
+use dreamachine_core::{
+    Clock, Compositor, Cycle, Exposure, Fixation, Flicker, FrameClock, Grading, History, IdleStop,
+    Journal, LoopFormat, Mask, ModeParams, OfflineRenderer, Program, Recorder, SessionTimer,
+    Shuffle, Stage, TapTempo, Timing, Transition, VisualMode, Waveform, compositor, modes, presets,
+    refresh, risk,
+};
+use eframe::{App, CreationContext, Frame, egui};
+use egui::Color32;
+use egui::containers::menu::MenuBar;
+use keepawake::KeepAwake;
+
+use crate::audio::AudioEngine;
+use crate::cli::{self, Cli};
+use crate::command::Command;
+use crate::config::{self, Config, Limits};
+use crate::diagnostics::Diagnostics;
+use crate::displays::{self, Display};
+use crate::error::Error;
+use crate::export::{ExportSettings, Rendered, VideoExport};
+use crate::hud::{Hud, HudInfo};
+use crate::inhibit;
+use crate::journal::{Answer, JournalPrompt};
+use crate::lights::Lights;
+use crate::midi::Midi;
+use crate::mqtt::Mqtt;
+use crate::program_editor;
+use crate::remote::Remote;
+use crate::settings::{
+    FrequencyGuard, RiskPolicy, SETTINGS_KEY, Settings, USER_PRESETS_KEY, UserPreset,
+};
+use crate::shader;
+use crate::stats;
+use crate::toasts::Toasts;
+
+const ROOT: egui::ViewportId = egui::ViewportId::ROOT;
+const WARNING_ACKNOWLEDGED_KEY: &str = "warning_acknowledged";
+/// Leaves kiosk mode and asks whether to quit.
+const KIOSK_EXIT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Q,
+);
+/// Idle time before the cursor is hidden over a fullscreen session.
+const CURSOR_HIDE_AFTER: Duration = Duration::from_secs(2);
+/// Repaint interval while nothing is flashing, enough for the rest timer and
+/// toasts; input repaints immediately anyway.
+const IDLE_REPAINT: Duration = Duration::from_millis(250);
+/// Sessions shorter than this aren't added to the history.
+const MIN_RECORDED: Duration = Duration::from_secs(30);
+/// Frame size of exported loops, small enough to share.
+const LOOP_SIZE: [usize; 2] = [480, 270];
+/// Wall time spent rendering an export per UI frame.
+const EXPORT_BUDGET: Duration = Duration::from_millis(50);
+
+pub struct DreamApp {
+    // blink mode
+    flashing: bool,
+    timing: Timing,
+    // the flicker as it will be when this frame is on screen
+    stimulus: Flicker,
+    frame_clock: FrameClock,
+    // held while flashing so the screen doesn't blank mid-session
+    keep_awake: Option<KeepAwake>,
+
+    // UI text
+    start_stop_text: String,
+
+    // visual modes
+    modes: Vec<Box<dyn VisualMode>>,
+    mode: usize,
+    mode_start: Instant,
+    // outgoing mode and its start time while a crossfade is running
+    previous_mode: Option<(usize, Instant)>,
+    crossfade: Duration,
+    compositor: Compositor,
+    shuffle: Shuffle,
+    shuffling: bool,
+    grading: Grading,
+    mask: Mask,
+    fixation: Fixation,
+    hud: Hud,
+    diagnostics: Diagnostics,
+    beam_width_norm: f32, // fraction of window width
+    sweep_speed: f32,     // cycles per second
+    on_color: Color32,
+    off_color: Color32,
+
+    tap_tempo: TapTempo,
+    user_presets: Vec<UserPreset>,
+    preset_name: String, // name for the next saved preset
+
+    // from the config file
+    limits: Limits,
+    guard: FrequencyGuard,
+    snap_to_refresh: bool,
+    // the frequency asked for, before clamping and snapping
+    requested_hz: f32,
+
+    // flash-risk analysis
+    risk_policy: RiskPolicy,
+    show_risk: bool,
+    aspect: f32, // of the stimulus area, for the mask coverage
+
+    // timed session
+    session: SessionTimer,
+    session_start: Instant,
+    countdown: Duration,
+    exposure: Exposure,
+    idle: IdleStop,
+    // set between pressing Start and the first flash
+    countdown_until: Option<Instant>,
+    history: History,
+    // where sessions are logged; `None` keeps them in memory only
+    history_path: Option<PathBuf>,
+    // the running session, for the history
+    recorder: Option<Recorder>,
+    show_stats: bool,
+    journal_prompt: bool,
+    journal: Option<JournalPrompt>,
+
+    // programs
+    program: Program,
+    running_program: Option<Program>,
+    show_program_editor: bool,
+
+    // video export
+    export_settings: ExportSettings,
+    export: Option<VideoExport>,
+
+    // audio
+    audio_settings: Arc<Mutex<AudioSettings>>,
+    audio: Option<AudioEngine>,
+
+    // opened once there is an egui context to wake
+    midi: Option<Midi>,
+    remote: Option<Remote>,
+    mqtt: Option<Mqtt>,
+
+    // external lights, fed from the timing thread and each frame
+    light_feed: Arc<Mutex<LightFeed>>,
+    lights: Option<Lights>,
+
+    // photosensitivity warning
+    warning_acknowledged: bool,
+    show_warning: bool,
+
+    confirm_quit: bool,
+    toasts: Toasts,
+    // launched with --autostart: quit once the session stops
+    autostart: bool,
+
+    fullscreen: bool,
+    borderless: bool, // maximised without decorations
+    always_on_top: bool,
+    kiosk: bool, // fullscreen and on top with the controls locked away
+    displays: Vec<Display>,
+    display: Option<String>,    // name of the monitor to flash on
+    window: Option<egui::Rect>, // last windowed geometry
+    separate_controls: bool,
+    span_displays: bool,
+    per_eye: bool,
+    eye_phase_offset_deg: f32,
+    eye_frequency_offset_hz: f32,
+    last_pointer_move: Instant,
+    // every timestamp comes from here rather than `Instant::now`
+    clock: Arc<dyn Clock>,
+}
+
+impl DreamApp {
+    fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        let audio_settings = Arc::new(Mutex::new(AudioSettings::default()));
+        // the isochronic tone follows the flicker from the timing thread
+        let gate_audio = audio_settings.clone();
+        let mut gate = false;
+        let light_feed = Arc::new(Mutex::new(LightFeed::new(Flicker::new(10.0))));
+        let tick_feed = light_feed.clone();
+        // ~10 Hz blink
+        let timing = Timing::spawn(
+            clock.clone(),
+            Flicker::new(10.0),
+            move |flicker, running| {
+                let open = running && flicker.show_white();
+                if open != gate {
+                    gate = open;
+                    gate_audio.lock().unwrap().gate = open;
+                }
+                let mut feed = tick_feed.lock().unwrap();
+                feed.flicker = flicker.clone();
+                feed.running = running;
+            },
+        );
+        Self {
+            flashing: false,
+            keep_awake: None,
+            stimulus: timing.snapshot(now),
+            timing,
+            frame_clock: FrameClock::default(),
+
+            start_stop_text: "Start".into(),
+            modes: modes::all(),
+            mode: 1, // Sweep
+            mode_start: now,
+            previous_mode: None,
+            crossfade: Duration::from_secs(2),
+            compositor: Compositor::default(),
+            shuffle: Shuffle::new((0..modes::all().len()).collect()),
+            shuffling: false,
+            grading: Grading::default(),
+            mask: Mask::default(),
+            fixation: Fixation::default(),
+            hud: Hud::default(),
+            diagnostics: Diagnostics::default(),
+            beam_width_norm: 0.4, // 20% of screen width
+
+            sweep_speed: 10.0, // half sweep per second
+            on_color: Color32::WHITE,
+            off_color: Color32::BLACK,
+            //
+            tap_tempo: TapTempo::default(),
+            user_presets: Vec::new(),
+            preset_name: String::new(),
+            limits: Limits::default(),
+            guard: FrequencyGuard::default(),
+            snap_to_refresh: false,
+            requested_hz: 0.0,
+            risk_policy: RiskPolicy::default(),
+            show_risk: false,
+            aspect: 16.0 / 9.0,
+            session: SessionTimer::default(),
+            session_start: now,
+            countdown: Duration::from_secs(10),
+            exposure: Exposure::default(),
+            idle: IdleStop::default(),
+            countdown_until: None,
+            history: History::default(),
+            history_path: None,
+            recorder: None,
+            show_stats: false,
+            journal_prompt: true,
+            journal: None,
+            program: Program {
+                name: "Descent".into(),
+                stages: vec![
+                    Stage {
+                        mode: "Flash".into(),
+                        start_hz: 13.0,
+                        end_hz: 8.0,
+                        duration_secs: 15.0 * 60.0,
+                        transition: Transition::Linear,
+                    },
+                    Stage {
+                        mode: "Flash".into(),
+                        start_hz: 8.0,
+                        end_hz: 8.0,
+                        duration_secs: 5.0 * 60.0,
+                        transition: Transition::Hold,
+                    },
+                ],
+            },
+            running_program: None,
+            show_program_editor: false,
+            export_settings: ExportSettings::default(),
+            export: None,
+            audio_settings,
+            audio: None,
+            midi: None,
+            remote: None,
+            mqtt: None,
+            light_feed,
+            lights: None,
+            warning_acknowledged: false,
+            show_warning: true,
+            confirm_quit: false,
+            toasts: Toasts::default(),
+            autostart: false,
+            fullscreen: false,
+            borderless: false,
+            always_on_top: false,
+            kiosk: false,
+            displays: Vec::new(),
+            display: None,
+            window: None,
+            separate_controls: false,
+            span_displays: false,
+            per_eye: false,
+            eye_phase_offset_deg: 180.0,
+            eye_frequency_offset_hz: 0.0,
+            last_pointer_move: now,
+            clock,
+        }
+    }
+
+    pub fn new(cc: &CreationContext<'_>, cli: Cli, clock: Arc<dyn Clock>) -> Self {
+        let mut s = Self::with_clock(clock);
+        // before the settings, which pick the mode by name
+        if let Some(render_state) = &cc.wgpu_render_state {
+            shader::init(render_state);
+            s.modes.extend(shader::modes());
+            for mode in shader::user_modes() {
+                if s.modes.iter().any(|m| m.name() == mode.name()) {
+                    tracing::warn!("Skipping shader {:?}: a mode has that name", mode.name());
+                    continue;
+                }
+                s.modes.push(mode);
+            }
+        }
+        let saved: Option<Settings> = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, SETTINGS_KEY));
+        if let Some(storage) = cc.storage {
+            s.warning_acknowledged =
+                eframe::get_value(storage, WARNING_ACKNOWLEDGED_KEY).unwrap_or(false);
+            if let Some(settings) = &saved {
+                s.apply_settings(settings.clone());
+            }
+            s.user_presets = eframe::get_value(storage, USER_PRESETS_KEY).unwrap_or_default();
+        }
+        match config::load() {
+            Ok(config) => s.apply_config(config),
+            Err(err) => s.report(err),
+        }
+        s.history_path = config::history_path();
+        if let Some(path) = s.history_path.clone() {
+            match History::load(&path) {
+                Ok(history) => s.history = history,
+                Err(source) => s.report(Error::Open { path, source }),
+            }
+        }
+        // external control, which isn't part of presets
+        let saved = saved.unwrap_or_default();
+        s.midi = Some(Midi::new(saved.midi, cc.egui_ctx.clone()));
+        let remote = Remote::new(saved.remote, cc.egui_ctx.clone());
+        s.mqtt = Some(Mqtt::new(saved.mqtt, remote.caller()));
+        s.remote = Some(remote);
+        s.lights = Some(Lights::new(
+            saved.lights,
+            s.light_feed.clone(),
+            s.clock.clone(),
+        ));
+        s.show_warning = !s.warning_acknowledged;
+        // restore the saved window state
+        s.displays = displays::all();
+        // the exact geometry if it's still on a connected monitor, otherwise
+        // just the monitor
+        let window = s
+            .window
+            .filter(|w| s.displays.iter().any(|d| d.rect.contains(w.center())));
+        let saved = s.display.as_ref();
+        if let Some(window) = window {
+            let ctx = &cc.egui_ctx;
+            ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::OuterPosition(window.min));
+            ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::InnerSize(window.size()));
+        } else if let Some(index) =
+            saved.and_then(|name| s.displays.iter().position(|d| &d.name == name))
+        {
+            s.move_to_display(&cc.egui_ctx, index);
+        }
+        let (fullscreen, borderless) = (s.fullscreen, s.borderless);
+        s.set_fullscreen(&cc.egui_ctx, fullscreen);
+        s.set_borderless(&cc.egui_ctx, borderless);
+        let (always_on_top, kiosk) = (s.always_on_top, s.kiosk);
+        s.set_always_on_top(&cc.egui_ctx, always_on_top);
+        if kiosk {
+            s.set_kiosk(&cc.egui_ctx, true);
+        }
+        s.requested_hz = s.timing.frequency_hz();
+        s.sweep_speed = s.timing.frequency_hz();
+        s.audio_settings.lock().unwrap().beat_hz = s.timing.frequency_hz();
+        s.apply_cli(&cc.egui_ctx, cli);
+        s
+    }
+
+    /// One tap of tap tempo; the frequency follows once there are two.
+    fn tap(&mut self) {
+        if let Some(hz) = self.tap_tempo.tap(self.clock.now()) {
+            self.set_frequency((hz * 10.0).round() / 10.0);
+        }
+    }
+
+    /// Log a failure and tell the user about it.
+    fn report(&mut self, err: Error) {
+        tracing::error!("{}", err);
+        self.toasts.push(err.to_string());
+    }
+
+    /// Values pinned in the config file win over the saved settings.
+    fn apply_config(&mut self, config: Config) {
+        self.limits = config.limits;
+        if let Some(name) = &config.mode {
+            match self.modes.iter().position(|m| m.name() == name) {
+                Some(index) => self.select_mode(index),
+                None => tracing::warn!("Unknown mode in config: {}", name),
+            }
+        }
+        if let Some(waveform) = config.waveform {
+            self.timing.set_waveform(waveform);
+        }
+        if let Some(color) = config.on_color {
+            self.on_color = color;
+        }
+        if let Some(color) = config.off_color {
+            self.off_color = color;
+        }
+        if let Some(audio) = config.audio {
+            self.set_audio(audio);
+        }
+        // re-clamps the saved frequency to the limits as well
+        let hz = config.frequency_hz.unwrap_or(self.timing.frequency_hz());
+        self.set_frequency(hz);
+    }
+
+    /// Command-line flags win over the config file and saved settings.
+    fn apply_cli(&mut self, ctx: &egui::Context, cli: Cli) {
+        if let Some(name) = &cli.mode {
+            match self
+                .modes
+                .iter()
+                .position(|m| cli::matches_name(m.name(), name))
+            {
+                Some(index) => self.select_mode(index),
+                None => tracing::warn!("Unknown mode: {}", name),
+            }
+        }
+        if let Some(hz) = cli.hz {
+            self.set_frequency(hz);
+        }
+        if cli.fullscreen {
+            self.set_fullscreen(ctx, true);
+        }
+        if let Some(duration) = cli.duration {
+            self.session.duration = Some(duration);
+        }
+        if cli.autostart {
+            self.autostart = true;
+            self.warning_acknowledged = true;
+            self.show_warning = false;
+            self.set_kiosk(ctx, true);
+        }
+        if let Some(path) = &cli.program {
+            match Program::load(path) {
+                Ok(program) => {
+                    self.program = program.clone();
+                    if self.check_program(&program) {
+                        self.run_program(program);
+                    }
+                }
+                Err(source) => self.report(Error::Open {
+                    path: path.clone(),
+                    source,
+                }),
+            }
+        } else if cli.start {
+            self.toggle_flashing();
+        }
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            mode: self.modes[self.mode].name().into(),
+            frequency_hz: self.timing.frequency_hz(),
+            waveform: self.timing.waveform(),
+            envelope: self.timing.envelope(),
+            on_color: self.on_color,
+            off_color: self.off_color,
+            beam_width_norm: self.beam_width_norm,
+            crossfade_secs: self.crossfade.as_secs_f32(),
+            countdown_secs: self.countdown.as_secs_f32(),
+            soft_start_secs: self.session.ramp.as_secs_f32(),
+            stimulation_limit_mins: self.exposure.limit.map(|d| d.as_secs_f32() / 60.0),
+            rest_mins: self.exposure.rest.as_secs_f32() / 60.0,
+            idle_grace_mins: self.idle.grace.map(|d| d.as_secs_f32() / 60.0),
+            frequency_guard: self.guard,
+            snap_to_refresh: self.snap_to_refresh,
+            risk_policy: self.risk_policy,
+            journal_prompt: self.journal_prompt,
+            grading: self.grading,
+            fullscreen: self.fullscreen,
+            borderless: self.borderless,
+            always_on_top: self.always_on_top,
+            kiosk: self.kiosk,
+            display: self.display.clone(),
+            window: self.window,
+            separate_controls: self.separate_controls,
+            audio: self.audio_settings.lock().unwrap().clone(),
+            midi: self
+                .midi
+                .as_ref()
+                .map(|m| m.map.clone())
+                .unwrap_or_default(),
+            remote: self
+                .remote
+                .as_ref()
+                .map(|r| r.settings.clone())
+                .unwrap_or_default(),
+            mqtt: self
+                .mqtt
+                .as_ref()
+                .map(|m| m.settings.clone())
+                .unwrap_or_default(),
+            lights: self
+                .lights
+                .as_ref()
+                .map(Lights::settings)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn apply_settings(&mut self, settings: Settings) {
+        if let Some(index) = self.modes.iter().position(|m| m.name() == settings.mode) {
+            self.select_mode(index);
+        }
+        self.timing.set_waveform(settings.waveform);
+        self.timing.set_envelope(settings.envelope);
+        self.on_color = settings.on_color;
+        self.off_color = settings.off_color;
+        self.beam_width_norm = settings.beam_width_norm;
+        self.crossfade = Duration::from_secs_f32(settings.crossfade_secs.max(0.0));
+        self.countdown = Duration::from_secs_f32(settings.countdown_secs.max(0.0));
+        self.session.ramp = Duration::from_secs_f32(settings.soft_start_secs.max(0.0));
+        self.exposure.limit = settings
+            .stimulation_limit_mins
+            .map(|mins| Duration::from_secs_f32(mins.max(1.0) * 60.0));
+        self.exposure.rest = Duration::from_secs_f32(settings.rest_mins.max(0.0) * 60.0);
+        self.idle.grace = settings
+            .idle_grace_mins
+            .map(|mins| Duration::from_secs_f32(mins.max(1.0) * 60.0));
+        self.grading = settings.grading;
+        self.fullscreen = settings.fullscreen;
+        self.borderless = settings.borderless;
+        self.always_on_top = settings.always_on_top;
+        self.kiosk = settings.kiosk;
+        self.display = settings.display;
+        self.window = settings.window;
+        self.separate_controls = settings.separate_controls;
+        self.guard = settings.frequency_guard;
+        self.snap_to_refresh = settings.snap_to_refresh;
+        self.risk_policy = settings.risk_policy;
+        self.journal_prompt = settings.journal_prompt;
+        self.set_audio(settings.audio);
+        self.set_frequency(settings.frequency_hz);
+    }
+
+    /// Swap in stored audio settings, keeping the fields the running session
+    /// drives.
+    fn set_audio(&mut self, mut audio: AudioSettings) {
+        let wants_audio = audio.any_enabled();
+        {
+            let mut current = self.audio_settings.lock().unwrap();
+            audio.playing = current.playing;
+            audio.level = current.level;
+            audio.beat_hz = current.beat_hz;
+            audio.gate = current.gate;
+            *current = audio;
+        }
+        if wants_audio {
+            self.ensure_audio();
+        }
+    }
+
+    /// Switch to a user preset. The window stays where it is, so this is safe
+    /// mid-session.
+    fn apply_user_preset(&mut self, index: usize) {
+        let Some(preset) = self.user_presets.get(index) else {
+            return;
+        };
+        let current = self.settings();
+        let settings = Settings {
+            // safety limits only change through their own controls, so a
+            // preset can't widen them without the unlock confirmation
+            stimulation_limit_mins: current.stimulation_limit_mins,
+            rest_mins: current.rest_mins,
+            idle_grace_mins: current.idle_grace_mins,
+            frequency_guard: current.frequency_guard,
+            risk_policy: current.risk_policy,
+            fullscreen: current.fullscreen,
+            borderless: current.borderless,
+            always_on_top: current.always_on_top,
+            kiosk: current.kiosk,
+            display: current.display,
+            window: current.window,
+            separate_controls: current.separate_controls,
+            midi: current.midi,
+            remote: current.remote,
+            mqtt: current.mqtt,
+            lights: current.lights,
+            ..preset.settings.clone()
+        };
+        self.apply_settings(settings);
+    }
+
+    /// Save the current settings under `preset_name`, replacing any preset
+    /// with the same name.
+    fn save_user_preset(&mut self) {
+        let preset = UserPreset {
+            name: self.preset_name.trim().to_string(),
+            settings: self.settings(),
+        };
+        match self.user_presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.user_presets.push(preset),
+        }
+        self.preset_name.clear();
+    }
+
+    /// Frequencies allowed right now: the guard rails within the config
+    /// limits, or all of the config limits once unlocked.
+    fn frequency_range(&self) -> RangeInclusive<f32> {
+        let (lo, hi) = (self.limits.min_frequency_hz, self.limits.max_frequency_hz);
+        if self.guard.unlocked {
+            return lo..=hi;
+        }
+        let min = self.guard.min_hz.clamp(lo, hi);
+        let max = self.guard.max_hz.clamp(min, hi);
+        min..=max
+    }
+
+    /// Every frequency change goes through here, so nothing gets past the
+    /// limits.
+    fn set_frequency(&mut self, hz: f32) {
+        self.requested_hz = hz;
+        let range = self.frequency_range();
+        let mut hz = hz.clamp(*range.start(), *range.end());
+        if self.snap_to_refresh
+            && let Some(refresh_hz) = self.refresh_hz()
+        {
+            hz = refresh::snap(hz, refresh_hz, range);
+        }
+        if (hz - self.timing.frequency_hz()).abs() >= 0.05 {
+            tracing::debug!("Frequency set to {:.1} Hz", hz);
+        }
+        self.timing.set_frequency(hz);
+        self.sweep_speed = hz;
+        self.audio_settings.lock().unwrap().beat_hz = hz;
+    }
+
+    fn toggle_flashing(&mut self) {
+        if !self.flashing && !self.may_start() {
+            return;
+        }
+        self.flashing = !self.flashing;
+        if self.flashing {
+            tracing::info!(
+                "Session started: {} at {:.1} Hz",
+                self.modes[self.mode].name(),
+                self.timing.frequency_hz()
+            );
+        } else {
+            tracing::info!(
+                "Session stopped after {:.0} s",
+                self.since(self.session_start).as_secs_f32()
+            );
+        }
+        self.start_stop_text = if self.flashing { "Stop" } else { "Start" }.into();
+        self.keep_awake = if self.flashing {
+            inhibit::keep_awake()
+        } else {
+            None
+        };
+        if !self.flashing {
+            self.record_session();
+            self.running_program = None;
+            self.previous_mode = None;
+        }
+        let now = self.clock.now();
+        self.countdown_until =
+            (self.flashing && !self.countdown.is_zero()).then(|| now + self.countdown);
+        self.restart_stimulus(now);
+    }
+
+    /// Whether a session may start now. If not, the user is told why.
+    fn may_start(&mut self) -> bool {
+        if !self.warning_acknowledged {
+            self.show_warning = true;
+            return false;
+        }
+        if let Some(left) = self.exposure.resting(self.clock.now()) {
+            let secs = left.as_secs();
+            self.toasts.push(format!(
+                "Take a break: {}:{:02} left. The limit is under Edit → Safety.",
+                secs / 60,
+                secs % 60
+            ));
+            return false;
+        }
+        if self.risk_policy != RiskPolicy::Off && self.flash_risk().risk == risk::Risk::Exceeds {
+            if self.risk_policy == RiskPolicy::Block {
+                self.toasts.push(
+                    "These settings exceed the flash guidelines. See Help → Flash Risk Analysis.",
+                );
+                return false;
+            }
+            self.toasts
+                .push("Warning: these settings exceed the broadcast flash guidelines.");
+        }
+        true
+    }
+
+    /// Check the current frequency, colours and mask against the broadcast
+    /// flash guidelines.
+    fn flash_risk(&self) -> risk::Report {
+        risk::analyze(&risk::Stimulus {
+            frequency_hz: self.timing.frequency_hz(),
+            on_color: self.grading.apply(self.on_color),
+            off_color: self.grading.apply(self.off_color),
+            area: self.mask.coverage(self.aspect),
+        })
+    }
+
+    fn risk_window(&mut self, ctx: &egui::Context) {
+        let report = self.flash_risk();
+        egui::Window::new("Flash Risk Analysis")
+            .open(&mut self.show_risk)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("risk_checks").striped(true).show(ui, |ui| {
+                    for check in &report.checks {
+                        ui.label(check.name);
+                        ui.label(&check.value);
+                        ui.colored_label(risk_color(check.risk), check.risk.name());
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Overall:");
+                    ui.colored_label(risk_color(report.risk), report.risk.name());
+                });
+                ui.label(
+                    "A stimulus exceeds the guidelines when it flashes more than three \
+                     times a second with a large luminance or red change over more than \
+                     a quarter of the screen.",
+                );
+                ui.separator();
+                ui.label("When starting a stimulus that exceeds them");
+                for policy in RiskPolicy::ALL {
+                    ui.radio_value(&mut self.risk_policy, policy, policy.name());
+                }
+            });
+    }
+
+    fn since(&self, earlier: Instant) -> Duration {
+        self.clock.now().saturating_duration_since(earlier)
+    }
+
+    /// Whether the stimulus is actually running: flashing and past the
+    /// countdown.
+    fn stimulating(&self) -> bool {
+        self.flashing && self.countdown_until.is_none()
+    }
+
+    /// Run the flicker, session clock and audio from the top.
+    fn restart_stimulus(&mut self, now: Instant) {
+        self.timing.reset(now);
+        self.timing.set_running(self.stimulating());
+        self.stimulus = self.timing.snapshot(now);
+        self.idle.reset();
+        if self.stimulating() {
+            self.diagnostics.reset();
+            if self.recorder.is_none() {
+                let program = self.running_program.as_ref().map(|p| p.name.clone());
+                self.recorder = Some(Recorder::new(Local::now(), program));
+            }
+        }
+        self.session_start = now;
+        self.shuffle.restart();
+        let mut audio = self.audio_settings.lock().unwrap();
+        audio.playing = self.stimulating();
+        audio.level = 1.0;
+    }
+
+    /// Add the session that just ended to the history.
+    fn record_session(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let record = recorder.finish();
+        if record.duration() < MIN_RECORDED {
+            return;
+        }
+        let Some(path) = self.history_path.clone() else {
+            return;
+        };
+        match self.history.append(&path, record) {
+            // nobody is around to answer after an unattended run
+            Ok(()) if self.journal_prompt && !self.autostart => {
+                self.journal = Some(JournalPrompt::new(self.history.records.len() - 1));
+            }
+            Ok(()) => {}
+            Err(source) => self.report(Error::Save { path, source }),
+        }
+    }
+
+    fn export_history(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON", &["json"])
+            .set_file_name("dreamachine-history.csv")
+            .save_file()
+        else {
+            return;
+        };
+        match self.history.export(&path) {
+            Ok(files) => {
+                for file in files {
+                    tracing::info!("Exported {}", file.display());
+                }
+            }
+            Err(source) => self.report(Error::Save { path, source }),
+        }
+    }
+
+    /// Store the answers from the journal prompt with their session.
+    fn save_journal(&mut self, record: usize, journal: Journal) {
+        let Some(record) = self.history.records.get_mut(record) else {
+            return;
+        };
+        record.journal = Some(journal);
+        if let Some(path) = self.history_path.clone()
+            && let Err(source) = self.history.save(&path)
+        {
+            self.report(Error::Save { path, source });
+        }
+    }
+
+    /// The problems [`Program::validate`] finds with `program` against the
+    /// loaded modes.
+    fn program_problems(&self, program: &Program) -> Vec<String> {
+        let names: Vec<&str> = self.modes.iter().map(|m| m.name()).collect();
+        program.validate(&names)
+    }
+
+    /// Report any problems with `program`; `true` if there are none.
+    fn check_program(&mut self, program: &Program) -> bool {
+        let problems = self.program_problems(program);
+        if problems.is_empty() {
+            return true;
+        }
+        self.report(Error::InvalidProgram {
+            name: program.name.clone(),
+            problems,
+        });
+        false
+    }
+
+    /// Start (or restart) a session driven by `program`.
+    fn run_program(&mut self, program: Program) {
+        if !self.flashing && !self.may_start() {
+            return;
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.program = Some(program.name.clone());
+        }
+        self.running_program = Some(program);
+        if self.flashing {
+            self.session_start = self.clock.now();
+        } else {
+            self.toggle_flashing();
+        }
+        self.advance_program();
+    }
+
+    /// Apply the running program's mode and frequency for the current moment.
+    fn advance_program(&mut self) {
+        let Some(program) = &self.running_program else {
+            return;
+        };
+        let Some(point) = program.sample(self.since(self.session_start)) else {
+            return;
+        };
+        let hz = point.frequency_hz;
+        let mode = self.modes.iter().position(|m| m.name() == point.mode);
+        if let Some(index) = mode.filter(|&i| i != self.mode) {
+            self.select_mode(index);
+        }
+        if hz != self.requested_hz {
+            self.set_frequency(hz);
+        }
+    }
+
+    fn open_program(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Program", &["toml", "json"])
+            .pick_file()
+        else {
+            return;
+        };
+        match Program::load(&path) {
+            Ok(program) => {
+                // Open it in the editor regardless, that's where it gets fixed.
+                self.check_program(&program);
+                self.program = program;
+                self.show_program_editor = true;
+            }
+            Err(source) => self.report(Error::Open { path, source }),
+        }
+    }
+
+    fn save_program(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Program", &["toml", "json"])
+            .set_file_name(format!("{}.toml", self.program.name))
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(source) = self.program.save(&path) {
+            self.report(Error::Save { path, source });
+        }
+    }
+
+    /// Ask where to save and start rendering the session chosen in the
+    /// export dialog.
+    fn start_export(&mut self) {
+        let format = self.export_settings.format;
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.name(), &[format.extension()])
+            .set_file_name(format!("session.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        let program = self.export_settings.program.then(|| self.program.clone());
+        let duration = match &program {
+            Some(program) => program.total_duration(),
+            None => Duration::from_secs_f32(self.export_settings.minutes * 60.0),
+        };
+        let session = SessionTimer {
+            duration: Some(duration),
+            ..self.session.clone()
+        };
+        let flicker = self.timing.snapshot(self.clock.now());
+        let audio = self.audio_settings.lock().unwrap().clone();
+        let audio = (self.export_settings.audio && audio.any_enabled()).then_some(audio);
+        match VideoExport::start(
+            &self.export_settings,
+            path,
+            program,
+            self.frequency_range(),
+            session,
+            flicker,
+            audio,
+            self.clock.now(),
+        ) {
+            Ok(export) => self.export = Some(export),
+            Err(err) => self.report(err),
+        }
+    }
+
+    /// Save one cycle of the current mode as a looping animation.
+    fn export_loop(&mut self) {
+        let mode = self.modes[self.mode].name();
+        let mut dialog = rfd::FileDialog::new().set_file_name(format!("{mode}.gif"));
+        for format in LoopFormat::ALL {
+            dialog = dialog.add_filter(format.name(), &[format.extension()]);
+        }
+        let Some(mut path) = dialog.save_file() else {
+            return;
+        };
+        let format = LoopFormat::from_path(&path).unwrap_or_else(|| {
+            path.set_extension(LoopFormat::Gif.extension());
+            LoopFormat::Gif
+        });
+        let mut flicker = self.timing.snapshot(self.clock.now());
+        let params = self.mode_params();
+        let cycle = Cycle::render(
+            &mut OfflineRenderer::new(LOOP_SIZE),
+            self.modes[self.mode].as_mut(),
+            &mut flicker,
+            &params,
+        );
+        if let Err(source) = cycle.save(&path, format) {
+            self.report(Error::Save { path, source });
+        }
+    }
+
+    /// Render the running export for a slice of this frame.
+    fn export_frames(&mut self, ctx: &egui::Context) {
+        let Some(mut export) = self.export.take() else {
+            return;
+        };
+        if export.show(ctx) {
+            export.cancel();
+            return;
+        }
+        match export.ready() {
+            Ok(true) => {}
+            // the soundtrack is still being written
+            Ok(false) => {
+                self.export = Some(export);
+                ctx.request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+            Err(err) => {
+                export.cancel();
+                self.report(err);
+                return;
+            }
+        }
+        // wall time, whatever the session clock says
+        let budget = Instant::now() + EXPORT_BUDGET;
+        let mut rendered = Rendered::Queued;
+        while !export.is_done() && rendered == Rendered::Queued && Instant::now() < budget {
+            let elapsed = export.elapsed();
+            let mode = export
+                .program
+                .as_ref()
+                .and_then(|p| p.sample(elapsed))
+                .and_then(|point| self.modes.iter().position(|m| m.name() == point.mode))
+                .unwrap_or(self.mode);
+            let level = export.session.intensity(elapsed);
+            let t = elapsed.as_secs_f32();
+            rendered = export.render_next(|painter, rect, flicker| {
+                let params = self.params_for(flicker);
+                self.paint_export_frame(painter, rect, mode, t, &params, level);
+            });
+        }
+        if export.is_done() || rendered == Rendered::Failed {
+            let path = export.path.clone();
+            match export.finish() {
+                Ok(()) => tracing::info!("Exported {}", path.display()),
+                Err(err) => self.report(err),
+            }
+        } else {
+            self.export = Some(export);
+            if rendered == Rendered::Behind {
+                // give ffmpeg a moment to drain the queue
+                ctx.request_repaint_after(Duration::from_millis(10));
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    /// One frame of an export: a mode with its layers, graded and faded like
+    /// the session.
+    fn paint_export_frame(
+        &mut self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        mode: usize,
+        t: f32,
+        params: &ModeParams,
+        level: f32,
+    ) {
+        let grading = self.grading;
+        let mut paint = |p: &egui::Painter| {
+            self.modes[mode].paint(p, rect, t, params);
+            self.compositor
+                .paint(&mut self.modes, mode, p, rect, t, params);
+        };
+        if grading.is_identity() {
+            paint(painter);
+        } else {
+            compositor::adjust_painted(painter, paint, move |color| *color = grading.apply(*color));
+        }
+        if level < 1.0 {
+            let alpha = ((1.0 - level) * 255.0) as u8;
+            painter.rect_filled(rect, 0.0, Color32::from_black_alpha(alpha));
+        }
+    }
+
+    fn warning_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Photosensitivity Warning")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "Dreamachine produces flashing and flickering light. In a small \
+                     percentage of people, exposure to certain light patterns or \
+                     flashing frequencies can trigger epileptic seizures, even with no \
+                     prior history of epilepsy.",
+                );
+                ui.label(
+                    "Do not use this program if you or anyone in your family has a \
+                     history of seizures or epilepsy. Stop immediately and consult a \
+                     doctor if you experience dizziness, altered vision, eye or muscle \
+                     twitching, loss of awareness, disorientation or any involuntary \
+                     movement.",
+                );
+                ui.label("Press Esc at any time to stop flashing immediately.");
+                ui.horizontal(|ui| {
+                    if self.warning_acknowledged {
+                        if ui.button("Close").clicked() {
+                            self.show_warning = false;
+                        }
+                    } else {
+                        if ui.button("I Understand").clicked() {
+                            self.warning_acknowledged = true;
+                            self.show_warning = false;
+                        }
+                        if ui.button("Quit").clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    }
+                });
+            });
+    }
+
+    /// The session timer in effect: a running program dictates the length.
+    fn active_session(&self) -> SessionTimer {
+        let mut session = match &self.running_program {
+            Some(program) => SessionTimer {
+                duration: Some(program.total_duration()),
+                fade: self.session.fade,
+                ramp: self.session.ramp,
+            },
+            None => self.session.clone(),
+        };
+        if let Some(max) = self.limits.max_session() {
+            session.duration = Some(session.duration.map_or(max, |d| d.min(max)));
+        }
+        session
+    }
+
+    /// Open the output device the first time any audio generator is enabled.
+    fn ensure_audio(&mut self) {
+        if self.audio.is_some() {
+            return;
+        }
+        match AudioEngine::start(self.audio_settings.clone()) {
+            Ok(engine) => self.audio = Some(engine),
+            Err(err) => {
+                self.report(err);
+                let mut settings = self.audio_settings.lock().unwrap();
+                settings.binaural = false;
+                settings.isochronic = false;
+                settings.noise = false;
+            }
+        }
+    }
+
+    fn mode_params(&self) -> ModeParams {
+        self.params_for(&self.stimulus)
+    }
+
+    fn params_for(&self, flicker: &Flicker) -> ModeParams {
+        ModeParams {
+            frequency_hz: flicker.frequency_hz(),
+            sweep_speed: self.sweep_speed,
+            beam_width_norm: self.beam_width_norm,
+            level: flicker.level(),
+            phase: flicker.phase(),
+            waveform: flicker.waveform(),
+            envelope: flicker.envelope(),
+            show_white: flicker.show_white(),
+            on_color: self.on_color,
+            off_color: self.off_color,
+            grading: self.grading,
+        }
+    }
+
+    /// Move the session on to the clock's now: the countdown, the exposure
+    /// and idle limits, the running program and the flicker as it will be
+    /// shown a `frame` from now. Returns the session fade level.
+    fn step(&mut self, frame: Duration) -> f32 {
+        if let Some(until) = self.countdown_until
+            && self.clock.now() >= until
+        {
+            self.countdown_until = None;
+            self.restart_stimulus(until);
+        }
+        if self.exposure.update(self.clock.now(), self.stimulating()) {
+            tracing::info!("Stimulation limit reached, resting");
+            self.toggle_flashing();
+        }
+        let mut fade_level = 1.0;
+        if self.stimulating() {
+            let elapsed = self.since(self.session_start);
+            let session = self.active_session();
+            let planned = session.duration.unwrap_or(Duration::ZERO);
+            let idle_level = self.idle.update(self.clock.now(), elapsed, planned);
+            if session.is_finished(elapsed) {
+                self.toggle_flashing();
+            } else if idle_level <= 0.0 {
+                tracing::info!("No input for a while, stopping the session");
+                self.toggle_flashing();
+            } else {
+                self.advance_program();
+                // a running program decides the mode itself
+                if self.shuffling
+                    && self.running_program.is_none()
+                    && let Some(index) = self.shuffle.poll(elapsed, self.mode)
+                {
+                    self.select_mode(index);
+                }
+                let present = self.frame_clock.present(self.clock.now(), frame);
+                self.stimulus = self.timing.snapshot(present);
+                if let Some(recorder) = &mut self.recorder {
+                    let mode = self.modes[self.mode].name();
+                    recorder.track(self.clock.now(), mode, self.timing.frequency_hz());
+                }
+                fade_level = session.intensity(elapsed) * idle_level;
+                self.audio_settings.lock().unwrap().level = fade_level;
+            }
+        }
+        fade_level
+    }
+
+    /// Fill `ui` with the stimulus: modes, grading, mask, fixation point and
+    /// the session fade.
+    fn draw_stimulus(
+        &mut self,
+        ui: &mut egui::Ui,
+        params: &ModeParams,
+        now: Instant,
+        fade_level: f32,
+    ) {
+        let rect = ui.max_rect();
+        let painter = ui.painter();
+        self.aspect = rect.aspect_ratio();
+
+        if !self.flashing {
+            // not flashing → always black
+            painter.rect_filled(rect, 0.0, Color32::BLACK);
+            if let Some(left) = self.exposure.resting(now) {
+                let secs = left.as_secs();
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    format!("Rest · {}:{:02}", secs / 60, secs % 60),
+                    egui::FontId::proportional(32.0),
+                    Color32::DARK_GRAY,
+                );
+            }
+            return;
+        }
+        if let Some(until) = self.countdown_until {
+            // black with a dim number that shrinks away over each second
+            painter.rect_filled(rect, 0.0, Color32::BLACK);
+            let left = until.saturating_duration_since(now).as_secs_f32();
+            let size = rect.width().min(rect.height()) * 0.3 * (0.3 + 0.7 * left.fract());
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                left.ceil() as u32,
+                egui::FontId::proportional(size),
+                Color32::DARK_GRAY,
+            );
+            return;
+        }
+        let grading = self.grading;
+        if grading.is_identity() {
+            self.paint_modes(painter, rect, now, params);
+        } else {
+            compositor::adjust_painted(
+                painter,
+                |p| self.paint_modes(p, rect, now, params),
+                move |color| *color = grading.apply(*color),
+            );
+        }
+        self.mask.paint(painter, rect);
+        self.fixation.paint(painter, rect);
+        if fade_level < 1.0 {
+            let alpha = ((1.0 - fade_level) * 255.0) as u8;
+            painter.rect_filled(rect, 0.0, Color32::from_black_alpha(alpha));
+        }
+    }
+
+    /// Name of the monitor the main window is on. Without an explicit choice
+    /// that is the primary one.
+    fn main_display(&self) -> Option<String> {
+        self.display.clone().or_else(|| {
+            self.displays
+                .iter()
+                .find(|d| d.primary)
+                .map(|d| d.name.clone())
+        })
+    }
+
+    /// Refresh rate of the display the stimulus is on.
+    fn refresh_hz(&self) -> Option<f32> {
+        let name = self.main_display()?;
+        self.displays.iter().find(|d| d.name == name)?.refresh_hz
+    }
+
+    /// A second window for the right eye, shifted in phase and frequency from
+    /// the main window, which becomes the left eye.
+    fn right_eye_window(&mut self, ctx: &egui::Context, now: Instant, fade_level: f32) {
+        let mut params = self.mode_params();
+        let elapsed = self.since(self.session_start).as_secs_f32();
+        let range = self.frequency_range();
+        params.frequency_hz = (params.frequency_hz + self.eye_frequency_offset_hz)
+            .clamp(*range.start(), *range.end());
+        params.sweep_speed += self.eye_frequency_offset_hz;
+        params.phase = (params.phase
+            + self.eye_phase_offset_deg / 360.0
+            + self.eye_frequency_offset_hz * elapsed)
+            .rem_euclid(1.0);
+        params.level = params
+            .envelope
+            .level(params.waveform, params.phase, params.frequency_hz);
+        params.show_white = params.phase >= 0.5;
+
+        // prefer a monitor of its own, otherwise a plain window to drag into place
+        let main = self.main_display();
+        let builder = egui::ViewportBuilder::default().with_title("Dreamachine – Right Eye");
+        let builder = match self
+            .displays
+            .iter()
+            .find(|d| Some(&d.name) != main.as_ref())
+        {
+            Some(display) => builder
+                .with_position(display.rect.min)
+                .with_inner_size(display.rect.size())
+                .with_decorations(false),
+            None => builder.with_inner_size([640.0, 480.0]),
+        };
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("right_eye"),
+            builder,
+            |ctx, _class| {
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::NONE)
+                    .show(ctx, |ui| self.draw_stimulus(ui, &params, now, fade_level));
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.per_eye = false;
+                }
+            },
+        );
+    }
+
+    /// One borderless window on every other monitor, all showing the same
+    /// stimulus as the main window.
+    fn span_windows(
+        &mut self,
+        ctx: &egui::Context,
+        params: &ModeParams,
+        now: Instant,
+        fade_level: f32,
+    ) {
+        let main = self.main_display();
+        let others: Vec<(String, egui::Rect)> = self
+            .displays
+            .iter()
+            .filter(|d| Some(&d.name) != main.as_ref())
+            .map(|d| (d.name.clone(), d.rect))
+            .collect();
+        for (name, rect) in others {
+            let builder = egui::ViewportBuilder::default()
+                .with_title(format!("Dreamachine – {name}"))
+                .with_position(rect.min)
+                .with_inner_size(rect.size())
+                .with_decorations(false);
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of(("span", &name)),
+                builder,
+                |ctx, _class| {
+                    egui::CentralPanel::default()
+                        .frame(egui::Frame::NONE)
+                        .show(ctx, |ui| self.draw_stimulus(ui, params, now, fade_level));
+                    if ctx.input(|i| i.viewport().close_requested()) {
+                        self.span_displays = false;
+                    }
+                },
+            );
+        }
+    }
+
+    /// Scroll for frequency, Ctrl+scroll for brightness and horizontal drag
+    /// for beam width, so the stimulus can be adjusted by feel.
+    fn canvas_gestures(&mut self, ui: &mut egui::Ui) {
+        if self.kiosk {
+            return;
+        }
+        let rect = ui.max_rect();
+        let response = ui.interact(rect, ui.id().with("canvas"), egui::Sense::drag());
+        if response.hovered() {
+            // a mouse wheel notch is about 50 points; make it 0.1 Hz
+            let (scroll, zoom) = ui.input(|i| (i.raw_scroll_delta.y, i.zoom_delta()));
+            if scroll != 0.0 {
+                self.set_frequency(self.requested_hz + scroll / 500.0);
+            }
+            // Ctrl+scroll arrives as zoom
+            if zoom != 1.0 {
+                self.grading.brightness =
+                    (self.grading.brightness.max(0.02) * zoom).clamp(0.0, 1.0);
+            }
+        }
+        if response.dragged() {
+            let dx = response.drag_delta().x / rect.width();
+            self.beam_width_norm = (self.beam_width_norm + dx).clamp(0.02, 1.0);
+        }
+    }
+
+    /// Paint the current mode, any outgoing crossfade and the layers on top.
+    fn paint_modes(
+        &mut self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        now: Instant,
+        params: &ModeParams,
+    ) {
+        let t = now.duration_since(self.mode_start).as_secs_f32();
+        let fade_in = t / self.crossfade.as_secs_f32();
+        match self.previous_mode {
+            Some((previous, start)) if fade_in < 1.0 => {
+                let previous_t = now.duration_since(start).as_secs_f32();
+                self.modes[previous].paint(painter, rect, previous_t, params);
+                let mut incoming = painter.clone();
+                incoming.set_opacity(fade_in);
+                self.modes[self.mode].paint(&incoming, rect, t, params);
+            }
+            _ => {
+                self.previous_mode = None;
+                self.modes[self.mode].paint(painter, rect, t, params);
+            }
+        }
+        self.compositor
+            .paint(&mut self.modes, self.mode, painter, rect, t, params);
+    }
+
+    // window commands always go to the stimulus window, even when issued
+    // from the separate controls window
+
+    fn set_fullscreen(&mut self, ctx: &egui::Context, on: bool) {
+        self.fullscreen = on;
+        ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::Fullscreen(on));
+    }
+
+    fn set_borderless(&mut self, ctx: &egui::Context, on: bool) {
+        self.borderless = on;
+        ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::Decorations(!on));
+        ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::Maximized(on));
+    }
+
+    fn set_always_on_top(&mut self, ctx: &egui::Context, on: bool) {
+        self.always_on_top = on;
+        self.apply_window_level(ctx);
+    }
+
+    /// Kiosk mode is fullscreen and on top, with the menus hidden and closing
+    /// the window blocked until [`KIOSK_EXIT`] is pressed.
+    fn set_kiosk(&mut self, ctx: &egui::Context, on: bool) {
+        self.kiosk = on;
+        self.set_fullscreen(ctx, on);
+        self.apply_window_level(ctx);
+    }
+
+    fn apply_window_level(&self, ctx: &egui::Context) {
+        let level = if self.always_on_top || self.kiosk {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+        ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::WindowLevel(level));
+    }
+
+    /// Remember where the window is while it's an ordinary window, and which
+    /// monitor it's on.
+    fn track_window(&mut self, ctx: &egui::Context) {
+        if self.fullscreen || self.borderless || self.kiosk {
+            return;
+        }
+        let (outer, inner) = ctx.input(|i| (i.viewport().outer_rect, i.viewport().inner_rect));
+        let (Some(outer), Some(inner)) = (outer, inner) else {
+            return;
+        };
+        let window = egui::Rect::from_min_size(outer.min, inner.size());
+        if self.window == Some(window) {
+            return;
+        }
+        self.window = Some(window);
+        if let Some(display) = self
+            .displays
+            .iter()
+            .find(|d| d.rect.contains(window.center()))
+        {
+            self.display = Some(display.name.clone());
+        }
+    }
+
+    /// Move the stimulus window onto `self.displays[index]`, keeping its
+    /// fullscreen state.
+    fn move_to_display(&mut self, ctx: &egui::Context, index: usize) {
+        let target = self.displays[index].rect.min + egui::vec2(40.0, 40.0);
+        self.display = Some(self.displays[index].name.clone());
+        // fullscreen windows can't be moved, so drop out and back in
+        let fullscreen = self.fullscreen;
+        if fullscreen {
+            self.set_fullscreen(ctx, false);
+        }
+        ctx.send_viewport_cmd_to(ROOT, egui::ViewportCommand::OuterPosition(target));
+        if fullscreen {
+            self.set_fullscreen(ctx, true);
+        }
+    }
+
+    /// The menus in their own window, placed on a different monitor from
+    /// the stimulus when there is one.
+    fn controls_window(&mut self, ctx: &egui::Context) {
+        let mut builder = egui::ViewportBuilder::default()
+            .with_title("Dreamachine Controls")
+            .with_inner_size([480.0, 120.0]);
+        let elsewhere = self
+            .displays
+            .iter()
+            .find(|d| Some(&d.name) != self.display.as_ref());
+        if let Some(display) = elsewhere {
+            builder = builder.with_position(display.rect.min + egui::vec2(40.0, 40.0));
+        }
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("controls"),
+            builder,
+            |ctx, _class| {
+                egui::TopBottomPanel::top("controls_menu").show(ctx, |ui| self.menu_bar(ui));
+                egui::CentralPanel::default().show(ctx, |_| {});
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.separate_controls = false;
+                }
+            },
+        );
+    }
+
+    /// Apply the commands from the MIDI controller.
+    fn poll_midi(&mut self) {
+        let range = self.frequency_range();
+        let Some(midi) = &mut self.midi else {
+            return;
+        };
+        for command in midi.poll(range, self.modes.len()) {
+            self.apply_command(command);
+        }
+    }
+
+    /// Answer the requests from remote control clients.
+    fn poll_remote(&mut self) {
+        let Some(remote) = &self.remote else {
+            return;
+        };
+        let calls: Vec<_> = remote.calls().collect();
+        for call in calls {
+            tracing::debug!("Remote request: {:?}", call.request);
+            let response = self.handle_request(call.request);
+            // the client may have given up waiting
+            let _ = call.reply.send(response);
+        }
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        let starts = matches!(
+            request,
+            Request::Start | Request::LoadProgram { .. } | Request::StartPreset { .. }
+        );
+        match request {
+            Request::Status => {}
+            Request::Start => {
+                if !self.flashing {
+                    self.toggle_flashing();
+                }
+            }
+            Request::Stop => {
+                if self.flashing {
+                    self.toggle_flashing();
+                }
+            }
+            Request::SetFrequency { hz } => self.apply_command(Command::SetFrequency(hz)),
+            Request::SetMode { mode } => {
+                match self
+                    .modes
+                    .iter()
+                    .position(|m| cli::matches_name(m.name(), &mode))
+                {
+                    Some(index) => self.apply_command(Command::SelectMode(index)),
+                    None => return Response::error(format!("unknown mode \"{mode}\"")),
+                }
+            }
+            Request::LoadProgram { program } => {
+                let problems = self.program_problems(&program);
+                if !problems.is_empty() {
+                    return Response::error(problems.join("; "));
+                }
+                self.run_program(program);
+            }
+            Request::StartPreset { preset } => {
+                let matches = |name: &str| cli::matches_name(name, &preset);
+                if let Some(program) = presets::all().into_iter().find(|p| matches(&p.name)) {
+                    self.run_program(program);
+                } else if let Some(index) = self.user_presets.iter().position(|p| matches(&p.name))
+                {
+                    self.apply_user_preset(index);
+                    if !self.flashing {
+                        self.toggle_flashing();
+                    }
+                } else {
+                    return Response::error(format!("unknown preset \"{preset}\""));
+                }
+            }
+        }
+        if starts && !self.flashing {
+            return Response::error("the session can't start yet; see the app");
+        }
+        Response::status(self.status())
+    }
+
+    /// What remote clients are told about the session.
+    fn status(&self) -> Status {
+        let elapsed = if self.flashing {
+            self.since(self.session_start)
+        } else {
+            Duration::ZERO
+        };
+        let remaining = self
+            .active_session()
+            .duration
+            .filter(|_| self.flashing)
+            .map(|duration| duration.saturating_sub(elapsed).as_secs_f32());
+        Status {
+            flashing: self.flashing,
+            counting_down: self.countdown_until.is_some(),
+            mode: self.modes[self.mode].name().into(),
+            frequency_hz: self.timing.frequency_hz(),
+            program: self.running_program.as_ref().map(|p| p.name.clone()),
+            elapsed_secs: elapsed.as_secs_f32(),
+            remaining_secs: remaining,
+        }
+    }
+
+    fn apply_command(&mut self, command: Command) {
+        match command {
+            Command::SetFrequency(hz) => self.set_frequency(hz),
+            Command::SetBrightness(level) => self.grading.brightness = level.clamp(0.0, 1.0),
+            Command::SelectMode(index) if index < self.modes.len() => self.select_mode(index),
+            Command::SelectMode(_) => {}
+            Command::NextMode => self.select_mode((self.mode + 1) % self.modes.len()),
+            Command::StartStop => self.toggle_flashing(),
+        }
+    }
+
+    fn select_mode(&mut self, index: usize) {
+        // fade over from the current mode if it is on screen
+        if self.flashing && index != self.mode && !self.crossfade.is_zero() {
+            self.previous_mode = Some((self.mode, self.mode_start));
+        }
+        self.mode = index;
+        self.mode_start = self.clock.now();
+    }
+
+    fn menu_bar(&mut self, ui: &mut egui::Ui) {
+        let ctx = &ui.ctx().clone();
+        MenuBar::new().ui(ui, |ui| {
+            ui.menu_button("File", |ui| {
+                if ui
+                    .add_enabled(
+                        self.warning_acknowledged,
+                        egui::Button::new(&self.start_stop_text),
+                    )
+                    .clicked()
+                {
+                    self.toggle_flashing();
+                }
+                ui.separator();
+                if ui.button("Open Program…").clicked() {
+                    self.open_program();
+                }
+                if ui.button("Save Program…").clicked() {
+                    self.save_program();
+                }
+                if ui
+                    .add_enabled(
+                        !self.flashing && self.export.is_none(),
+                        egui::Button::new("Export Video…"),
+                    )
+                    .clicked()
+                {
+                    if let Some(duration) = self.session.duration {
+                        self.export_settings.minutes = duration.as_secs_f32() / 60.0;
+                    }
+                    self.export_settings.open = true;
+                }
+                if ui
+                    .add_enabled(
+                        !self.flashing && self.modes[self.mode].renders_offline(),
+                        egui::Button::new("Export Loop…"),
+                    )
+                    .on_hover_text("One flicker cycle of the current mode as a GIF or APNG")
+                    .on_disabled_hover_text("Shader modes can't be exported")
+                    .clicked()
+                {
+                    self.export_loop();
+                }
+                if ui
+                    .add_enabled(
+                        !self.history.records.is_empty(),
+                        egui::Button::new("Export History…"),
+                    )
+                    .on_hover_text(
+                        "Past sessions as JSON, or as CSV with the frequency timelines \
+                         in a second -timeline.csv file",
+                    )
+                    .clicked()
+                {
+                    self.export_history();
+                }
+                ui.separator();
+                if ui.button("Open Config Folder").clicked()
+                    && let Err(err) = config::open_folder()
+                {
+                    self.report(err);
+                }
+                if ui
+                    .button("Open Shaders Folder")
+                    .on_hover_text("Custom .wgsl modes are loaded from here on launch")
+                    .clicked()
+                    && let Err(err) = config::open_shaders_folder()
+                {
+                    self.report(err);
+                }
+                ui.separator();
+                if ui.button("Quit").clicked() {
+                    self.confirm_quit = true;
+                }
+            });
+            ui.menu_button("Edit", |ui| {
+                ui.menu_button("Mode", |ui| {
+                    for i in 0..self.modes.len() {
+                        let label = format!(
+                            "{}{}",
+                            self.modes[i].name(),
+                            if i == self.mode { " *" } else { "" }
+                        );
+                        if ui.button(label).clicked() {
+                            self.select_mode(i);
+                        }
+                    }
+                    ui.separator();
+                    let mut secs = self.crossfade.as_secs_f32();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut secs, 0.0..=10.0)
+                                .text("Crossfade")
+                                .suffix(" s"),
+                        )
+                        .changed()
+                    {
+                        self.crossfade = Duration::from_secs_f32(secs);
+                    }
+                });
+                ui.menu_button("Mode Settings", |ui| {
+                    self.modes[self.mode].settings_ui(ui);
+                });
+                ui.menu_button("Shuffle", |ui| {
+                    ui.checkbox(&mut self.shuffling, "Shuffle modes");
+                    let mut secs = self.shuffle.interval.as_secs_f32();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut secs, 5.0..=600.0)
+                                .logarithmic(true)
+                                .text("Every")
+                                .suffix(" s"),
+                        )
+                        .changed()
+                    {
+                        self.shuffle.interval = Duration::from_secs_f32(secs);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Seed");
+                        ui.add(egui::DragValue::new(&mut self.shuffle.seed));
+                    });
+                    ui.separator();
+                    for (i, mode) in self.modes.iter().enumerate() {
+                        let mut enabled = self.shuffle.pool.contains(&i);
+                        if ui.checkbox(&mut enabled, mode.name()).changed() {
+                            if enabled {
+                                // in mode order, so a seed always gives the same sequence
+                                self.shuffle.pool.push(i);
+                                self.shuffle.pool.sort_unstable();
+                            } else {
+                                self.shuffle.pool.retain(|&m| m != i);
+                            }
+                        }
+                    }
+                });
+                ui.menu_button("Layers", |ui| {
+                    let names: Vec<&str> = self.modes.iter().map(|m| m.name()).collect();
+                    self.compositor.settings_ui(ui, &names);
+                });
+                ui.menu_button("Waveform", |ui| {
+                    for waveform in Waveform::ALL {
+                        let label = format!(
+                            "{}{}",
+                            waveform.name(),
+                            if waveform == self.timing.waveform() {
+                                " *"
+                            } else {
+                                ""
+                            }
+                        );
+                        if ui.button(label).clicked() {
+                            self.timing.set_waveform(waveform);
+                        }
+                    }
+                    ui.separator();
+                    let mut envelope = self.timing.envelope();
+                    ui.add(
+                        egui::Slider::new(&mut envelope.attack_ms, 0.0..=200.0)
+                            .text("Attack")
+                            .suffix(" ms"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut envelope.decay_ms, 0.0..=200.0)
+                            .text("Decay")
+                            .suffix(" ms"),
+                    );
+                    ui.label("Attack and decay soften the edges of square pulses.");
+                    self.timing.set_envelope(envelope);
+                });
+                ui.menu_button("Colors", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.color_edit_button_srgba(&mut self.on_color);
+                        ui.label("On");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.color_edit_button_srgba(&mut self.off_color);
+                        ui.label("Off");
+                    });
+                });
+                ui.menu_button("Session", |ui| {
+                    ui.radio_value(&mut self.session.duration, None, "Unlimited");
+                    for mins in [10, 20, 30] {
+                        ui.radio_value(
+                            &mut self.session.duration,
+                            Some(Duration::from_secs(mins * 60)),
+                            format!("{mins} min"),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Custom:");
+                        let mut mins = self
+                            .session
+                            .duration
+                            .map_or(0.0, |d| d.as_secs_f32() / 60.0);
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut mins)
+                                    .range(1.0..=240.0)
+                                    .speed(1.0)
+                                    .suffix(" min"),
+                            )
+                            .changed()
+                        {
+                            self.session.duration = Some(Duration::from_secs_f32(mins * 60.0));
+                        }
+                    });
+                    let mut fade = self.session.fade.as_secs_f32();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut fade, 0.0..=120.0)
+                                .text("Fade-out")
+                                .suffix(" s"),
+                        )
+                        .changed()
+                    {
+                        self.session.fade = Duration::from_secs_f32(fade);
+                    }
+                    let mut ramp = self.session.ramp.as_secs_f32();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut ramp, 0.0..=120.0)
+                                .text("Soft start")
+                                .suffix(" s"),
+                        )
+                        .on_hover_text("Ramp up from black instead of starting at full brightness")
+                        .changed()
+                    {
+                        self.session.ramp = Duration::from_secs_f32(ramp);
+                    }
+                    let mut countdown = self.countdown.as_secs_f32();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut countdown, 0.0..=30.0)
+                                .step_by(1.0)
+                                .text("Countdown")
+                                .suffix(" s"),
+                        )
+                        .on_hover_text("Black screen before the first flash, to settle in")
+                        .changed()
+                    {
+                        self.countdown = Duration::from_secs_f32(countdown);
+                    }
+                    ui.checkbox(&mut self.journal_prompt, "Journal after sessions")
+                        .on_hover_text("Ask for notes and ratings when a session ends");
+                });
+                ui.menu_button("Safety", |ui| {
+                    let mut limited = self.exposure.limit.is_some();
+                    if ui
+                        .checkbox(&mut limited, "Limit continuous stimulation")
+                        .changed()
+                    {
+                        self.exposure.limit = limited.then(|| Duration::from_secs(30 * 60));
+                    }
+                    if let Some(limit) = &mut self.exposure.limit {
+                        let mut mins = limit.as_secs_f32() / 60.0;
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut mins, 5.0..=120.0)
+                                    .step_by(5.0)
+                                    .text("Limit")
+                                    .suffix(" min"),
+                            )
+                            .changed()
+                        {
+                            *limit = Duration::from_secs_f32(mins * 60.0);
+                        }
+                        let mut rest = self.exposure.rest.as_secs_f32() / 60.0;
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut rest, 1.0..=60.0)
+                                    .step_by(1.0)
+                                    .text("Rest")
+                                    .suffix(" min"),
+                            )
+                            .changed()
+                        {
+                            self.exposure.rest = Duration::from_secs_f32(rest * 60.0);
+                        }
+                    }
+                    ui.separator();
+                    let mut idle_stop = self.idle.grace.is_some();
+                    if ui
+                        .checkbox(&mut idle_stop, "Stop unattended sessions")
+                        .on_hover_text(
+                            "Fade out once a session runs past its length by the grace \
+                             period with no keyboard or mouse input",
+                        )
+                        .changed()
+                    {
+                        self.idle.grace = idle_stop.then(|| Duration::from_secs(10 * 60));
+                    }
+                    if let Some(grace) = &mut self.idle.grace {
+                        let mut mins = grace.as_secs_f32() / 60.0;
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut mins, 1.0..=60.0)
+                                    .step_by(1.0)
+                                    .text("Grace")
+                                    .suffix(" min"),
+                            )
+                            .changed()
+                        {
+                            *grace = Duration::from_secs_f32(mins * 60.0);
+                        }
+                    }
+                    ui.separator();
+                    ui.label("Frequency guard rails");
+                    let (lo, hi) = (self.limits.min_frequency_hz, self.limits.max_frequency_hz);
+                    let mut changed = false;
+                    ui.add_enabled_ui(!self.guard.unlocked, |ui| {
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut self.guard.min_hz)
+                                        .range(lo..=hi)
+                                        .speed(0.1)
+                                        .suffix(" Hz"),
+                                )
+                                .changed();
+                            ui.label("to");
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut self.guard.max_hz)
+                                        .range(lo..=hi)
+                                        .speed(0.1)
+                                        .suffix(" Hz"),
+                                )
+                                .changed();
+                        });
+                    });
+                    changed |= ui
+                        .checkbox(&mut self.guard.unlocked, "Allow the full range (advanced)")
+                        .on_hover_text(format!("{lo}–{hi} Hz"))
+                        .changed();
+                    if changed {
+                        // pull the current frequency inside the new range
+                        self.set_frequency(self.requested_hz);
+                    }
+                });
+                if ui.button("Program…").clicked() {
+                    self.show_program_editor = true;
+                }
+                ui.menu_button("Hertz", |ui| {
+                    // edit what was asked for, so snapping doesn't hold the
+                    // slider on one even frequency
+                    let mut hz = self.requested_hz;
+                    let slider = ui.add(
+                        egui::Slider::new(&mut hz, self.frequency_range())
+                            .step_by(0.1)
+                            .show_value(false),
+                    );
+                    let entry = ui.add(
+                        egui::DragValue::new(&mut hz)
+                            .range(self.frequency_range())
+                            .speed(0.1)
+                            .fixed_decimals(1)
+                            .suffix(" Hz"),
+                    );
+                    if slider.changed() || entry.changed() {
+                        self.set_frequency((hz * 10.0).round() / 10.0);
+                    }
+                    if ui
+                        .button(format!("Tap (T) · {}", self.tap_tempo.count()))
+                        .on_hover_text("Tap along with a beat to set the frequency")
+                        .clicked()
+                    {
+                        self.tap();
+                    }
+                    if let Some(refresh_hz) = self.refresh_hz() {
+                        ui.separator();
+                        if ui
+                            .checkbox(&mut self.snap_to_refresh, "Snap to refresh rate")
+                            .changed()
+                        {
+                            self.set_frequency(self.requested_hz);
+                        }
+                        let hz = self.timing.frequency_hz();
+                        if !refresh::is_representable(hz, refresh_hz) {
+                            ui.colored_label(
+                                Color32::YELLOW,
+                                format!("{hz:.2} Hz is uneven at {refresh_hz:.0} Hz"),
+                            );
+                            let (below, above) = refresh::neighbours(hz, refresh_hz);
+                            ui.horizontal(|ui| {
+                                for even in [below, above] {
+                                    if ui.button(format!("{even:.2} Hz")).clicked() {
+                                        self.set_frequency(even);
+                                    }
+                                }
+                            });
+                        }
+                    }
+                });
+            });
+            ui.menu_button("Presets", |ui| {
+                for preset in presets::all() {
+                    let mins = preset.total_duration().as_secs_f32() / 60.0;
+                    if ui
+                        .button(&preset.name)
+                        .on_hover_text(format!("{mins:.0} min"))
+                        .clicked()
+                    {
+                        self.run_program(preset);
+                    }
+                }
+                ui.separator();
+                let mut remove = None;
+                for i in 0..self.user_presets.len() {
+                    ui.horizontal(|ui| {
+                        let key = if i < 9 {
+                            format!("{}  ", i + 1)
+                        } else {
+                            String::new()
+                        };
+                        if ui
+                            .button(format!("{key}{}", self.user_presets[i].name))
+                            .clicked()
+                        {
+                            self.apply_user_preset(i);
+                        }
+                        if ui.small_button("×").on_hover_text("Delete").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    self.user_presets.remove(i);
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.preset_name)
+                            .hint_text("Preset name")
+                            .desired_width(120.0),
+                    );
+                    let named = !self.preset_name.trim().is_empty();
+                    if ui
+                        .add_enabled(named, egui::Button::new("Save Current"))
+                        .clicked()
+                    {
+                        self.save_user_preset();
+                    }
+                });
+            });
+            ui.menu_button("Audio", |ui| {
+                let mut settings = self.audio_settings.lock().unwrap().clone();
+                let mut toggled = ui
+                    .checkbox(&mut settings.binaural, "Binaural Beats")
+                    .changed();
+                ui.add(
+                    egui::Slider::new(&mut settings.carrier_hz, 100.0..=1000.0)
+                        .text("Carrier")
+                        .suffix(" Hz"),
+                );
+                ui.add(egui::Slider::new(&mut settings.volume, 0.0..=1.0).text("Volume"));
+                ui.separator();
+                toggled |= ui
+                    .checkbox(&mut settings.isochronic, "Isochronic Tones")
+                    .changed();
+                ui.add(
+                    egui::Slider::new(&mut settings.iso_carrier_hz, 100.0..=1000.0)
+                        .text("Pitch")
+                        .suffix(" Hz"),
+                );
+                ui.add(egui::Slider::new(&mut settings.iso_duty, 0.1..=1.0).text("Duty Cycle"));
+                ui.add(egui::Slider::new(&mut settings.iso_volume, 0.0..=1.0).text("Volume"));
+                ui.separator();
+                toggled |= ui.checkbox(&mut settings.noise, "Pink Noise").changed();
+                ui.add(egui::Slider::new(&mut settings.noise_volume, 0.0..=1.0).text("Volume"));
+                // keep the live fields the UI doesn't edit
+                let mut shared = self.audio_settings.lock().unwrap();
+                settings.gate = shared.gate;
+                *shared = settings.clone();
+                drop(shared);
+                if toggled && settings.any_enabled() {
+                    self.ensure_audio();
+                }
+            });
+            ui.menu_button("View", |ui| {
+                ui.menu_button("Display", |ui| {
+                    if self.displays.is_empty() {
+                        ui.label("No displays found");
+                    }
+                    for i in 0..self.displays.len() {
+                        let d = &self.displays[i];
+                        let selected = self.display.as_ref() == Some(&d.name);
+                        let label = format!(
+                            "{} ({:.0}×{:.0}{}){}",
+                            d.name,
+                            d.rect.width(),
+                            d.rect.height(),
+                            d.refresh_hz
+                                .map_or_else(String::new, |hz| format!(" @ {hz:.0} Hz")),
+                            if d.primary { ", primary" } else { "" }
+                        );
+                        if ui.radio(selected, label).clicked() {
+                            self.move_to_display(ctx, i);
+                        }
+                    }
+                    if ui.button("Refresh").clicked() {
+                        self.displays = displays::all();
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.separate_controls, "Controls in Separate Window");
+                    ui.checkbox(&mut self.span_displays, "Span All Displays")
+                        .on_hover_text("Open a borderless window on every other display");
+                });
+                ui.menu_button("Per-Eye Windows", |ui| {
+                    ui.checkbox(&mut self.per_eye, "Right-Eye Window")
+                        .on_hover_text("Open a second window; the main window is the left eye");
+                    ui.add(
+                        egui::Slider::new(&mut self.eye_phase_offset_deg, 0.0..=360.0)
+                            .text("Phase offset")
+                            .suffix("°"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.eye_frequency_offset_hz, -5.0..=5.0)
+                            .step_by(0.1)
+                            .text("Frequency offset")
+                            .suffix(" Hz"),
+                    );
+                });
+                ui.menu_button("Mask", |ui| self.mask.settings_ui(ui));
+                ui.menu_button("Fixation Point", |ui| self.fixation.settings_ui(ui));
+                ui.checkbox(&mut self.hud.enabled, "HUD");
+                ui.checkbox(&mut self.diagnostics.open, "Diagnostics");
+                ui.checkbox(&mut self.show_stats, "Statistics");
+                ui.menu_button("Picture", |ui| {
+                    ui.add(
+                        egui::Slider::new(&mut self.grading.brightness, 0.0..=1.0)
+                            .text("Brightness"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.grading.contrast, 0.0..=2.0).text("Contrast"),
+                    );
+                    ui.add(egui::Slider::new(&mut self.grading.gamma, 0.2..=3.0).text("Gamma"));
+                    ui.separator();
+                    ui.checkbox(&mut self.grading.warm_tint, "Warm tint");
+                    ui.add_enabled(
+                        self.grading.warm_tint,
+                        egui::Slider::new(&mut self.grading.kelvin, 1900.0..=6500.0)
+                            .text("Color temperature")
+                            .suffix(" K"),
+                    );
+                    if ui.button("Reset").clicked() {
+                        self.grading = Grading::default();
+                    }
+                });
+                let mut fullscreen = self.fullscreen;
+                if ui.checkbox(&mut fullscreen, "Fullscreen (F11)").changed() {
+                    self.set_fullscreen(ctx, fullscreen);
+                }
+                let mut borderless = self.borderless;
+                if ui.checkbox(&mut borderless, "Borderless Window").changed() {
+                    self.set_borderless(ctx, borderless);
+                }
+                let mut always_on_top = self.always_on_top;
+                if ui.checkbox(&mut always_on_top, "Always on Top").changed() {
+                    self.set_always_on_top(ctx, always_on_top);
+                }
+                let shortcut = ctx.format_shortcut(&KIOSK_EXIT);
+                if ui
+                    .button("Kiosk Mode")
+                    .on_hover_text(format!("Hide all controls; press {shortcut} to leave"))
+                    .clicked()
+                {
+                    self.set_kiosk(ctx, true);
+                }
+            });
+            ui.menu_button("Control", |ui| {
+                if let Some(midi) = &mut self.midi {
+                    ui.menu_button("MIDI", |ui| midi.settings_ui(ui));
+                }
+                if let Some(remote) = &mut self.remote {
+                    ui.menu_button("Remote", |ui| remote.settings_ui(ui));
+                }
+                if let Some(mqtt) = &mut self.mqtt {
+                    ui.menu_button("MQTT", |ui| mqtt.settings_ui(ui));
+                }
+                if let Some(lights) = &mut self.lights {
+                    ui.menu_button("Lights", |ui| lights.menu_ui(ui));
+                }
+            });
+            ui.menu_button("Help", |ui| {
+                if ui.button("Photosensitivity Warning").clicked() {
+                    self.show_warning = true;
+                }
+                if ui.button("Flash Risk Analysis").clicked() {
+                    self.show_risk = true;
+                }
+                if ui.button("Learn More").clicked() {
+                    let url = "https://en.wikipedia.org/wiki/Dreamachine";
+                    if let Err(source) = webbrowser::open(url) {
+                        self.report(Error::Browser {
+                            url: url.into(),
+                            source,
+                        });
+                    }
+                }
+            })
+        });
+    }
+}
+
+impl App for DreamApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        // without window chrome the menu only appears when the mouse is near the top
+        let show_menu = !self.kiosk
+            && (!(self.fullscreen || self.borderless)
+                || ctx.input(|i| i.pointer.hover_pos().is_some_and(|pos| pos.y <= 60.0)));
+
+        // panic key: checked before anything else so it works regardless of UI state.
+        // When nothing is flashing it leaves fullscreen instead.
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            if self.flashing {
+                self.toggle_flashing();
+            } else if self.kiosk {
+                // only KIOSK_EXIT gets out of kiosk mode
+            } else if self.fullscreen {
+                self.set_fullscreen(ctx, false);
+            } else if self.borderless {
+                self.set_borderless(ctx, false);
+            }
+        }
+        if !self.kiosk && ctx.input(|i| i.key_pressed(egui::Key::F11)) {
+            self.set_fullscreen(ctx, !self.fullscreen);
+        }
+        // single-key shortcuts, unless a text field has focus
+        if !ctx.wants_keyboard_input() {
+            if ctx.input(|i| i.key_pressed(egui::Key::T)) {
+                self.tap();
+            }
+            let keys = [
+                egui::Key::Num1,
+                egui::Key::Num2,
+                egui::Key::Num3,
+                egui::Key::Num4,
+                egui::Key::Num5,
+                egui::Key::Num6,
+                egui::Key::Num7,
+                egui::Key::Num8,
+                egui::Key::Num9,
+            ];
+            if let Some(index) = keys.iter().position(|&k| ctx.input(|i| i.key_pressed(k))) {
+                self.apply_user_preset(index);
+            }
+        }
+
+        // in kiosk mode stray clicks reach nothing and the window won't close
+        if self.kiosk {
+            if ctx.input_mut(|i| i.consume_shortcut(&KIOSK_EXIT)) {
+                self.set_kiosk(ctx, false);
+                self.confirm_quit = true;
+            } else if ctx.input(|i| i.viewport().close_requested()) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            }
+        }
+
+        if self.separate_controls && !self.kiosk {
+            self.controls_window(ctx);
+        } else if show_menu {
+            egui::TopBottomPanel::top("top_panel").show(ctx, |ui| self.menu_bar(ui));
+        }
+
+        self.track_window(ctx);
+        self.poll_midi();
+        self.poll_remote();
+        if self.mqtt.is_some() {
+            let status = self.status();
+            if let Some(mqtt) = &mut self.mqtt {
+                mqtt.update(&status);
+            }
+        }
+
+        // a frame that takes twice as long as expected has dropped flashes
+        let (dt, expected) = ctx.input(|i| (i.unstable_dt, i.predicted_dt));
+        if self.stimulating() && dt > 2.0 * expected {
+            tracing::warn!(
+                "Slow frame: {:.1} ms, expected {:.1} ms",
+                dt * 1000.0,
+                expected * 1000.0
+            );
+        }
+
+        // === BLINK STATE ===
+        if ctx.input(|i| !i.events.is_empty()) {
+            self.idle.input(self.clock.now());
+        }
+        let fade_level = self.step(Duration::from_secs_f32(expected.max(1e-3)));
+        {
+            let mut feed = self.light_feed.lock().unwrap();
+            feed.on_color = self.on_color;
+            feed.off_color = self.off_color;
+            feed.grading = self.grading;
+            feed.fade = fade_level;
+        }
+        if self.autostart && !self.flashing {
+            self.kiosk = false;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        if self.show_warning {
+            self.warning_window(ctx);
+        }
+        if self.show_risk {
+            self.risk_window(ctx);
+        }
+        if self.show_stats {
+            stats::show(ctx, &mut self.show_stats, &self.history);
+        }
+        if let Some(prompt) = &mut self.journal
+            && let Some(answer) = prompt.show(ctx)
+        {
+            let prompt = self.journal.take().expect("prompt is open");
+            if let Answer::Save = answer {
+                self.save_journal(prompt.record, prompt.journal);
+            }
+        }
+
+        let mode_names: Vec<&str> = self.modes.iter().map(|m| m.name()).collect();
+        if program_editor::show(
+            ctx,
+            &mut self.show_program_editor,
+            &mut self.program,
+            &mode_names,
+        ) {
+            let program = self.program.clone();
+            if self.check_program(&program) {
+                self.run_program(program);
+            }
+        }
+
+        if self.export_settings.open {
+            let frequencies: Vec<f32> = if self.export_settings.program {
+                let range = self.frequency_range();
+                self.program
+                    .stages
+                    .iter()
+                    .flat_map(|s| [s.start_hz, s.end_hz])
+                    .map(|hz| hz.clamp(*range.start(), *range.end()))
+                    .collect()
+            } else {
+                vec![self.timing.frequency_hz()]
+            };
+            let has_audio = self.audio_settings.lock().unwrap().any_enabled();
+            let used: Vec<usize> = if self.export_settings.program {
+                self.program
+                    .stages
+                    .iter()
+                    .filter_map(|s| self.modes.iter().position(|m| m.name() == s.mode))
+                    .collect()
+            } else {
+                vec![self.mode]
+            };
+            let gpu_modes: Vec<&str> = used
+                .into_iter()
+                .chain(self.compositor.layers.iter().map(|layer| layer.mode))
+                .filter_map(|i| self.modes.get(i))
+                .filter(|mode| !mode.renders_offline())
+                .map(|mode| mode.name())
+                .collect();
+            if self
+                .export_settings
+                .show(ctx, &frequencies, has_audio, &gpu_modes)
+            {
+                self.start_export();
+            }
+        }
+        self.export_frames(ctx);
+
+        if self.confirm_quit {
+            egui::Window::new("Confirm Quit")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label("Are you sure you want to quit?");
+                    ui.horizontal(|ui| {
+                        if ui.button("Yes").clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                        if ui.button("No").clicked() {
+                            self.confirm_quit = false;
+                        }
+                    });
+                });
+        }
+
+        // === DRAW ===
+        let now = self.clock.now();
+        let params = self.mode_params();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.draw_stimulus(ui, &params, now, fade_level);
+            self.canvas_gestures(ui);
+        });
+        if self.stimulating() {
+            self.hud.record(now, self.stimulus.show_white());
+            self.diagnostics
+                .record(now, expected, self.stimulus.show_white());
+        }
+        if self.diagnostics.open {
+            self.diagnostics.show(ctx, self.timing.frequency_hz());
+        }
+        if self.span_displays {
+            self.span_windows(ctx, &params, now, fade_level);
+        }
+        if self.per_eye {
+            self.right_eye_window(ctx, now, fade_level);
+        }
+
+        // keep the arrow out of the strobe field until the mouse moves
+        if ctx.input(|i| i.pointer.delta() != egui::Vec2::ZERO) {
+            self.last_pointer_move = self.clock.now();
+        }
+        if (self.fullscreen || self.borderless)
+            && self.flashing
+            && self.since(self.last_pointer_move) > CURSOR_HIDE_AFTER
+        {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+        }
+
+        if self.stimulating() {
+            let elapsed = self.since(self.session_start);
+            let info = HudInfo {
+                mode: self.modes[self.mode].name(),
+                frequency_hz: self.timing.frequency_hz(),
+                elapsed,
+                remaining: self.active_session().remaining(elapsed),
+            };
+            self.hud.show(ctx, self.clock.now(), &info);
+        }
+
+        self.toasts.show(ctx);
+
+        // every frame while flashing, including the countdown
+        if self.flashing {
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(IDLE_REPAINT);
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // an unattended run mustn't leave the next launch in kiosk mode
+        if self.autostart {
+            return;
+        }
+        eframe::set_value(
+            storage,
+            WARNING_ACKNOWLEDGED_KEY,
+            &self.warning_acknowledged,
+        );
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings());
+        eframe::set_value(storage, USER_PRESETS_KEY, &self.user_presets);
+    }
+}
+
+fn risk_color(risk: risk::Risk) -> Color32 {
+    match risk {
+        risk::Risk::Ok => Color32::LIGHT_GREEN,
+        risk::Risk::Caution => Color32::YELLOW,
+        risk::Risk::Exceeds => Color32::LIGHT_RED,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dreamachine_core::ManualClock;
+
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+    const FRAME: Duration = Duration::from_millis(16);
+
+    /// An app on a manual clock, past the warning and without a countdown.
+    fn app() -> (DreamApp, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::default());
+        let mut app = DreamApp::with_clock(clock.clone());
+        app.warning_acknowledged = true;
+        app.countdown = Duration::ZERO;
+        (app, clock)
+    }
+
+    fn mode(app: &DreamApp) -> &str {
+        app.modes[app.mode].name()
+    }
+
+    #[test]
+    fn flashes_follow_the_clock() {
+        let (mut app, clock) = app();
+        app.set_frequency(10.0);
+        app.toggle_flashing();
+        let mut flashes = 0;
+        let mut was_white = false;
+        for _ in 0..1000 {
+            clock.advance(Duration::from_millis(1));
+            app.step(Duration::ZERO);
+            let white = app.stimulus.show_white();
+            if white && !was_white {
+                flashes += 1;
+            }
+            was_white = white;
+        }
+        assert_eq!(flashes, 10);
+    }
+
+    #[test]
+    fn ramps_in_and_fades_out() {
+        let (mut app, clock) = app();
+        app.session.duration = Some(120 * SEC);
+        app.toggle_flashing();
+        assert_eq!(app.step(FRAME), 0.0);
+        clock.advance(15 * SEC);
+        assert_eq!(app.step(FRAME), 0.5);
+        clock.advance(15 * SEC);
+        assert_eq!(app.step(FRAME), 1.0);
+        clock.advance(75 * SEC);
+        assert_eq!(app.step(FRAME), 0.5);
+        assert!(app.flashing);
+        clock.advance(15 * SEC);
+        app.step(FRAME);
+        assert!(!app.flashing);
+    }
+
+    #[test]
+    fn programs_change_stage_on_time() {
+        let (mut app, clock) = app();
+        let stage = |mode: &str, hz: f32| Stage {
+            mode: mode.into(),
+            start_hz: hz,
+            end_hz: hz,
+            duration_secs: 60.0,
+            transition: Transition::Hold,
+        };
+        app.run_program(Program {
+            name: "Test".into(),
+            stages: vec![stage("Flash", 10.0), stage("Sweep", 9.0)],
+        });
+        app.step(FRAME);
+        assert_eq!(mode(&app), "Flash");
+        assert_eq!(app.timing.frequency_hz(), 10.0);
+        clock.advance(61 * SEC);
+        app.step(FRAME);
+        assert_eq!(mode(&app), "Sweep");
+        assert_eq!(app.timing.frequency_hz(), 9.0);
+        clock.advance(60 * SEC);
+        app.step(FRAME);
+        assert!(!app.flashing);
+        assert!(app.running_program.is_none());
+    }
+
+    #[test]
+    fn rests_after_the_exposure_limit() {
+        let (mut app, clock) = app();
+        app.exposure.limit = Some(60 * SEC);
+        app.exposure.rest = 30 * SEC;
+        app.toggle_flashing();
+        app.step(FRAME);
+        clock.advance(60 * SEC);
+        app.step(FRAME);
+        assert!(!app.flashing);
+        // refused until the rest is over
+        app.toggle_flashing();
+        assert!(!app.flashing);
+        clock.advance(30 * SEC);
+        app.step(FRAME);
+        app.toggle_flashing();
+        assert!(app.flashing);
+    }
+}
@@ -0,0 +1,114 @@
+//! Art-Net output to DMX fixtures.
+
+use std::io;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use dreamachine_core::lights::Sample;
+use dreamachine_core::lights::artnet::{self, Layout, Patch};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::lights::{Device, Sink};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtNetSettings {
+    pub enabled: bool,
+    /// The node's address, or a broadcast address such as `2.255.255.255`.
+    pub host: String,
+    /// Port-address: net, sub-net and universe in 15 bits.
+    pub universe: u16,
+    pub patch: Patch,
+    /// Frames per second; DMX itself tops out at about 44.
+    pub rate: f32,
+}
+
+impl Default for ArtNetSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "2.255.255.255".into(),
+            universe: 0,
+            patch: Patch::default(),
+            rate: 44.0,
+        }
+    }
+}
+
+impl Device for ArtNetSettings {
+    type Sink = ArtNet;
+
+    const NAME: &'static str = "Art-Net";
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("artnet_settings").show(ui, |ui| {
+            ui.label("Node");
+            ui.text_edit_singleline(&mut self.host);
+            ui.end_row();
+            ui.label("Universe");
+            ui.add(egui::DragValue::new(&mut self.universe).range(0..=0x7fff));
+            ui.end_row();
+            ui.label("Fixture");
+            egui::ComboBox::from_id_salt("artnet_layout")
+                .selected_text(self.patch.layout.name())
+                .show_ui(ui, |ui| {
+                    for layout in Layout::ALL {
+                        ui.selectable_value(&mut self.patch.layout, layout, layout.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("Address");
+            ui.add(egui::DragValue::new(&mut self.patch.address).range(1..=512));
+            ui.end_row();
+            ui.label("Fixtures");
+            ui.add(egui::DragValue::new(&mut self.patch.fixtures).range(1..=512));
+            ui.end_row();
+            ui.label("Rate");
+            ui.add(
+                egui::DragValue::new(&mut self.rate)
+                    .range(1.0..=100.0)
+                    .suffix(" fps"),
+            );
+            ui.end_row();
+        });
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.rate.clamp(1.0, 100.0))
+    }
+
+    fn connect(&self) -> io::Result<ArtNet> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_broadcast(true)?;
+        socket.connect((self.host.trim(), artnet::PORT))?;
+        Ok(ArtNet {
+            socket,
+            universe: self.universe,
+            patch: self.patch,
+            sequence: 0,
+        })
+    }
+}
+
+pub struct ArtNet {
+    socket: UdpSocket,
+    universe: u16,
+    patch: Patch,
+    sequence: u8,
+}
+
+impl Sink for ArtNet {
+    fn show(&mut self, sample: &Sample) -> io::Result<()> {
+        // 1..=255; 0 would turn sequencing off
+        self.sequence = self.sequence % 255 + 1;
+        let data = self.patch.universe(sample);
+        let packet = artnet::dmx_packet(self.universe, self.sequence, &data);
+        self.socket.send(&packet)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+//! Audio output device for the entrainment generators in `dreamachine_core::audio`.
+
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use dreamachine_core::audio::{AudioSettings, Mixer};
+
+use crate::error::Error;
+
+/// Keeps the output stream alive; audio stops when this is dropped.
+pub struct AudioEngine {
+    _stream: Stream,
+}
+
+impl AudioEngine {
+    pub fn start(settings: Arc<Mutex<AudioSettings>>) -> Result<Self, Error> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(Error::NoAudioDevice)?;
+        let supported = device.default_output_config()?;
+        let format = supported.sample_format();
+        let config: StreamConfig = supported.into();
+
+        let stream = match format {
+            SampleFormat::F32 => build::<f32>(&device, &config, settings)?,
+            SampleFormat::I16 => build::<i16>(&device, &config, settings)?,
+            SampleFormat::U16 => build::<u16>(&device, &config, settings)?,
+            other => return Err(Error::AudioFormat(other)),
+        };
+        stream.play()?;
+        Ok(Self { _stream: stream })
+    }
+}
+
+fn build<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    settings: Arc<Mutex<AudioSettings>>,
+) -> Result<Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let sample_rate = config.sample_rate.0 as f32;
+    let mut mixer = Mixer::default();
+
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let settings = settings.lock().unwrap().clone();
+            for frame in data.chunks_mut(channels) {
+                let [left, right] = mixer.next(&settings, sample_rate);
+                for (i, sample) in frame.iter_mut().enumerate() {
+                    let v = if i % 2 == 0 { left } else { right };
+                    *sample = T::from_sample(v);
+                }
+            }
+        },
+        |err| tracing::error!("Audio stream error: {}", err),
+        None,
+    )
+}
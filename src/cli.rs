@@ -0,0 +1,178 @@
+//! Command-line flags, for launching sessions from scripts and shortcuts.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use dreamachine_core::modes::{self, VisualMode};
+use dreamachine_core::{Program, presets};
+
+use crate::shader;
+
+/// Flicker-light stimulation on your screen.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Visual mode to start in, e.g. `sweep` or `noise-flicker`.
+    #[arg(long)]
+    pub mode: Option<String>,
+    /// Flicker frequency in hertz.
+    #[arg(long)]
+    pub hz: Option<f32>,
+    /// Start fullscreen.
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Session length, e.g. `20m`, `90s` or `1h30m`. A bare number is minutes.
+    #[arg(long, value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+    /// Program file to run as soon as the window opens.
+    #[arg(long)]
+    pub program: Option<PathBuf>,
+    /// Start flashing as soon as the window opens.
+    #[arg(long)]
+    pub start: bool,
+    /// Unattended run for installations and labs: no menus or warning, the
+    /// program starts fullscreen straight away and the app quits when it ends.
+    /// Nothing is saved from the run.
+    #[arg(long, requires = "program")]
+    pub autostart: bool,
+    /// Log more: `-v` for session events, `-vv` for frequency changes and
+    /// `-vvv` for everything.
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Also write logs to a file in this directory, rotated daily.
+    #[arg(long, global = true)]
+    pub log_dir: Option<PathBuf>,
+}
+
+/// Things to do instead of opening the window.
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print the names of the visual modes.
+    ListModes,
+    /// Print the bundled presets with their lengths.
+    ListPresets,
+    /// Print a shell completion script.
+    Completions { shell: Shell },
+    /// Check program files for unknown modes, out-of-range frequencies and
+    /// bad durations. Exits non-zero if any problem is found.
+    Validate {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl Command {
+    /// Carry out the command; `false` if it failed.
+    pub fn run(self) -> bool {
+        match self {
+            Command::ListModes => {
+                for mode in all_modes() {
+                    println!("{}", mode.name());
+                }
+            }
+            Command::ListPresets => {
+                for preset in presets::all() {
+                    let mins = preset.total_duration().as_secs_f32() / 60.0;
+                    println!("{}\t{mins:.0} min", preset.name);
+                }
+            }
+            Command::Completions { shell } => {
+                clap_complete::generate(
+                    shell,
+                    &mut Cli::command(),
+                    "dreamachine",
+                    &mut io::stdout(),
+                );
+            }
+            Command::Validate { paths } => {
+                // check every file rather than stopping at the first bad one
+                let failed = paths.iter().filter(|path| !validate(path)).count();
+                return failed == 0;
+            }
+        }
+        true
+    }
+}
+
+/// Every mode the GUI offers: the core ones followed by the shader modes.
+fn all_modes() -> Vec<Box<dyn VisualMode>> {
+    modes::all()
+        .into_iter()
+        .chain(shader::modes())
+        .chain(shader::user_modes())
+        .collect()
+}
+
+/// Report the problems with one program file; `true` if there are none.
+fn validate(path: &Path) -> bool {
+    let program = match Program::load(path) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            return false;
+        }
+    };
+    let modes = all_modes();
+    let names: Vec<&str> = modes.iter().map(|m| m.name()).collect();
+    let problems = program.validate(&names);
+    for problem in &problems {
+        eprintln!("{}: {}", path.display(), problem);
+    }
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+    }
+    problems.is_empty()
+}
+
+/// Whether `arg` names the mode called `name`, ignoring case, spaces and
+/// punctuation, so `noise-flicker` finds "Noise Flicker".
+pub fn matches_name(name: &str, arg: &str) -> bool {
+    let fold = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    fold(name) == fold(arg)
+}
+
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    if let Ok(mins) = arg.parse::<f32>() {
+        return duration_from_secs(mins * 60.0, arg);
+    }
+    let mut secs = 0.0;
+    let mut number = String::new();
+    for c in arg.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return Err(format!("unknown unit '{c}' in \"{arg}\"; use h, m or s")),
+        };
+        let value: f32 = number
+            .parse()
+            .map_err(|_| format!("expected a number before '{c}' in \"{arg}\""))?;
+        secs += value * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(format!("missing unit after {number} in \"{arg}\""));
+    }
+    duration_from_secs(secs, arg)
+}
+
+fn duration_from_secs(secs: f32, arg: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f32(secs)
+        .ok()
+        .filter(|d| !d.is_zero())
+        .ok_or_else(|| format!("\"{arg}\" is not a positive duration"))
+}
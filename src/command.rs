@@ -0,0 +1,13 @@
+//! Commands from outside the window, such as a MIDI controller, applied by
+//! the app between frames.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    SetFrequency(f32),
+    /// Master brightness, `0..=1`.
+    SetBrightness(f32),
+    /// Mode by position in the Mode menu.
+    SelectMode(usize),
+    NextMode,
+    StartStop,
+}
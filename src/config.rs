@@ -0,0 +1,132 @@
+//! Optional hand-written `config.toml` in the user's config directory
+//! (`~/.config/dreamachine` on Linux), for pinning defaults and safety limits.
+//!
+//! Anything set here overrides the settings saved from the last session on
+//! every launch; command-line flags override both.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use dreamachine_core::Waveform;
+use dreamachine_core::audio::AudioSettings;
+use dreamachine_core::flicker::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+use eframe::egui::Color32;
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Mode name, as shown in the Mode menu.
+    pub mode: Option<String>,
+    pub frequency_hz: Option<f32>,
+    pub waveform: Option<Waveform>,
+    /// `[r, g, b, a]`.
+    pub on_color: Option<Color32>,
+    pub off_color: Option<Color32>,
+    pub audio: Option<AudioSettings>,
+    pub limits: Limits,
+}
+
+/// Bounds the UI, programs and the command line can't go past.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub min_frequency_hz: f32,
+    pub max_frequency_hz: f32,
+    /// Longest session allowed, in minutes.
+    pub max_session_minutes: Option<f32>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            min_frequency_hz: MIN_FREQUENCY_HZ,
+            max_frequency_hz: MAX_FREQUENCY_HZ,
+            max_session_minutes: None,
+        }
+    }
+}
+
+impl Limits {
+    pub fn max_session(&self) -> Option<Duration> {
+        self.max_session_minutes
+            .map(|mins| Duration::from_secs_f32(mins * 60.0))
+    }
+
+    /// Keep the limits inside what the app supports and in order.
+    fn sanitized(self) -> Self {
+        let min = self
+            .min_frequency_hz
+            .clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ);
+        let max = self.max_frequency_hz.clamp(min, MAX_FREQUENCY_HZ);
+        Self {
+            min_frequency_hz: min,
+            max_frequency_hz: max,
+            max_session_minutes: self.max_session_minutes.map(|mins| mins.max(1.0)),
+        }
+    }
+}
+
+fn dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("dreamachine"))
+}
+
+/// Where the config file lives on this platform.
+pub fn path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join("config.toml"))
+}
+
+/// The session history log.
+pub fn history_path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join("history.jsonl"))
+}
+
+/// Where custom `.wgsl` shader modes are picked up from.
+pub fn shaders_dir() -> Option<PathBuf> {
+    dir().map(|dir| dir.join("shaders"))
+}
+
+/// The config file's contents, or the defaults if there isn't one.
+pub fn load() -> Result<Config> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => {
+            return Err(Error::Config {
+                path,
+                source: err.into(),
+            });
+        }
+    };
+    let mut config: Config = toml::from_str(&text).map_err(|err| Error::Config {
+        path,
+        source: err.into(),
+    })?;
+    config.limits = config.limits.sanitized();
+    Ok(config)
+}
+
+/// Show the folder holding the config file, creating it if needed.
+pub fn open_folder() -> Result<()> {
+    reveal(dir().ok_or(Error::NoConfigDir)?)
+}
+
+/// Show the custom shader folder, creating it if needed.
+pub fn open_shaders_folder() -> Result<()> {
+    reveal(shaders_dir().ok_or(Error::NoConfigDir)?)
+}
+
+fn reveal(dir: PathBuf) -> Result<()> {
+    fs::create_dir_all(&dir).map_err(|source| Error::CreateDir {
+        path: dir.clone(),
+        source,
+    })?;
+    opener::open(&dir).map_err(|source| Error::Reveal { path: dir, source })
+}
@@ -0,0 +1,151 @@
+//! Timing measurements of the flicker as actually rendered.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use eframe::egui::{self, Color32, Pos2, Rect, Sense, Vec2};
+
+/// Frames and flashes kept for the statistics.
+const HISTORY: usize = 600;
+const BINS: usize = 24;
+
+#[derive(Default)]
+pub struct Diagnostics {
+    pub open: bool,
+    frame_times: VecDeque<f32>, // seconds
+    periods: VecDeque<f32>,     // seconds between rising edges
+    frames: u64,
+    dropped: u64,
+    last_frame: Option<Instant>,
+    last_flash: Option<Instant>,
+    was_white: bool,
+}
+
+impl Diagnostics {
+    /// Forget the previous session.
+    pub fn reset(&mut self) {
+        *self = Self {
+            open: self.open,
+            ..Self::default()
+        };
+    }
+
+    /// Note a frame drawn at `now`, `expected_dt` after the previous one.
+    pub fn record(&mut self, now: Instant, expected_dt: f32, show_white: bool) {
+        if let Some(last) = self.last_frame {
+            let dt = now.duration_since(last).as_secs_f32();
+            push(&mut self.frame_times, dt);
+            self.frames += 1;
+            if expected_dt > 0.0 && dt > 1.5 * expected_dt {
+                self.dropped += (dt / expected_dt).round() as u64 - 1;
+            }
+        }
+        self.last_frame = Some(now);
+        if show_white && !self.was_white {
+            if let Some(last) = self.last_flash {
+                push(&mut self.periods, now.duration_since(last).as_secs_f32());
+            }
+            self.last_flash = Some(now);
+        }
+        self.was_white = show_white;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, target_hz: f32) {
+        let mut open = self.open;
+        egui::Window::new("Diagnostics")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let (frame_mean, frame_dev) = stats(&self.frame_times);
+                let (period_mean, period_dev) = stats(&self.periods);
+                let max_frame = self.frame_times.iter().copied().fold(0.0, f32::max);
+                egui::Grid::new("diagnostics").show(ui, |ui| {
+                    ui.label("Requested");
+                    ui.label(format!("{target_hz:.2} Hz"));
+                    ui.end_row();
+                    ui.label("Achieved");
+                    ui.label(if period_mean > 0.0 {
+                        format!("{:.2} Hz", 1.0 / period_mean)
+                    } else {
+                        "–".into()
+                    });
+                    ui.end_row();
+                    ui.label("Flash jitter");
+                    ui.label(format!("±{:.2} ms", period_dev * 1000.0));
+                    ui.end_row();
+                    ui.label("Frame time");
+                    ui.label(format!(
+                        "{:.2} ms ±{:.2} (max {:.2})",
+                        frame_mean * 1000.0,
+                        frame_dev * 1000.0,
+                        max_frame * 1000.0
+                    ));
+                    ui.end_row();
+                    ui.label("Dropped frames");
+                    ui.label(format!("{} of {}", self.dropped, self.frames));
+                    ui.end_row();
+                });
+                ui.separator();
+                ui.label("Frame times");
+                histogram(ui, &self.frame_times);
+                ui.label("Flash periods");
+                histogram(ui, &self.periods);
+                if ui.button("Reset").clicked() {
+                    self.reset();
+                }
+            });
+        self.open = open;
+    }
+}
+
+fn push(history: &mut VecDeque<f32>, value: f32) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+/// Mean and standard deviation.
+fn stats(values: &VecDeque<f32>) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Bar chart of `values` (seconds) between their minimum and maximum.
+fn histogram(ui: &mut egui::Ui, values: &VecDeque<f32>) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(240.0, 60.0), Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, Color32::from_black_alpha(80));
+    if values.is_empty() {
+        return;
+    }
+    let lo = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let hi = values.iter().copied().fold(0.0, f32::max);
+    let span = (hi - lo).max(1e-4);
+    let mut counts = [0usize; BINS];
+    for v in values {
+        let bin = ((v - lo) / span * BINS as f32) as usize;
+        counts[bin.min(BINS - 1)] += 1;
+    }
+    let most = counts.iter().copied().max().unwrap_or(1).max(1) as f32;
+    let width = rect.width() / BINS as f32;
+    for (i, &count) in counts.iter().enumerate() {
+        let height = rect.height() * count as f32 / most;
+        let x = rect.left() + i as f32 * width;
+        let bar = Rect::from_min_max(
+            Pos2::new(x + 1.0, rect.bottom() - height),
+            Pos2::new(x + width - 1.0, rect.bottom()),
+        );
+        painter.rect_filled(bar, 0.0, Color32::LIGHT_BLUE);
+    }
+    ui.horizontal(|ui| {
+        ui.small(format!("{:.2} ms", lo * 1000.0));
+        ui.small("–");
+        ui.small(format!("{:.2} ms", hi * 1000.0));
+    });
+}
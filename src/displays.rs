@@ -0,0 +1,37 @@
+//! Connected monitors, for sending the stimulus to a chosen screen.
+
+use display_info::DisplayInfo;
+use eframe::egui::{Pos2, Rect, Vec2};
+
+pub struct Display {
+    pub name: String,
+    /// Position and size in points.
+    pub rect: Rect,
+    pub primary: bool,
+    /// `None` if the OS doesn't say.
+    pub refresh_hz: Option<f32>,
+}
+
+/// Every monitor the OS reports; empty if they can't be listed.
+pub fn all() -> Vec<Display> {
+    match DisplayInfo::all() {
+        Ok(displays) => displays
+            .into_iter()
+            .map(|d| {
+                let scale = d.scale_factor.max(1.0);
+                let min = Pos2::new(d.x as f32 / scale, d.y as f32 / scale);
+                let size = Vec2::new(d.width as f32, d.height as f32) / scale;
+                Display {
+                    name: d.name,
+                    rect: Rect::from_min_size(min, size),
+                    primary: d.is_primary,
+                    refresh_hz: (d.frequency > 0.0).then_some(d.frequency),
+                }
+            })
+            .collect(),
+        Err(err) => {
+            tracing::warn!("Failed to list displays: {}", err);
+            Vec::new()
+        }
+    }
+}
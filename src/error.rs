@@ -0,0 +1,54 @@
+//! Failures the user should hear about, shown as toasts by the app.
+
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("No audio output device")]
+    NoAudioDevice,
+    #[error("Audio device: {0}")]
+    AudioConfig(#[from] cpal::DefaultStreamConfigError),
+    #[error("Unsupported audio sample format {0}")]
+    AudioFormat(cpal::SampleFormat),
+    #[error("Couldn't start audio: {0}")]
+    AudioBuild(#[from] cpal::BuildStreamError),
+    #[error("Couldn't start audio: {0}")]
+    AudioPlay(#[from] cpal::PlayStreamError),
+    #[error("Couldn't open {}: {source}", path.display())]
+    Open {
+        path: PathBuf,
+        source: dreamachine_core::Error,
+    },
+    #[error("The program \"{name}\" can't run: {}", problems.join("; "))]
+    InvalidProgram { name: String, problems: Vec<String> },
+    #[error("Couldn't save {}: {source}", path.display())]
+    Save {
+        path: PathBuf,
+        source: dreamachine_core::Error,
+    },
+    #[error("Couldn't read the config file {}: {source}", path.display())]
+    Config {
+        path: PathBuf,
+        source: dreamachine_core::Error,
+    },
+    #[error("This platform has no config directory")]
+    NoConfigDir,
+    #[error("Couldn't create {}: {source}", path.display())]
+    CreateDir { path: PathBuf, source: io::Error },
+    #[error("Couldn't show {}: {source}", path.display())]
+    Reveal {
+        path: PathBuf,
+        source: opener::OpenError,
+    },
+    #[error("Couldn't open {url} in a browser: {source}")]
+    Browser { url: String, source: io::Error },
+    #[error("Couldn't run ffmpeg, is it installed? {0}")]
+    Ffmpeg(io::Error),
+    #[error("Exporting {} failed: {message}", path.display())]
+    Export { path: PathBuf, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
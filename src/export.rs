@@ -0,0 +1,696 @@
+//! File → Export Video: a session rendered offline and encoded by ffmpeg, so
+//! protocols can be played on devices without the app.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use dreamachine_core::audio::{AudioSettings, Mixer};
+use dreamachine_core::{Flicker, OfflineRenderer, Program, SessionTimer, refresh};
+use eframe::egui::{self, Color32, ColorImage, Painter, Rect};
+
+use crate::error::{Error, Result};
+
+/// Frames rendered ahead of the encoder before rendering waits for it.
+const QUEUE: usize = 8;
+/// Sample rate of the soundtrack.
+const SAMPLE_RATE: u32 = 48_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum VideoFormat {
+    Mp4,
+    WebM,
+}
+
+impl VideoFormat {
+    pub const ALL: [VideoFormat; 2] = [VideoFormat::Mp4, VideoFormat::WebM];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "MP4 (H.264)",
+            VideoFormat::WebM => "WebM (VP9)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::WebM => "webm",
+        }
+    }
+
+    // high quality: compression smears hard on/off edges
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &[
+                "-c:v",
+                "libx264",
+                "-crf",
+                "16",
+                "-pix_fmt",
+                "yuv420p",
+                "-movflags",
+                "+faststart",
+            ],
+            VideoFormat::WebM => &[
+                "-c:v",
+                "libvpx-vp9",
+                "-crf",
+                "20",
+                "-b:v",
+                "0",
+                "-pix_fmt",
+                "yuv420p",
+            ],
+        }
+    }
+
+    fn audio_codec_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &["-c:a", "aac", "-b:a", "192k"],
+            VideoFormat::WebM => &["-c:a", "libopus", "-b:a", "160k"],
+        }
+    }
+}
+
+/// Choices in the export dialog.
+pub struct ExportSettings {
+    pub open: bool,
+    pub size: [usize; 2],
+    pub fps: u32,
+    pub format: VideoFormat,
+    /// Export the program from the editor instead of the current mode.
+    pub program: bool,
+    /// Length of an export without a program.
+    pub minutes: f32,
+    /// Include the audio generators that are switched on.
+    pub audio: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            open: false,
+            size: [1920, 1080],
+            fps: 60,
+            format: VideoFormat::Mp4,
+            program: false,
+            minutes: 15.0,
+            audio: true,
+        }
+    }
+}
+
+impl ExportSettings {
+    /// The export dialog. Returns true when Export… is pressed.
+    /// `frequencies` are the ones the export will flicker at, to warn about
+    /// those the frame rate can't show evenly. `has_audio` offers the audio
+    /// generators as a soundtrack. `gpu_modes` names the modes the export
+    /// would use that can't be rendered offline; any of them blocks it.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        frequencies: &[f32],
+        has_audio: bool,
+        gpu_modes: &[&str],
+    ) -> bool {
+        let mut export = false;
+        let mut open = self.open;
+        egui::Window::new("Export Video")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("export_settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Session");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.program, false, "Current mode");
+                            ui.radio_value(&mut self.program, true, "Program");
+                        });
+                        ui.end_row();
+
+                        if !self.program {
+                            ui.label("Length");
+                            ui.add(
+                                egui::DragValue::new(&mut self.minutes)
+                                    .range(0.1..=180.0)
+                                    .speed(0.5)
+                                    .suffix(" min"),
+                            );
+                            ui.end_row();
+                        }
+
+                        ui.label("Resolution");
+                        ui.horizontal(|ui| {
+                            for size in [[1280, 720], [1920, 1080], [3840, 2160]] {
+                                ui.selectable_value(&mut self.size, size, format!("{}p", size[1]));
+                            }
+                            ui.add(egui::DragValue::new(&mut self.size[0]).range(16..=7680));
+                            ui.label("×");
+                            ui.add(egui::DragValue::new(&mut self.size[1]).range(16..=4320));
+                            // yuv420p halves the colour resolution, so both
+                            // sides have to be even
+                            self.size = self.size.map(|n| n / 2 * 2);
+                        });
+                        ui.end_row();
+
+                        ui.label("Frame rate");
+                        ui.horizontal(|ui| {
+                            for fps in [30, 60, 120] {
+                                ui.selectable_value(&mut self.fps, fps, format!("{fps} fps"));
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Format");
+                        ui.horizontal(|ui| {
+                            for format in VideoFormat::ALL {
+                                ui.radio_value(&mut self.format, format, format.name());
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Audio");
+                        ui.add_enabled(has_audio, egui::Checkbox::new(&mut self.audio, "Include"))
+                            .on_disabled_hover_text("No audio generator is switched on");
+                        ui.end_row();
+                    });
+
+                let fps = self.fps as f32;
+                let uneven = frequencies
+                    .iter()
+                    .any(|&hz| !refresh::is_representable(hz, fps));
+                if frequencies.iter().any(|&hz| hz > fps / 2.0) {
+                    ui.colored_label(
+                        Color32::LIGHT_RED,
+                        "The frequency is above half the frame rate and won't flicker \
+                         properly. Use a higher frame rate.",
+                    );
+                } else if uneven {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        "Not every frequency is a whole number of frames per cycle at \
+                         this frame rate, so some flashes will be a frame longer.",
+                    );
+                }
+                if let Some(mode) = gpu_modes.first() {
+                    ui.colored_label(
+                        Color32::LIGHT_RED,
+                        format!("{mode} is drawn on the GPU and can't be exported."),
+                    );
+                }
+                ui.label(
+                    "The mask, fixation point and Image Flash pictures aren't \
+                     included. Encoding needs ffmpeg on the PATH.",
+                );
+                ui.separator();
+                if ui
+                    .add_enabled(gpu_modes.is_empty(), egui::Button::new("Export…"))
+                    .clicked()
+                {
+                    export = true;
+                }
+            });
+        self.open = open && !export;
+        export
+    }
+}
+
+/// ffmpeg reading raw RGBA frames from a pipe. The frames are written from a
+/// thread of their own so a slow encoder doesn't stall the UI.
+struct Encoder {
+    frames: SyncSender<Vec<u8>>,
+    // a frame the queue was full for, sent before the next one
+    pending: Option<Vec<u8>>,
+    writer: JoinHandle<io::Result<()>>,
+    child: Child,
+}
+
+impl Encoder {
+    /// Start ffmpeg writing `path`, muxing in `soundtrack` if there is one.
+    fn spawn(
+        path: &Path,
+        [w, h]: [usize; 2],
+        fps: u32,
+        format: VideoFormat,
+        soundtrack: Option<&Path>,
+    ) -> Result<Self> {
+        let mut command = Command::new("ffmpeg");
+        command
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgba",
+            ])
+            .args(["-s", &format!("{w}x{h}"), "-r", &fps.to_string(), "-i", "-"]);
+        if let Some(soundtrack) = soundtrack {
+            command
+                .arg("-i")
+                .arg(soundtrack)
+                .args(format.audio_codec_args())
+                .arg("-shortest");
+        }
+        let mut child = command
+            .args(format.codec_args())
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(Error::Ffmpeg)?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let (frames, rx) = mpsc::sync_channel::<Vec<u8>>(QUEUE);
+        let writer = thread::spawn(move || {
+            for frame in rx {
+                stdin.write_all(&frame)?;
+            }
+            Ok(())
+        });
+        Ok(Self {
+            frames,
+            pending: None,
+            writer,
+            child,
+        })
+    }
+
+    /// Queue a frame without blocking. If the queue is full the frame is
+    /// held back for [`Encoder::flush`]. False once the encoder has stopped
+    /// taking frames.
+    fn push(&mut self, image: &ColorImage) -> bool {
+        // frames are opaque, so premultiplied is plain RGBA
+        self.pending = Some(image.pixels.iter().flat_map(|c| c.to_array()).collect());
+        self.flush()
+    }
+
+    /// Try again to queue the held-back frame, if there is one. False once
+    /// the encoder has stopped taking frames.
+    fn flush(&mut self) -> bool {
+        let Some(frame) = self.pending.take() else {
+            return true;
+        };
+        match self.frames.try_send(frame) {
+            Ok(()) => true,
+            Err(TrySendError::Full(frame)) => {
+                self.pending = Some(frame);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    /// Whether a frame is still waiting for room in the queue.
+    fn is_behind(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Close the pipe and wait for ffmpeg to write the file.
+    fn finish(self, path: &Path) -> Result<()> {
+        if let Some(frame) = self.pending {
+            // the writer reports the failure if it's gone
+            let _ = self.frames.send(frame);
+        }
+        drop(self.frames);
+        let written = self.writer.join().expect("frame writer panicked");
+        let output = self.child.wait_with_output().map_err(Error::Ffmpeg)?;
+        if output.status.success() && written.is_ok() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = match stderr.lines().last() {
+            Some(line) => line.to_owned(),
+            None => output.status.to_string(),
+        };
+        Err(Error::Export {
+            path: path.to_owned(),
+            message,
+        })
+    }
+
+    fn kill(mut self) {
+        drop(self.frames);
+        if let Err(e) = self.child.kill() {
+            tracing::warn!("Couldn't stop ffmpeg: {e}");
+        }
+        let _ = self.writer.join();
+        let _ = self.child.wait();
+    }
+}
+
+/// The soundtrack being written on a thread of its own, before the video
+/// encoder starts.
+struct Mixing {
+    thread: JoinHandle<io::Result<()>>,
+    /// Samples written so far, for the progress bar.
+    written: Arc<AtomicU64>,
+    samples: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+/// An export in progress: the soundtrack first, if there is one, then the
+/// frames, rendered a few at a time between UI frames.
+pub struct VideoExport {
+    pub path: PathBuf,
+    /// Stages to follow; without one the whole export is a single mode.
+    pub program: Option<Program>,
+    pub session: SessionTimer,
+    // frequency without a program
+    hz: f32,
+    // the guard rails, which the program's frequencies are held to
+    range: RangeInclusive<f32>,
+    soundtrack: Option<PathBuf>,
+    mixing: Option<Mixing>,
+    renderer: OfflineRenderer,
+    // started once the soundtrack is written
+    encoder: Option<Encoder>,
+    flicker: Flicker,
+    start: Instant,
+    size: [usize; 2],
+    format: VideoFormat,
+    fps: u32,
+    frame: usize,
+    frames: usize,
+}
+
+/// What came of [`VideoExport::render_next`].
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rendered {
+    /// The frame is on its way to the encoder.
+    Queued,
+    /// The encoder is still busy with earlier frames; try again later.
+    Behind,
+    /// The encoder has stopped or never started.
+    Failed,
+}
+
+impl VideoExport {
+    /// Start exporting to `path`. The export runs for the session's
+    /// duration from `start`, flickering like `flicker` from the dark start
+    /// of its cycle, at frequencies within `range`. With `audio` the
+    /// generators' output is written first, in the background, and muxed
+    /// in; call [`VideoExport::ready`] until it is done.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        settings: &ExportSettings,
+        path: PathBuf,
+        program: Option<Program>,
+        range: RangeInclusive<f32>,
+        session: SessionTimer,
+        mut flicker: Flicker,
+        audio: Option<AudioSettings>,
+        start: Instant,
+    ) -> Result<Self> {
+        let length = session.duration.unwrap_or_default().as_secs_f64();
+        flicker.reset(start);
+        let hz = flicker.frequency_hz();
+        let mut export = Self {
+            path,
+            program,
+            session,
+            hz,
+            range,
+            soundtrack: None,
+            mixing: None,
+            renderer: OfflineRenderer::new(settings.size),
+            encoder: None,
+            flicker,
+            start,
+            size: settings.size,
+            format: settings.format,
+            fps: settings.fps,
+            frame: 0,
+            frames: (length * settings.fps as f64).round() as usize,
+        };
+        match audio {
+            Some(audio) => {
+                let wav = env::temp_dir().join(format!("dreamachine-{}.wav", process::id()));
+                let track = Soundtrack {
+                    audio,
+                    flicker: export.flicker.clone(),
+                    program: export.program.clone(),
+                    range: export.range.clone(),
+                    session: export.session.clone(),
+                    hz,
+                    start,
+                };
+                let samples = (length * SAMPLE_RATE as f64).round() as u64;
+                let written = Arc::new(AtomicU64::new(0));
+                let cancel = Arc::new(AtomicBool::new(false));
+                let (thread_wav, thread_written, thread_cancel) =
+                    (wav.clone(), written.clone(), cancel.clone());
+                let thread = thread::Builder::new()
+                    .name("soundtrack".into())
+                    .spawn(move || {
+                        track.write(&thread_wav, samples, &thread_written, &thread_cancel)
+                    })
+                    .map_err(|e| Error::Export {
+                        path: export.path.clone(),
+                        message: format!("couldn't write the soundtrack: {e}"),
+                    })?;
+                export.soundtrack = Some(wav);
+                export.mixing = Some(Mixing {
+                    thread,
+                    written,
+                    samples,
+                    cancel,
+                });
+            }
+            None => export.ready().map(drop)?,
+        }
+        Ok(export)
+    }
+
+    /// Whether frames can be rendered: false while the soundtrack is still
+    /// being written. Starts the encoder once it is.
+    pub fn ready(&mut self) -> Result<bool> {
+        if let Some(mixing) = &self.mixing {
+            if !mixing.thread.is_finished() {
+                return Ok(false);
+            }
+            let mixing = self.mixing.take().expect("checked above");
+            let written = mixing.thread.join().expect("soundtrack writer panicked");
+            if let Err(e) = written {
+                return Err(Error::Export {
+                    path: self.path.clone(),
+                    message: format!("couldn't write the soundtrack: {e}"),
+                });
+            }
+        }
+        if self.encoder.is_none() {
+            self.encoder = Some(Encoder::spawn(
+                &self.path,
+                self.size,
+                self.fps,
+                self.format,
+                self.soundtrack.as_deref(),
+            )?);
+        }
+        Ok(true)
+    }
+
+    /// Flicker frequency `elapsed` into the export.
+    fn frequency_at(&self, elapsed: Duration) -> f32 {
+        frequency_at(self.program.as_ref(), self.hz, &self.range, elapsed)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame >= self.frames
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.frame as f32 / self.frames.max(1) as f32
+    }
+
+    /// Session time of the next frame.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64(self.frame as f64 / self.fps as f64)
+    }
+
+    /// Render the next frame with `paint` and queue it for the encoder,
+    /// unless the encoder hasn't caught up with the last one yet.
+    pub fn render_next(&mut self, mut paint: impl FnMut(&Painter, Rect, &Flicker)) -> Rendered {
+        let Some(encoder) = &mut self.encoder else {
+            return Rendered::Failed;
+        };
+        if !encoder.flush() {
+            return Rendered::Failed;
+        }
+        if encoder.is_behind() {
+            return Rendered::Behind;
+        }
+        let elapsed = self.elapsed();
+        self.flicker.set_frequency(self.frequency_at(elapsed));
+        self.flicker.update(self.start + elapsed);
+        let flicker = &self.flicker;
+        let image = self
+            .renderer
+            .render(|painter, rect| paint(painter, rect, flicker));
+        self.frame += 1;
+        let encoder = self.encoder.as_mut().expect("checked above");
+        if encoder.push(&image) {
+            Rendered::Queued
+        } else {
+            Rendered::Failed
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        let result = match self.encoder {
+            Some(encoder) => encoder.finish(&self.path),
+            None => Ok(()),
+        };
+        if let Some(wav) = &self.soundtrack {
+            remove(wav);
+        }
+        result
+    }
+
+    /// Stop early and delete the partial files.
+    pub fn cancel(self) {
+        if let Some(mixing) = self.mixing {
+            mixing.cancel.store(true, Ordering::Relaxed);
+            let _ = mixing.thread.join();
+        }
+        if let Some(encoder) = self.encoder {
+            encoder.kill();
+            remove(&self.path);
+        }
+        if let Some(wav) = &self.soundtrack {
+            remove(wav);
+        }
+    }
+
+    /// Progress window. Returns true when Cancel is pressed.
+    pub fn show(&self, ctx: &egui::Context) -> bool {
+        let mut cancel = false;
+        egui::Window::new("Exporting")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(self.path.display().to_string());
+                let total = self.session.duration.unwrap_or_default().as_secs();
+                let (label, progress, secs) = match &self.mixing {
+                    Some(mixing) => {
+                        let written = mixing.written.load(Ordering::Relaxed);
+                        let progress = written as f32 / mixing.samples.max(1) as f32;
+                        ("Soundtrack", progress, written / SAMPLE_RATE as u64)
+                    }
+                    None => ("Video", self.progress(), self.elapsed().as_secs()),
+                };
+                ui.add(egui::ProgressBar::new(progress).text(format!(
+                    "{label} {}:{:02} / {}:{:02}",
+                    secs / 60,
+                    secs % 60,
+                    total / 60,
+                    total % 60
+                )));
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        cancel
+    }
+}
+
+fn frequency_at(
+    program: Option<&Program>,
+    hz: f32,
+    range: &RangeInclusive<f32>,
+    elapsed: Duration,
+) -> f32 {
+    program
+        .and_then(|p| p.sample(elapsed))
+        .map_or(hz, |point| point.frequency_hz)
+        .clamp(*range.start(), *range.end())
+}
+
+fn remove(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        tracing::warn!("Couldn't remove {}: {e}", path.display());
+    }
+}
+
+/// The audio generators driven through an export the way the timing thread
+/// drives them live: the beat follows the frequency, the isochronic gate the
+/// flashes and the level the session fades.
+struct Soundtrack {
+    audio: AudioSettings,
+    flicker: Flicker,
+    program: Option<Program>,
+    range: RangeInclusive<f32>,
+    session: SessionTimer,
+    hz: f32,
+    start: Instant,
+}
+
+impl Soundtrack {
+    /// Write `samples` stereo frames of 16-bit WAV to `path`, counting them
+    /// in `written`, until done or `cancel` is set.
+    fn write(
+        mut self,
+        path: &Path,
+        samples: u64,
+        written: &AtomicU64,
+        cancel: &AtomicBool,
+    ) -> io::Result<()> {
+        let samples = samples.min(u32::MAX as u64) as u32;
+        let mut out = BufWriter::new(File::create(path)?);
+        write_wav_header(&mut out, samples)?;
+        let mut mixer = Mixer::default();
+        self.audio.playing = true;
+        for n in 0..samples {
+            // a tenth of a second at a time
+            if n % (SAMPLE_RATE / 10) == 0 {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                written.store(n as u64, Ordering::Relaxed);
+            }
+            let elapsed = Duration::from_secs_f64(n as f64 / SAMPLE_RATE as f64);
+            let hz = frequency_at(self.program.as_ref(), self.hz, &self.range, elapsed);
+            self.flicker.set_frequency(hz);
+            self.flicker.update(self.start + elapsed);
+            self.audio.beat_hz = hz;
+            self.audio.gate = self.flicker.show_white();
+            self.audio.level = self.session.intensity(elapsed);
+            for v in mixer.next(&self.audio, SAMPLE_RATE as f32) {
+                let sample = (v.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                out.write_all(&sample.to_le_bytes())?;
+            }
+        }
+        out.flush()
+    }
+}
+
+fn write_wav_header(out: &mut impl Write, samples: u32) -> io::Result<()> {
+    const CHANNELS: u16 = 2;
+    const BYTES_PER_SAMPLE: u16 = 2;
+    let block = CHANNELS * BYTES_PER_SAMPLE;
+    let data = samples.saturating_mul(block as u32);
+    out.write_all(b"RIFF")?;
+    out.write_all(&data.saturating_add(36).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // PCM
+    out.write_all(&CHANNELS.to_le_bytes())?;
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * block as u32).to_le_bytes())?;
+    out.write_all(&block.to_le_bytes())?;
+    out.write_all(&(BYTES_PER_SAMPLE * 8).to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data.to_le_bytes())
+}
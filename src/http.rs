@@ -0,0 +1,155 @@
+//! The HTTP side of the remote control, for curl and home automation.
+//!
+//! - `GET /status`
+//! - `POST /start`, or `POST /start/<preset>` for a built-in program or a
+//!   saved preset, e.g. `/start/theta-meditation`
+//! - `POST /stop`
+//! - `POST /command` with a JSON request body, as sent over the WebSocket
+//!
+//! Every reply is a JSON response from `dreamachine_core::remote`.
+//!
+//! A POST needs `Content-Type: application/json` or the pairing token, so a
+//! form on some web page can't start a session:
+//!
+//! ```sh
+//! curl -X POST -H 'Content-Type: application/json' localhost:8766/stop
+//! ```
+
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use dreamachine_core::remote::{Request, Response};
+use tiny_http::{Header, Method};
+
+use crate::remote::{Access, Caller, POLL, Server, presented_token};
+
+/// Largest `/command` body read.
+const MAX_BODY: u64 = 1 << 20;
+
+/// Listen on `addr`.
+pub fn start(addr: SocketAddr, caller: Caller, access: Access) -> io::Result<Server> {
+    let http = tiny_http::Server::http(addr).map_err(io::Error::other)?;
+    let addr = http
+        .server_addr()
+        .to_ip()
+        .ok_or_else(|| io::Error::other("not an IP address"))?;
+    let server = Server::spawn("http", addr, move |stop| serve(http, caller, &access, stop))?;
+    tracing::info!("HTTP control listening on {addr}");
+    Ok(server)
+}
+
+fn serve(http: tiny_http::Server, caller: Caller, access: &Access, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        let mut request = match http.recv_timeout(POLL) {
+            Ok(Some(request)) => request,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("HTTP receive failed: {e}");
+                continue;
+            }
+        };
+        let (code, response) = match refusal(&request, access) {
+            Some(refused) => refused,
+            None => route(&mut request, &caller),
+        };
+        let json = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .expect("valid header");
+        let reply = tiny_http::Response::from_string(response.to_json())
+            .with_status_code(code)
+            .with_header(json);
+        if let Err(e) = request.respond(reply) {
+            tracing::debug!("HTTP reply failed: {e}");
+        }
+    }
+}
+
+/// The status code and error for a request that isn't let in.
+fn refusal(request: &tiny_http::Request, access: &Access) -> Option<(u16, Response)> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str())
+    };
+    let token = presented_token(header("Authorization"), request.url());
+    // with no address to go by, treat the client as another device
+    let peer = request
+        .remote_addr()
+        .map_or(Ipv4Addr::UNSPECIFIED.into(), |addr| addr.ip());
+    if let Some(reason) = access.refusal(peer, header("Origin"), token) {
+        tracing::warn!("Refused HTTP client {peer}: {reason}");
+        return Some((403, Response::error(reason)));
+    }
+    // browsers send forms without asking first, but never JSON
+    let json = header("Content-Type")
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if *request.method() == Method::Post && !json && !access.paired(token) {
+        return Some((
+            415,
+            Response::error("send Content-Type: application/json or the pairing token"),
+        ));
+    }
+    None
+}
+
+/// The status code and response for `request`.
+fn route(request: &mut tiny_http::Request, caller: &Caller) -> (u16, Response) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let call = match (&method, segments.as_slice()) {
+        (Method::Get, ["status"]) => Request::Status,
+        (Method::Post, ["start"]) => Request::Start,
+        (Method::Post, ["start", preset]) => Request::StartPreset {
+            preset: decode(preset),
+        },
+        (Method::Post, ["stop"]) => Request::Stop,
+        (Method::Post, ["command"]) => {
+            let mut body = String::new();
+            let read = request.as_reader().take(MAX_BODY).read_to_string(&mut body);
+            if let Err(e) = read {
+                return (400, Response::error(e.to_string()));
+            }
+            match Request::parse(&body) {
+                Ok(call) => call,
+                Err(e) => return (400, Response::error(e)),
+            }
+        }
+        (_, ["status" | "start" | "stop" | "command"] | ["start", _]) => {
+            return (
+                405,
+                Response::error(format!("{method} isn't allowed on {path}")),
+            );
+        }
+        _ => return (404, Response::error(format!("no endpoint {path}"))),
+    };
+    let response = caller.call(call);
+    let code = if response.is_ok() { 200 } else { 409 };
+    (code, response)
+}
+
+/// Undo percent-encoding in a path segment.
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = segment
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
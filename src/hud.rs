@@ -0,0 +1,109 @@
+//! Translucent on-screen readout of the running session.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use egui::Color32;
+
+/// How long the HUD stays fully visible after the mouse last moved.
+const HOLD: Duration = Duration::from_secs(3);
+/// How long it then takes to fade out.
+const FADE: Duration = Duration::from_secs(1);
+/// Window over which the actual flash rate is measured.
+const RATE_WINDOW: Duration = Duration::from_secs(2);
+
+pub struct HudInfo<'a> {
+    pub mode: &'a str,
+    pub frequency_hz: f32,
+    pub elapsed: Duration,
+    pub remaining: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct Hud {
+    pub enabled: bool,
+    // rising edges of the flicker as actually drawn, for the measured rate
+    flashes: VecDeque<Instant>,
+    was_white: bool,
+    // `None` until the first frame shown
+    last_activity: Option<Instant>,
+}
+
+impl Hud {
+    /// Note what was drawn this frame.
+    pub fn record(&mut self, now: Instant, show_white: bool) {
+        if show_white && !self.was_white {
+            self.flashes.push_back(now);
+        }
+        self.was_white = show_white;
+        while self
+            .flashes
+            .front()
+            .is_some_and(|&t| now.duration_since(t) > RATE_WINDOW)
+        {
+            self.flashes.pop_front();
+        }
+    }
+
+    /// Flashes per second over the last couple of seconds, as rendered.
+    pub fn measured_hz(&self) -> Option<f32> {
+        let (first, last) = (self.flashes.front()?, self.flashes.back()?);
+        let span = last.duration_since(*first).as_secs_f32();
+        (span > 0.0).then(|| (self.flashes.len() - 1) as f32 / span)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, now: Instant, info: &HudInfo) {
+        if !self.enabled {
+            return;
+        }
+        if ctx.input(|i| i.pointer.delta() != egui::Vec2::ZERO) {
+            self.last_activity = Some(now);
+        }
+        let last_activity = *self.last_activity.get_or_insert(now);
+        let idle = now.duration_since(last_activity).saturating_sub(HOLD);
+        let opacity = 1.0 - idle.as_secs_f32() / FADE.as_secs_f32();
+        if opacity <= 0.0 {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("hud"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-12.0, -12.0])
+            .interactable(false)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.multiply_opacity(opacity);
+                egui::Frame::new()
+                    .fill(Color32::from_black_alpha(160))
+                    .corner_radius(6.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        let text = |ui: &mut egui::Ui, s: String| {
+                            ui.label(
+                                egui::RichText::new(s)
+                                    .color(Color32::LIGHT_GRAY)
+                                    .monospace(),
+                            );
+                        };
+                        text(ui, info.mode.to_owned());
+                        let measured = self
+                            .measured_hz()
+                            .map_or_else(|| "–".to_owned(), |hz| format!("{hz:.1}"));
+                        text(
+                            ui,
+                            format!("{:.1} Hz (measured {measured})", info.frequency_hz),
+                        );
+                        let mut time = format!("Elapsed {}", clock(info.elapsed));
+                        if let Some(remaining) = info.remaining {
+                            time += &format!("  Left {}", clock(remaining));
+                        }
+                        text(ui, time);
+                    });
+            });
+    }
+}
+
+fn clock(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,435 @@
+//! Philips Hue bulbs in an entertainment area, streamed to through the
+//! bridge.
+//!
+//! The bridge has to be paired once, by pressing its link button and then
+//! Pair, which stores an application key and the streaming key. Areas are
+//! set up in the Hue app.
+
+use std::io::{self, Read, Write};
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dreamachine_core::lights::Sample;
+use dreamachine_core::lights::hue;
+use eframe::egui::{self, Color32};
+use openssl::error::ErrorStack;
+use openssl::ssl::{SslConnector, SslMethod, SslStream, SslVerifyMode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::lights::{Device, Sink};
+
+/// Timeout for requests to the bridge.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Area {
+    pub id: String,
+    pub name: String,
+    pub channels: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HueSettings {
+    pub enabled: bool,
+    /// The bridge's address.
+    pub bridge: String,
+    /// Application key from pairing; empty until paired.
+    pub app_key: String,
+    /// Streaming key from pairing, in hex.
+    pub client_key: String,
+    pub area: Option<Area>,
+    /// Channels of the area that flash; empty for all of them.
+    pub channels: Vec<u8>,
+    /// How far ahead to send, to make up for the bridge and bulbs.
+    pub latency_ms: f32,
+    /// Frames per second; the bridge passes on about 25.
+    pub rate: f32,
+    // what the last Pair or Refresh said
+    #[serde(skip)]
+    message: Option<Result<String, String>>,
+    #[serde(skip)]
+    areas: Vec<Area>,
+}
+
+impl Default for HueSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bridge: String::new(),
+            app_key: String::new(),
+            client_key: String::new(),
+            area: None,
+            channels: Vec::new(),
+            latency_ms: 80.0,
+            rate: 50.0,
+            message: None,
+            areas: Vec::new(),
+        }
+    }
+}
+
+impl HueSettings {
+    fn pair(&mut self) {
+        match Api::new(self.bridge.trim(), "").and_then(|api| api.pair()) {
+            Ok((app_key, client_key)) => {
+                self.app_key = app_key;
+                self.client_key = client_key;
+                self.refresh_areas();
+            }
+            Err(e) => self.message = Some(Err(e.to_string())),
+        }
+    }
+
+    fn refresh_areas(&mut self) {
+        let result = Api::new(self.bridge.trim(), &self.app_key).and_then(|api| api.areas());
+        self.message = Some(match result {
+            Ok(areas) => {
+                let found = format!("Paired; {} entertainment areas", areas.len());
+                self.areas = areas;
+                Ok(found)
+            }
+            Err(e) => Err(e.to_string()),
+        });
+    }
+
+    /// The channels to send, within the area.
+    fn selected(&self, area: &Area) -> Vec<u8> {
+        if self.channels.is_empty() {
+            return area.channels.clone();
+        }
+        let selected = area.channels.iter().filter(|c| self.channels.contains(c));
+        selected.copied().collect()
+    }
+}
+
+impl Device for HueSettings {
+    type Sink = HueStream;
+
+    const NAME: &'static str = "Philips Hue";
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Bridge");
+            ui.text_edit_singleline(&mut self.bridge);
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("Pair")
+                .on_hover_text("Press the link button on the bridge first")
+                .clicked()
+            {
+                self.pair();
+            }
+            let paired = !self.app_key.is_empty();
+            if ui
+                .add_enabled(paired, egui::Button::new("Refresh Areas"))
+                .clicked()
+            {
+                self.refresh_areas();
+            }
+        });
+        match &self.message {
+            Some(Ok(text)) => {
+                ui.label(text.as_str());
+            }
+            Some(Err(error)) => {
+                ui.colored_label(Color32::LIGHT_RED, error.as_str());
+            }
+            None => {}
+        }
+        let selected = self.area.as_ref().map_or("None", |a| a.name.as_str());
+        let mut area = self.area.clone();
+        egui::ComboBox::from_label("Area")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for choice in &self.areas {
+                    ui.selectable_value(&mut area, Some(choice.clone()), &choice.name);
+                }
+            });
+        if area != self.area {
+            self.area = area;
+            self.channels.clear();
+        }
+        if let Some(area) = &self.area {
+            ui.label("Channels");
+            let mut selected = self.selected(area);
+            let mut changed = false;
+            for &channel in &area.channels {
+                let mut on = selected.contains(&channel);
+                if ui.checkbox(&mut on, format!("Channel {channel}")).changed() {
+                    changed = true;
+                    selected.retain(|&c| c != channel);
+                    if on {
+                        selected.push(channel);
+                    }
+                }
+            }
+            if changed {
+                // all of them is stored as none, so new bulbs join in
+                self.channels = if selected.len() == area.channels.len() {
+                    Vec::new()
+                } else {
+                    selected
+                };
+            }
+        }
+        egui::Grid::new("hue_settings").show(ui, |ui| {
+            ui.label("Latency");
+            ui.add(
+                egui::DragValue::new(&mut self.latency_ms)
+                    .range(0.0..=500.0)
+                    .suffix(" ms"),
+            )
+            .on_hover_text("Send this far ahead of the screen, so the bulbs flash with it");
+            ui.end_row();
+            ui.label("Rate");
+            ui.add(
+                egui::DragValue::new(&mut self.rate)
+                    .range(1.0..=60.0)
+                    .suffix(" fps"),
+            );
+            ui.end_row();
+        });
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.rate.clamp(1.0, 60.0))
+    }
+
+    fn lead(&self) -> Duration {
+        Duration::from_secs_f32(self.latency_ms.max(0.0) / 1000.0)
+    }
+
+    fn connect(&self) -> io::Result<HueStream> {
+        let Some(area) = &self.area else {
+            return Err(io::Error::other("no area chosen"));
+        };
+        if self.app_key.is_empty() {
+            return Err(io::Error::other("the bridge isn't paired"));
+        }
+        let api = Api::new(self.bridge.trim(), &self.app_key)?;
+        let identity = api.application_id()?;
+        let key = decode_hex(&self.client_key)?;
+        api.set_streaming(&area.id, true)?;
+        let stream = match dtls(self.bridge.trim(), identity, key) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = api.set_streaming(&area.id, false);
+                return Err(e);
+            }
+        };
+        Ok(HueStream {
+            stream,
+            api,
+            area_id: area.id.clone(),
+            channels: self.selected(area),
+            sequence: 0,
+        })
+    }
+}
+
+pub struct HueStream {
+    stream: SslStream<Udp>,
+    api: Api,
+    area_id: String,
+    channels: Vec<u8>,
+    sequence: u8,
+}
+
+impl Sink for HueStream {
+    fn show(&mut self, sample: &Sample) -> io::Result<()> {
+        self.sequence = self.sequence.wrapping_add(1);
+        let message = hue::message(&self.area_id, self.sequence, &self.channels, sample.rgb());
+        self.stream.write_all(&message)
+    }
+}
+
+impl Drop for HueStream {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown();
+        if let Err(e) = self.api.set_streaming(&self.area_id, false) {
+            tracing::warn!("Couldn't stop Hue streaming: {e}");
+        }
+    }
+}
+
+/// The bridge's REST API.
+struct Api {
+    agent: ureq::Agent,
+    bridge: String,
+    app_key: String,
+}
+
+impl Api {
+    fn new(bridge: &str, app_key: &str) -> io::Result<Self> {
+        if bridge.is_empty() {
+            return Err(io::Error::other("no bridge address"));
+        }
+        // bridges have self-signed certificates
+        let tls = native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()
+            .map_err(io::Error::other)?;
+        let agent = ureq::AgentBuilder::new()
+            .timeout(TIMEOUT)
+            .tls_connector(Arc::new(tls))
+            .build();
+        Ok(Self {
+            agent,
+            bridge: bridge.into(),
+            app_key: app_key.into(),
+        })
+    }
+
+    /// Register with the bridge, returning the application key and the
+    /// streaming key.
+    fn pair(&self) -> io::Result<(String, String)> {
+        let body = json!({
+            "devicetype": "dreamachine#desktop",
+            "generateclientkey": true,
+        });
+        let reply: Value = self
+            .agent
+            .post(&format!("https://{}/api", self.bridge))
+            .send_json(body)
+            .map_err(io::Error::other)?
+            .into_json()?;
+        let reply = &reply[0];
+        if let Some(error) = reply["error"]["description"].as_str() {
+            return Err(io::Error::other(error.to_string()));
+        }
+        let field = |name: &str| {
+            reply["success"][name]
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| io::Error::other("unexpected reply from the bridge"))
+        };
+        Ok((field("username")?, field("clientkey")?))
+    }
+
+    fn areas(&self) -> io::Result<Vec<Area>> {
+        let reply: Value = self
+            .agent
+            .get(&self.url("entertainment_configuration"))
+            .set("hue-application-key", &self.app_key)
+            .call()
+            .map_err(io::Error::other)?
+            .into_json()?;
+        let areas = reply["data"].as_array().into_iter().flatten();
+        let areas = areas.filter_map(|area| {
+            let channels = area["channels"].as_array().into_iter().flatten();
+            Some(Area {
+                id: area["id"].as_str()?.into(),
+                name: area["metadata"]["name"]
+                    .as_str()
+                    .unwrap_or("Unnamed")
+                    .into(),
+                channels: channels
+                    .filter_map(|c| c["channel_id"].as_u64())
+                    .map(|id| id as u8)
+                    .collect(),
+            })
+        });
+        Ok(areas.collect())
+    }
+
+    /// The identity to stream under.
+    fn application_id(&self) -> io::Result<String> {
+        let response = self
+            .agent
+            .get(&format!("https://{}/auth/v1", self.bridge))
+            .set("hue-application-key", &self.app_key)
+            .call()
+            .map_err(io::Error::other)?;
+        let id = response.header("hue-application-id");
+        id.map(String::from)
+            .ok_or_else(|| io::Error::other("the bridge didn't give an application id"))
+    }
+
+    fn set_streaming(&self, area_id: &str, on: bool) -> io::Result<()> {
+        let action = if on { "start" } else { "stop" };
+        self.agent
+            .put(&self.url(&format!("entertainment_configuration/{area_id}")))
+            .set("hue-application-key", &self.app_key)
+            .send_json(json!({ "action": action }))
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    fn url(&self, resource: &str) -> String {
+        format!("https://{}/clip/v2/resource/{resource}", self.bridge)
+    }
+}
+
+/// The DTLS session to the bridge's streaming port.
+fn dtls(bridge: &str, identity: String, key: Vec<u8>) -> io::Result<SslStream<Udp>> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.connect((bridge, hue::PORT))?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+    let mut builder = SslConnector::builder(SslMethod::dtls()).map_err(io::Error::other)?;
+    builder
+        .set_cipher_list("PSK-AES128-GCM-SHA256")
+        .map_err(io::Error::other)?;
+    builder.set_verify(SslVerifyMode::NONE);
+    builder.set_psk_client_callback(move |_, _, identity_out, key_out| {
+        // the identity is a C string
+        let id = identity.as_bytes();
+        if id.len() >= identity_out.len() || key.len() > key_out.len() {
+            return Err(ErrorStack::get());
+        }
+        identity_out[..id.len()].copy_from_slice(id);
+        identity_out[id.len()] = 0;
+        key_out[..key.len()].copy_from_slice(&key);
+        Ok(key.len())
+    });
+    let connector = builder.build();
+    let config = connector.configure().map_err(io::Error::other)?;
+    config
+        .use_server_name_indication(false)
+        .verify_hostname(false)
+        .connect(bridge, Udp(socket))
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// A connected UDP socket as a byte stream, for DTLS.
+#[derive(Debug)]
+struct Udp(UdpSocket);
+
+impl Read for Udp {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+}
+
+impl Write for Udp {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn decode_hex(hex: &str) -> io::Result<Vec<u8>> {
+    let invalid = || io::Error::other("the streaming key isn't valid hex");
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            let byte = hex.get(i..i + 2).ok_or_else(invalid)?;
+            u8::from_str_radix(byte, 16).map_err(|_| invalid())
+        })
+        .collect()
+}
@@ -0,0 +1,22 @@
+//! Keeping the screensaver and display sleep away while a session runs.
+
+use keepawake::KeepAwake;
+
+/// Hold off the screensaver and display sleep until the returned guard is
+/// dropped; `None` if the OS refused.
+pub fn keep_awake() -> Option<KeepAwake> {
+    match keepawake::Builder::default()
+        .display(true)
+        .idle(true)
+        .reason("Flicker session running")
+        .app_name("Dreamachine")
+        .app_reverse_domain("io.github.jefrecantuledesma.dreamachine")
+        .create()
+    {
+        Ok(guard) => Some(guard),
+        Err(err) => {
+            tracing::warn!("Failed to inhibit the screensaver: {}", err);
+            None
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Notes and ratings asked for after a session.
+
+use dreamachine_core::history::Journal;
+use eframe::egui;
+
+pub enum Answer {
+    Save,
+    Skip,
+}
+
+/// The prompt for one session in the history.
+pub struct JournalPrompt {
+    /// Index of the session's record.
+    pub record: usize,
+    pub journal: Journal,
+}
+
+impl JournalPrompt {
+    pub fn new(record: usize) -> Self {
+        Self {
+            record,
+            journal: Journal::default(),
+        }
+    }
+
+    /// Returns the answer once one of the buttons is pressed.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Answer> {
+        let mut answer = None;
+        egui::Window::new("How Was It?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("journal_ratings").show(ui, |ui| {
+                    ui.label("Relaxation");
+                    rating(ui, &mut self.journal.relaxation);
+                    ui.end_row();
+                    ui.label("Imagery");
+                    rating(ui, &mut self.journal.imagery)
+                        .on_hover_text("How vivid the closed-eye visuals were");
+                    ui.end_row();
+                });
+                ui.label("Notes");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.journal.notes)
+                        .desired_rows(5)
+                        .hint_text("Colours, patterns, thoughts…"),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        answer = Some(Answer::Save);
+                    }
+                    if ui.button("Skip").clicked() {
+                        answer = Some(Answer::Skip);
+                    }
+                });
+            });
+        answer
+    }
+}
+
+/// 1–5 buttons; clicking the chosen one again clears it.
+fn rating(ui: &mut egui::Ui, value: &mut Option<u8>) -> egui::Response {
+    ui.horizontal(|ui| {
+        for n in 1..=5 {
+            let selected = *value == Some(n);
+            if ui.selectable_label(selected, n.to_string()).clicked() {
+                *value = (!selected).then_some(n);
+            }
+        }
+    })
+    .response
+}
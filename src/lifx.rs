@@ -0,0 +1,236 @@
+//! LIFX bulbs over the LAN protocol.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use dreamachine_core::lights::Sample;
+use dreamachine_core::lights::lifx::{self, Hsbk};
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+
+use crate::lights::{Device, Sink};
+
+/// How long Find Bulbs listens for answers.
+const DISCOVERY: Duration = Duration::from_millis(500);
+/// LIFX asks for no more than 20 messages a second to each bulb.
+const MAX_RATE: f32 = 20.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bulb {
+    /// Host name or address.
+    pub host: String,
+    /// Shown beside the address; from the bulb when found on the network.
+    pub label: String,
+    /// Brightest this bulb goes, `0..=1`, to keep bright ones from glaring.
+    pub max_brightness: f32,
+}
+
+impl Default for Bulb {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            label: String::new(),
+            max_brightness: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifxSettings {
+    pub enabled: bool,
+    pub bulbs: Vec<Bulb>,
+    /// Frames per second, up to [`MAX_RATE`].
+    pub rate: f32,
+    // what the last Find Bulbs said
+    #[serde(skip)]
+    message: Option<Result<String, String>>,
+}
+
+impl Default for LifxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bulbs: Vec::new(),
+            rate: MAX_RATE,
+            message: None,
+        }
+    }
+}
+
+impl LifxSettings {
+    /// Ask every bulb on the network for its label, and add the new ones.
+    fn find_bulbs(&mut self) {
+        self.message = Some(match discover() {
+            Ok(found) => {
+                let mut added = 0;
+                for (host, label) in found {
+                    if let Some(bulb) = self.bulbs.iter_mut().find(|b| b.host.trim() == host) {
+                        bulb.label = label;
+                    } else {
+                        self.bulbs.push(Bulb {
+                            host,
+                            label,
+                            ..Bulb::default()
+                        });
+                        added += 1;
+                    }
+                }
+                Ok(format!("Found {added} new bulbs"))
+            }
+            Err(e) => Err(e.to_string()),
+        });
+    }
+}
+
+/// The address and label of every bulb that answers within [`DISCOVERY`].
+fn discover() -> io::Result<Vec<(String, String)>> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&lifx::get_label(source()), ("255.255.255.255", lifx::PORT))?;
+    let deadline = Instant::now() + DISCOVERY;
+    let mut found: Vec<(String, String)> = Vec::new();
+    let mut buf = [0; 128];
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        socket.set_read_timeout(Some(deadline - now))?;
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                let host = from.ip().to_string();
+                if let Some(label) = lifx::parse_label(&buf[..len])
+                    && !found.iter().any(|(h, _)| *h == host)
+                {
+                    found.push((host, label));
+                }
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(found)
+}
+
+/// Identifies us to the bulbs; anything but 0, which asks for broadcast
+/// replies.
+fn source() -> u32 {
+    std::process::id().max(1)
+}
+
+impl Device for LifxSettings {
+    type Sink = Lifx;
+
+    const NAME: &'static str = "LIFX";
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Bulbs");
+        let mut remove = None;
+        for (i, bulb) in self.bulbs.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut bulb.host);
+                if !bulb.label.is_empty() {
+                    ui.label(bulb.label.as_str());
+                }
+                ui.add(egui::Slider::new(&mut bulb.max_brightness, 0.0..=1.0).text("Max"))
+                    .on_hover_text("Brightest this bulb goes");
+                if ui.small_button("×").on_hover_text("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            self.bulbs.remove(i);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add Bulb").clicked() {
+                self.bulbs.push(Bulb::default());
+            }
+            if ui
+                .button("Find Bulbs")
+                .on_hover_text("Look for bulbs on the local network")
+                .clicked()
+            {
+                self.find_bulbs();
+            }
+        });
+        match &self.message {
+            Some(Ok(text)) => {
+                ui.label(text.as_str());
+            }
+            Some(Err(error)) => {
+                ui.colored_label(Color32::LIGHT_RED, error.as_str());
+            }
+            None => {}
+        }
+        egui::Grid::new("lifx_settings").show(ui, |ui| {
+            ui.label("Rate");
+            ui.add(
+                egui::DragValue::new(&mut self.rate)
+                    .range(1.0..=MAX_RATE)
+                    .suffix(" fps"),
+            )
+            .on_hover_text("Bulbs drop messages sent faster than 20 a second");
+            ui.end_row();
+        });
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.rate.clamp(1.0, MAX_RATE))
+    }
+
+    fn connect(&self) -> io::Result<Lifx> {
+        let mut targets = Vec::new();
+        for bulb in self.bulbs.iter().filter(|b| !b.host.trim().is_empty()) {
+            let host = bulb.host.trim();
+            let addr = (host, lifx::PORT)
+                .to_socket_addrs()?
+                .find(SocketAddr::is_ipv4)
+                .ok_or_else(|| io::Error::other(format!("{host} has no IPv4 address")))?;
+            targets.push((addr, bulb.max_brightness.clamp(0.0, 1.0)));
+        }
+        if targets.is_empty() {
+            return Err(io::Error::other("no bulbs"));
+        }
+        Ok(Lifx {
+            socket: UdpSocket::bind(("0.0.0.0", 0))?,
+            targets,
+            source: source(),
+            sequence: 0,
+        })
+    }
+}
+
+pub struct Lifx {
+    socket: UdpSocket,
+    /// Each bulb and its brightness cap.
+    targets: Vec<(SocketAddr, f32)>,
+    source: u32,
+    sequence: u8,
+}
+
+impl Sink for Lifx {
+    fn show(&mut self, sample: &Sample) -> io::Result<()> {
+        self.sequence = self.sequence.wrapping_add(1);
+        for (target, cap) in &self.targets {
+            let color = Hsbk::from_rgb(sample.rgb(), *cap);
+            let packet = lifx::set_color(self.source, self.sequence, color);
+            self.socket.send_to(&packet, target)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! External lights that flash with the screen. Each output runs on its own
+//! thread, sampling the shared [`LightFeed`] at its own rate.
+//!
+//! A light too slow for the frequency holds the average level instead of
+//! flickering at a false, slower rate.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use dreamachine_core::Clock;
+use dreamachine_core::lights::{LightFeed, Sample};
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+
+use crate::artnet::ArtNetSettings;
+use crate::hue::HueSettings;
+use crate::lifx::LifxSettings;
+use crate::serial::SerialSettings;
+use crate::wled::WledSettings;
+
+/// A kind of light, described by its settings.
+pub trait Device: Clone + Send + 'static {
+    type Sink: Sink;
+
+    /// Shown in the Lights menu.
+    const NAME: &'static str;
+
+    fn enabled(&mut self) -> &mut bool;
+
+    /// Connection and patch settings, without the enable switch.
+    fn settings_ui(&mut self, ui: &mut egui::Ui);
+
+    /// Time between frames.
+    fn interval(&self) -> Duration;
+
+    /// How far ahead of the screen to sample, making up for the light's
+    /// own delay.
+    fn lead(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Open the connection. Runs on the output's thread, so it may block.
+    fn connect(&self) -> io::Result<Self::Sink>;
+}
+
+/// A connected light.
+pub trait Sink {
+    fn show(&mut self, sample: &Sample) -> io::Result<()>;
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightSettings {
+    pub artnet: ArtNetSettings,
+    pub wled: WledSettings,
+    pub hue: HueSettings,
+    pub lifx: LifxSettings,
+    pub serial: SerialSettings,
+}
+
+/// Every light output, started and stopped to match its settings.
+pub struct Lights {
+    artnet: Slot<ArtNetSettings>,
+    wled: Slot<WledSettings>,
+    hue: Slot<HueSettings>,
+    lifx: Slot<LifxSettings>,
+    serial: Slot<SerialSettings>,
+}
+
+impl Lights {
+    pub fn new(
+        settings: LightSettings,
+        feed: Arc<Mutex<LightFeed>>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            artnet: Slot::new(settings.artnet, &feed, &clock),
+            wled: Slot::new(settings.wled, &feed, &clock),
+            hue: Slot::new(settings.hue, &feed, &clock),
+            lifx: Slot::new(settings.lifx, &feed, &clock),
+            serial: Slot::new(settings.serial, &feed, &clock),
+        }
+    }
+
+    pub fn settings(&self) -> LightSettings {
+        LightSettings {
+            artnet: self.artnet.device.clone(),
+            wled: self.wled.device.clone(),
+            hue: self.hue.device.clone(),
+            lifx: self.lifx.device.clone(),
+            serial: self.serial.device.clone(),
+        }
+    }
+
+    pub fn menu_ui(&mut self, ui: &mut egui::Ui) {
+        self.artnet.menu_ui(ui);
+        self.wled.menu_ui(ui);
+        self.hue.menu_ui(ui);
+        self.lifx.menu_ui(ui);
+        self.serial.menu_ui(ui);
+    }
+}
+
+/// One output and its thread, if enabled.
+struct Slot<D: Device> {
+    device: D,
+    feed: Arc<Mutex<LightFeed>>,
+    clock: Arc<dyn Clock>,
+    output: Option<Output>,
+}
+
+impl<D: Device> Slot<D> {
+    fn new(device: D, feed: &Arc<Mutex<LightFeed>>, clock: &Arc<dyn Clock>) -> Self {
+        let mut slot = Self {
+            device,
+            feed: feed.clone(),
+            clock: clock.clone(),
+            output: None,
+        };
+        slot.restart();
+        slot
+    }
+
+    fn restart(&mut self) {
+        self.output = None;
+        if *self.device.enabled() {
+            let (feed, clock) = (self.feed.clone(), self.clock.clone());
+            self.output = Some(Output::spawn(self.device.clone(), feed, clock));
+        }
+    }
+
+    fn menu_ui(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(D::NAME, |ui| {
+            if ui.checkbox(self.device.enabled(), "Enabled").changed() {
+                self.restart();
+            }
+            self.device.settings_ui(ui);
+            if ui
+                .add_enabled(self.output.is_some(), egui::Button::new("Apply"))
+                .on_hover_text("Reconnect with these settings")
+                .clicked()
+            {
+                self.restart();
+            }
+            if let Some(output) = &self.output {
+                match &*output.state.lock().unwrap() {
+                    Ok(text) => ui.label(text.as_str()),
+                    Err(error) => ui.colored_label(Color32::LIGHT_RED, error.as_str()),
+                };
+            }
+        });
+    }
+}
+
+/// A thread sending frames to one light. Dropping it turns the light off.
+struct Output {
+    /// What to show in the menu: how the light is doing, or what failed.
+    state: Arc<Mutex<Result<String, String>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Output {
+    fn spawn<D: Device>(device: D, feed: Arc<Mutex<LightFeed>>, clock: Arc<dyn Clock>) -> Self {
+        let state = Arc::new(Mutex::new(Ok("Connecting…".to_string())));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (thread_state, thread_stop) = (state.clone(), stop.clone());
+        let thread = thread::Builder::new()
+            .name(D::NAME.to_lowercase())
+            .spawn(move || run(device, feed, clock, thread_state, thread_stop));
+        let thread = match thread {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                *state.lock().unwrap() = Err(e.to_string());
+                None
+            }
+        };
+        Self {
+            state,
+            stop,
+            thread,
+        }
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run<D: Device>(
+    device: D,
+    feed: Arc<Mutex<LightFeed>>,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<Result<String, String>>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut sink = match device.connect() {
+        Ok(sink) => sink,
+        Err(e) => {
+            tracing::error!("Couldn't connect to {}: {e}", D::NAME);
+            *state.lock().unwrap() = Err(format!("Couldn't connect: {e}"));
+            return;
+        }
+    };
+    tracing::info!("Sending to {}", D::NAME);
+    *state.lock().unwrap() = Ok(sending(true));
+    let (interval, lead) = (device.interval(), device.lead());
+    // paced by the real clock; `clock` only says where the stimulus is
+    let mut next = Instant::now();
+    let mut failing = false;
+    let mut following = true;
+    while !stop.load(Ordering::Relaxed) {
+        let (sample, follows) = {
+            let feed = feed.lock().unwrap();
+            let when = clock.now() + lead;
+            (feed.sample_every(when, interval), feed.can_follow(interval))
+        };
+        if follows != following {
+            following = follows;
+            if !follows {
+                tracing::warn!(
+                    "{} updates too slowly for the frequency; holding a steady level",
+                    D::NAME
+                );
+            }
+            if !failing {
+                *state.lock().unwrap() = Ok(sending(follows));
+            }
+        }
+        match sink.show(&sample) {
+            Ok(()) if failing => {
+                failing = false;
+                *state.lock().unwrap() = Ok(sending(following));
+            }
+            Ok(()) => {}
+            // once per outage, not once per frame
+            Err(e) if !failing => {
+                failing = true;
+                tracing::warn!("{}: {e}", D::NAME);
+                *state.lock().unwrap() = Err(e.to_string());
+            }
+            Err(_) => {}
+        }
+        next += interval;
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        } else {
+            // fell behind; don't try to catch up
+            next = now;
+        }
+    }
+    let _ = sink.show(&Sample::OFF);
+}
+
+/// The state shown while frames go out.
+fn sending(follows: bool) -> String {
+    if follows {
+        "Sending".into()
+    } else {
+        "Sending a steady level: the frequency is over half the update rate".into()
+    }
+}
@@ -0,0 +1,45 @@
+//! Log output: stderr at the verbosity picked on the command line, plus an
+//! optional daily-rotated file that always records at least session events.
+
+use std::path::Path;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Start logging. `verbose` counts `-v` flags; `RUST_LOG` overrides it for
+/// stderr. The returned guard flushes the file log when dropped, so keep it
+/// alive until exit.
+pub fn init(verbose: u8, log_dir: Option<&Path>) -> Option<WorkerGuard> {
+    let level = match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let stderr_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("dreamachine={level}")));
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(stderr_filter);
+
+    let (file, guard) = match log_dir {
+        Some(dir) => {
+            let appender = tracing_appender::rolling::daily(dir, "dreamachine.log");
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_filter(level.max(LevelFilter::INFO));
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .init();
+    guard
+}
@@ -0,0 +1,84 @@
+//! Zeroconf announcements of the remote control servers, so companion apps
+//! find the app without an address typed in.
+//!
+//! Each server is a `_dreamachine._tcp` service with the TXT records
+//! `scheme` (`ws` or `http`) and `version`.
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+const SERVICE_TYPE: &str = "_dreamachine._tcp.local.";
+
+/// The mDNS responder, started with the first announcement.
+#[derive(Default)]
+pub struct Mdns {
+    daemon: Option<ServiceDaemon>,
+}
+
+impl Mdns {
+    /// Announce the server on `port` until the advertisement is dropped.
+    /// `label` tells the servers of one host apart.
+    pub fn advertise(
+        &mut self,
+        scheme: &str,
+        label: &str,
+        port: u16,
+    ) -> mdns_sd::Result<Advertisement> {
+        let daemon = match &self.daemon {
+            Some(daemon) => daemon.clone(),
+            None => self.daemon.insert(ServiceDaemon::new()?).clone(),
+        };
+        let host = host_name();
+        let properties = [("scheme", scheme), ("version", env!("CARGO_PKG_VERSION"))];
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &format!("Dreamachine on {host} ({label})"),
+            &format!("{host}.local."),
+            "",
+            port,
+            &properties[..],
+        )?
+        .enable_addr_auto();
+        let fullname = info.get_fullname().to_string();
+        daemon.register(info)?;
+        tracing::info!("Advertising {fullname}");
+        Ok(Advertisement { daemon, fullname })
+    }
+}
+
+impl Drop for Mdns {
+    fn drop(&mut self) {
+        if let Some(daemon) = self.daemon.take() {
+            let _ = daemon.shutdown();
+        }
+    }
+}
+
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        if let Err(e) = self.daemon.unregister(&self.fullname) {
+            tracing::debug!("Couldn't withdraw {}: {e}", self.fullname);
+        }
+    }
+}
+
+/// This machine's name as a DNS label.
+fn host_name() -> String {
+    let name: String = gethostname::gethostname()
+        .to_string_lossy()
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    if name.is_empty() {
+        "dreamachine".into()
+    } else {
+        name
+    }
+}
@@ -0,0 +1,192 @@
+//! MIDI input for the mapping in `dreamachine_core::midi`, so hardware knob
+//! controllers can drive a session.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use dreamachine_core::midi::{Control, Event, MidiAction, MidiMap};
+use eframe::egui::{self, Color32};
+use midir::{MidiInput, MidiInputConnection};
+
+use crate::command::Command;
+
+const CLIENT_NAME: &str = "Dreamachine";
+
+pub struct Midi {
+    pub map: MidiMap,
+    /// Waiting for a control to bind to this action.
+    learning: Option<MidiAction>,
+    connection: Option<MidiInputConnection<()>>,
+    ports: Vec<String>,
+    // pass the settings were last drawn in, to list the ports when they open
+    shown: Option<u64>,
+    sender: Sender<Event>,
+    events: Receiver<Event>,
+    // last value of each control, to see pads and buttons go down
+    last: HashMap<Control, f32>,
+    error: Option<String>,
+    ctx: egui::Context,
+}
+
+impl Midi {
+    /// Open the port saved in `map`, if any. `ctx` is woken for every
+    /// message.
+    pub fn new(map: MidiMap, ctx: egui::Context) -> Self {
+        let (sender, events) = mpsc::channel();
+        let mut midi = Self {
+            map,
+            learning: None,
+            connection: None,
+            ports: Vec::new(),
+            shown: None,
+            sender,
+            events,
+            last: HashMap::new(),
+            error: None,
+            ctx,
+        };
+        if midi.map.port.is_some() {
+            midi.refresh_ports();
+            midi.connect(midi.map.port.clone());
+        }
+        midi
+    }
+
+    fn refresh_ports(&mut self) {
+        self.ports = match MidiInput::new(CLIENT_NAME) {
+            Ok(input) => input
+                .ports()
+                .iter()
+                .filter_map(|port| input.port_name(port).ok())
+                .collect(),
+            Err(e) => {
+                self.error = Some(e.to_string());
+                Vec::new()
+            }
+        };
+    }
+
+    /// Switch to the input port called `name`, or close MIDI with `None`.
+    fn connect(&mut self, name: Option<String>) {
+        self.connection = None;
+        self.error = None;
+        self.map.port = name.clone();
+        let Some(name) = name else {
+            return;
+        };
+        let input = match MidiInput::new(CLIENT_NAME) {
+            Ok(input) => input,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        let port = input
+            .ports()
+            .into_iter()
+            .find(|port| input.port_name(port).is_ok_and(|n| n == name));
+        let Some(port) = port else {
+            self.error = Some(format!("{name} isn't connected"));
+            return;
+        };
+        let sender = self.sender.clone();
+        let ctx = self.ctx.clone();
+        let connected = input.connect(
+            &port,
+            "dreamachine-in",
+            move |_, message, _| {
+                if let Some(event) = Event::parse(message) {
+                    let _ = sender.send(event);
+                    ctx.request_repaint();
+                }
+            },
+            (),
+        );
+        match connected {
+            Ok(connection) => {
+                tracing::info!("Listening to MIDI from {name}");
+                self.connection = Some(connection);
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    /// Commands from the controls moved since the last call. `frequencies`
+    /// is the range a frequency knob sweeps and `modes` how many modes a
+    /// mode knob chooses between.
+    pub fn poll(&mut self, frequencies: RangeInclusive<f32>, modes: usize) -> Vec<Command> {
+        let mut commands = Vec::new();
+        while let Ok(Event { control, value }) = self.events.try_recv() {
+            let previous = self.last.insert(control, value).unwrap_or(0.0);
+            if let Some(action) = self.learning.take() {
+                self.map.bind(action, control);
+                continue;
+            }
+            let pressed = value >= 0.5 && previous < 0.5;
+            let is_note = matches!(control, Control::Note { .. });
+            let command = match self.map.action(control) {
+                Some(MidiAction::Frequency) => {
+                    let (lo, hi) = (*frequencies.start(), *frequencies.end());
+                    Some(Command::SetFrequency(lo + value * (hi - lo)))
+                }
+                Some(MidiAction::Brightness) => Some(Command::SetBrightness(value)),
+                Some(MidiAction::Mode) if is_note => pressed.then_some(Command::NextMode),
+                Some(MidiAction::Mode) => {
+                    let last = modes.saturating_sub(1) as f32;
+                    Some(Command::SelectMode((value * last).round() as usize))
+                }
+                Some(MidiAction::StartStop) => pressed.then_some(Command::StartStop),
+                None => None,
+            };
+            commands.extend(command);
+        }
+        commands
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        let pass = ui.ctx().cumulative_pass_nr();
+        if self.shown.is_none_or(|last| last + 1 < pass) {
+            self.refresh_ports();
+        }
+        self.shown = Some(pass);
+        let selected = self.map.port.clone();
+        let mut port = selected.clone();
+        egui::ComboBox::from_label("Input")
+            .selected_text(port.as_deref().unwrap_or("Off"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut port, None, "Off");
+                for name in &self.ports {
+                    ui.selectable_value(&mut port, Some(name.clone()), name);
+                }
+            });
+        if port != selected {
+            self.connect(port);
+        }
+        if ui.button("Refresh Ports").clicked() {
+            self.refresh_ports();
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::LIGHT_RED, error.as_str());
+        }
+        ui.separator();
+        egui::Grid::new("midi_bindings").show(ui, |ui| {
+            for action in MidiAction::ALL {
+                ui.label(action.name());
+                if self.learning == Some(action) {
+                    ui.label("Move a control…");
+                } else {
+                    ui.label(self.map.control(action).map_or("–".into(), Control::label));
+                }
+                let learning = self.learning == Some(action);
+                if ui.selectable_label(learning, "Learn").clicked() {
+                    self.learning = (!learning).then_some(action);
+                }
+                if ui.small_button("Clear").clicked() {
+                    self.map.unbind(action);
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
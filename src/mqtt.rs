@@ -0,0 +1,333 @@
+//! MQTT, for Home Assistant and other home automation.
+//!
+//! Under the configured base topic:
+//! - `<topic>/state`: the session status as JSON, retained, published on
+//!   every change and every few seconds while flashing
+//! - `<topic>/availability`: `online`, or `offline` once the app is gone
+//! - `<topic>/command`: JSON requests as sent over the WebSocket; the reply
+//!   goes to `<topic>/response`
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use dreamachine_core::remote::{Request, Response, Status};
+use eframe::egui::{self, Color32};
+use rumqttc::{
+    Client, Connection, Event, LastWill, MqttOptions, Outgoing, Packet, QoS, RecvTimeoutError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::remote::{Caller, POLL};
+
+/// Republish the state this often while flashing, so elapsed time moves.
+const STATE_INTERVAL: Duration = Duration::from_secs(5);
+/// Wait between reconnection attempts.
+const RETRY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// Leave empty for brokers without authentication.
+    pub username: String,
+    /// Asked for each session rather than saved with the settings.
+    #[serde(skip)]
+    pub password: String,
+    /// Base of every topic.
+    pub topic: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".into(),
+            port: 1883,
+            username: String::new(),
+            password: String::new(),
+            topic: "dreamachine".into(),
+        }
+    }
+}
+
+impl MqttSettings {
+    /// Whether the broker wants a password that hasn't been given yet.
+    fn needs_password(&self) -> bool {
+        !self.username.is_empty() && self.password.is_empty()
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{name}", self.topic.trim_end_matches('/'))
+    }
+}
+
+pub struct Mqtt {
+    pub settings: MqttSettings,
+    caller: Caller,
+    client: Option<MqttClient>,
+}
+
+impl Mqtt {
+    pub fn new(settings: MqttSettings, caller: Caller) -> Self {
+        let mut mqtt = Self {
+            settings,
+            caller,
+            client: None,
+        };
+        mqtt.reconnect();
+        mqtt
+    }
+
+    fn reconnect(&mut self) {
+        self.client = None;
+        if self.settings.enabled && !self.settings.needs_password() {
+            self.client = Some(MqttClient::connect(&self.settings, self.caller.clone()));
+        }
+    }
+
+    /// Publish `status` if it changed or is due again.
+    pub fn update(&mut self, status: &Status) {
+        if let Some(client) = &mut self.client {
+            client.update(status);
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("mqtt_settings").show(ui, |ui| {
+            ui.label("Broker");
+            ui.text_edit_singleline(&mut self.settings.host);
+            ui.end_row();
+            ui.label("Port");
+            ui.add(egui::DragValue::new(&mut self.settings.port));
+            ui.end_row();
+            ui.label("Username");
+            ui.text_edit_singleline(&mut self.settings.username);
+            ui.end_row();
+            ui.label("Password");
+            ui.add(egui::TextEdit::singleline(&mut self.settings.password).password(true));
+            ui.end_row();
+            ui.label("Topic");
+            ui.text_edit_singleline(&mut self.settings.topic);
+            ui.end_row();
+        });
+        ui.horizontal(|ui| {
+            let label = if self.client.is_some() {
+                "Reconnect"
+            } else {
+                "Connect"
+            };
+            if ui.button(label).clicked() {
+                self.settings.enabled = true;
+                self.reconnect();
+            }
+            if ui
+                .add_enabled(self.client.is_some(), egui::Button::new("Disconnect"))
+                .clicked()
+            {
+                self.settings.enabled = false;
+                self.reconnect();
+            }
+        });
+        if self.settings.enabled && self.settings.needs_password() {
+            ui.label("Enter the password to connect; it isn't saved.");
+        }
+        if let Some(client) = &self.client {
+            let state = client.state.lock().unwrap();
+            match &*state {
+                Ok(text) => ui.label(text.as_str()),
+                Err(error) => ui.colored_label(Color32::LIGHT_RED, error.as_str()),
+            };
+        }
+    }
+}
+
+/// A connection to the broker, kept up by its own thread until dropped.
+struct MqttClient {
+    client: Client,
+    state_topic: String,
+    availability_topic: String,
+    /// What to show in the settings: how the connection is, or what failed.
+    state: Arc<Mutex<Result<String, String>>>,
+    last: Option<(Status, Instant)>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MqttClient {
+    fn connect(settings: &MqttSettings, caller: Caller) -> Self {
+        let client_id = format!("dreamachine-{}", std::process::id());
+        let mut options = MqttOptions::new(client_id, settings.host.trim(), settings.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if !settings.username.is_empty() {
+            options.set_credentials(&settings.username, &settings.password);
+        }
+        let availability_topic = settings.topic("availability");
+        options.set_last_will(LastWill::new(
+            &availability_topic,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        let (client, connection) = Client::new(options, 16);
+        let state = Arc::new(Mutex::new(Ok("Connecting…".to_string())));
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            client: client.clone(),
+            command_topic: settings.topic("command"),
+            response_topic: settings.topic("response"),
+            availability_topic: availability_topic.clone(),
+            caller,
+            state: state.clone(),
+            stop: stop.clone(),
+        };
+        let thread = thread::Builder::new()
+            .name("mqtt".into())
+            .spawn(move || worker.run(connection));
+        let thread = match thread {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                *state.lock().unwrap() = Err(e.to_string());
+                None
+            }
+        };
+        tracing::info!(
+            "Connecting to MQTT broker {}:{}",
+            settings.host,
+            settings.port
+        );
+        Self {
+            client,
+            state_topic: settings.topic("state"),
+            availability_topic,
+            state,
+            last: None,
+            stop,
+            thread,
+        }
+    }
+
+    fn update(&mut self, status: &Status) {
+        // elapsed time alone isn't worth a message every frame
+        let due = match &self.last {
+            Some((last, at)) => {
+                !same_state(last, status) || (status.flashing && at.elapsed() >= STATE_INTERVAL)
+            }
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        let json = Response::status(status.clone()).to_json();
+        match self
+            .client
+            .try_publish(&self.state_topic, QoS::AtLeastOnce, true, json)
+        {
+            Ok(()) => self.last = Some((status.clone(), Instant::now())),
+            // the queue is full while the broker is away; try next frame
+            Err(e) => tracing::trace!("MQTT state not sent: {e}"),
+        }
+    }
+}
+
+impl Drop for MqttClient {
+    fn drop(&mut self) {
+        let _ =
+            self.client
+                .try_publish(&self.availability_topic, QoS::AtLeastOnce, true, "offline");
+        let _ = self.client.try_disconnect();
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Everything but the clocks.
+fn same_state(a: &Status, b: &Status) -> bool {
+    a.flashing == b.flashing
+        && a.counting_down == b.counting_down
+        && a.mode == b.mode
+        && (a.frequency_hz - b.frequency_hz).abs() < 0.05
+        && a.program == b.program
+}
+
+/// Drives the connection and answers commands.
+struct Worker {
+    client: Client,
+    command_topic: String,
+    response_topic: String,
+    availability_topic: String,
+    caller: Caller,
+    state: Arc<Mutex<Result<String, String>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn run(self, mut connection: Connection) {
+        while !self.stop.load(Ordering::Relaxed) {
+            match connection.recv_timeout(POLL) {
+                Ok(Ok(Event::Incoming(Packet::ConnAck(_)))) => self.connected(),
+                Ok(Ok(Event::Incoming(Packet::Publish(publish)))) => {
+                    if publish.topic == self.command_topic {
+                        self.command(&publish.payload);
+                    }
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => {
+                    tracing::warn!("MQTT: {e}");
+                    *self.state.lock().unwrap() = Err(e.to_string());
+                    // polling again reconnects
+                    let retry = Instant::now() + RETRY;
+                    while Instant::now() < retry && !self.stop.load(Ordering::Relaxed) {
+                        thread::sleep(POLL);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        // send the offline message and disconnect queued on drop
+        while let Ok(Ok(event)) = connection.recv_timeout(POLL) {
+            if let Event::Outgoing(Outgoing::Disconnect) = event {
+                break;
+            }
+        }
+    }
+
+    fn connected(&self) {
+        tracing::info!("Connected to the MQTT broker");
+        *self.state.lock().unwrap() = Ok("Connected".into());
+        let subscribed = self
+            .client
+            .try_subscribe(&self.command_topic, QoS::AtLeastOnce);
+        let announced =
+            self.client
+                .try_publish(&self.availability_topic, QoS::AtLeastOnce, true, "online");
+        if let Err(e) = subscribed.and(announced) {
+            tracing::warn!("MQTT: {e}");
+        }
+    }
+
+    fn command(&self, payload: &[u8]) {
+        let response = match std::str::from_utf8(payload)
+            .map_err(|e| e.to_string())
+            .and_then(Request::parse)
+        {
+            Ok(request) => self.caller.call(request),
+            Err(e) => Response::error(e),
+        };
+        let sent = self.client.try_publish(
+            &self.response_topic,
+            QoS::AtLeastOnce,
+            false,
+            response.to_json(),
+        );
+        if let Err(e) = sent {
+            tracing::warn!("MQTT response not sent: {e}");
+        }
+    }
+}
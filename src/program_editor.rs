@@ -0,0 +1,107 @@
+//! Window for building a multi-stage program by hand.
+
+use dreamachine_core::flicker::{MAX_FREQUENCY_HZ, MIN_FREQUENCY_HZ};
+use dreamachine_core::{Program, Stage, Transition};
+use eframe::egui;
+
+/// Show the editor. Returns true when the user asks to run the program.
+pub fn show(ctx: &egui::Context, open: &mut bool, program: &mut Program, modes: &[&str]) -> bool {
+    let mut run = false;
+    egui::Window::new("Program")
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut program.name);
+            });
+
+            let mut remove = None;
+            egui::Grid::new("program_stages")
+                .num_columns(6)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Mode");
+                    ui.label("Start");
+                    ui.label("End");
+                    ui.label("Length");
+                    ui.label("Transition");
+                    ui.end_row();
+
+                    for (i, stage) in program.stages.iter_mut().enumerate() {
+                        egui::ComboBox::from_id_salt(("stage_mode", i))
+                            .selected_text(stage.mode.as_str())
+                            .show_ui(ui, |ui| {
+                                for &name in modes {
+                                    ui.selectable_value(&mut stage.mode, name.to_owned(), name);
+                                }
+                            });
+                        ui.add(hz_value(&mut stage.start_hz));
+                        ui.add(hz_value(&mut stage.end_hz));
+                        let mut mins = stage.duration_secs / 60.0;
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut mins)
+                                    .range(0.1..=240.0)
+                                    .speed(0.1)
+                                    .suffix(" min"),
+                            )
+                            .changed()
+                        {
+                            stage.duration_secs = mins * 60.0;
+                        }
+                        egui::ComboBox::from_id_salt(("stage_transition", i))
+                            .selected_text(stage.transition.name())
+                            .show_ui(ui, |ui| {
+                                for t in Transition::ALL {
+                                    ui.selectable_value(&mut stage.transition, t, t.name());
+                                }
+                            });
+                        if ui.button("Remove").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(i) = remove {
+                program.stages.remove(i);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Add Stage").clicked() {
+                    let stage = program.stages.last().cloned().map_or_else(
+                        || Stage {
+                            mode: modes[0].to_owned(),
+                            start_hz: 10.0,
+                            end_hz: 10.0,
+                            duration_secs: 300.0,
+                            transition: Transition::Hold,
+                        },
+                        |last| Stage {
+                            start_hz: last.end_hz,
+                            transition: Transition::Hold,
+                            ..last
+                        },
+                    );
+                    program.stages.push(stage);
+                }
+                let total = program.total_duration().as_secs_f32() / 60.0;
+                ui.label(format!("Total: {total:.1} min"));
+                if ui
+                    .add_enabled(!program.stages.is_empty(), egui::Button::new("Run"))
+                    .clicked()
+                {
+                    run = true;
+                }
+            });
+        });
+    run
+}
+
+fn hz_value(hz: &mut f32) -> egui::DragValue<'_> {
+    egui::DragValue::new(hz)
+        .range(MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ)
+        .speed(0.1)
+        .fixed_decimals(1)
+        .suffix(" Hz")
+}
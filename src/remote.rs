@@ -0,0 +1,584 @@
+//! Remote control over a WebSocket, for a phone or companion app on the
+//! same network, and over HTTP for scripts. Requests are handled by the app
+//! between frames; see `dreamachine_core::remote` for the protocol.
+//!
+//! The servers only listen on this computer unless the network is allowed.
+//! Other devices then have to present the pairing token, as
+//! `Authorization: Bearer <token>` or a `?token=<token>` query. Web pages
+//! are only let in when this computer served them, so a site open in a
+//! browser can't drive the session.
+
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use dreamachine_core::remote::{Request, Response};
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+use tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request as Handshake, Response as Accepted,
+};
+use tungstenite::http::StatusCode;
+use tungstenite::{Message, WebSocket};
+
+use crate::http;
+use crate::mdns::{Advertisement, Mdns};
+
+/// How often idle server threads look at the stop flag.
+pub const POLL: Duration = Duration::from_millis(100);
+/// Longest wait for the app to answer; it only answers while it is drawing.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSettings {
+    pub websocket: bool,
+    pub websocket_port: u16,
+    pub http: bool,
+    pub http_port: u16,
+    /// Listen on every interface rather than only this computer.
+    pub allow_lan: bool,
+    /// What other devices pair with; made up when empty.
+    pub token: String,
+    /// Announce the running servers over mDNS, when other devices are
+    /// allowed.
+    pub advertise: bool,
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            websocket: false,
+            websocket_port: 8765,
+            http: false,
+            http_port: 8766,
+            allow_lan: false,
+            token: String::new(),
+            advertise: false,
+        }
+    }
+}
+
+impl RemoteSettings {
+    /// Whether to announce the servers: only once other devices may use them.
+    fn advertised(&self) -> bool {
+        self.advertise && self.allow_lan
+    }
+}
+
+/// A request waiting for the app, and where the answer goes.
+pub struct Call {
+    pub request: Request,
+    pub reply: Sender<Response>,
+}
+
+/// Hands requests from server threads to the app.
+#[derive(Clone)]
+pub struct Caller {
+    calls: Sender<Call>,
+    ctx: egui::Context,
+}
+
+impl Caller {
+    pub fn new(ctx: egui::Context) -> (Self, Receiver<Call>) {
+        let (calls, receiver) = mpsc::channel();
+        (Self { calls, ctx }, receiver)
+    }
+
+    /// Send `request` to the app and wait for its answer.
+    pub fn call(&self, request: Request) -> Response {
+        let (reply, answer) = mpsc::channel();
+        if self.calls.send(Call { request, reply }).is_err() {
+            return Response::error("the app is closing");
+        }
+        self.ctx.request_repaint();
+        answer
+            .recv_timeout(REPLY_TIMEOUT)
+            .unwrap_or_else(|_| Response::error("the app didn't answer"))
+    }
+}
+
+/// Who may use the servers: anyone on this computer, and other devices
+/// with the pairing token. Web pages only when this computer served them.
+#[derive(Clone)]
+pub struct Access {
+    token: Arc<str>,
+}
+
+impl Access {
+    pub fn new(token: &str) -> Self {
+        Self {
+            token: token.into(),
+        }
+    }
+
+    /// Why a request from `peer` is turned away, if it is. `origin` is the
+    /// Origin header browsers send, `token` the one the client presented.
+    pub fn refusal(
+        &self,
+        peer: IpAddr,
+        origin: Option<&str>,
+        token: Option<&str>,
+    ) -> Option<&'static str> {
+        if origin.is_some_and(|origin| !is_local_origin(origin)) {
+            return Some("web pages from other sites can't use the remote control");
+        }
+        if !peer.is_loopback() && !self.paired(token) {
+            return Some("other devices need the pairing token");
+        }
+        None
+    }
+
+    /// Whether `token` is the pairing token.
+    pub fn paired(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|token| self.matches(token))
+    }
+
+    /// Compare in constant time, so the token can't be guessed byte by byte.
+    fn matches(&self, token: &str) -> bool {
+        token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+/// The token from an `Authorization: Bearer` header or a `token` query
+/// parameter in `url`.
+pub fn presented_token<'a>(authorization: Option<&'a str>, url: &'a str) -> Option<&'a str> {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            let (_, query) = url.split_once('?')?;
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+        })
+}
+
+/// Whether `origin` is a page served by this computer.
+fn is_local_origin(origin: &str) -> bool {
+    let Some((_, host)) = origin.split_once("://") else {
+        // includes `null`, from sandboxed frames and files
+        return false;
+    };
+    let host = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// A fresh pairing token: 128 random bits in hex.
+fn new_token() -> String {
+    let mut bytes = [0; 16];
+    openssl::rand::rand_bytes(&mut bytes).expect("the system random source failed");
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Where the servers listen: only this computer unless `allow_lan`.
+pub fn bind_address(allow_lan: bool) -> IpAddr {
+    if allow_lan {
+        Ipv4Addr::UNSPECIFIED.into()
+    } else {
+        Ipv4Addr::LOCALHOST.into()
+    }
+}
+
+/// The remote control servers, started and stopped to match the settings.
+pub struct Remote {
+    pub settings: RemoteSettings,
+    caller: Caller,
+    calls: Receiver<Call>,
+    websocket: Listener,
+    http: Listener,
+    // after the listeners, which withdraw their advertisements on drop
+    mdns: Mdns,
+}
+
+/// One of the servers and why it isn't running, if it should be.
+#[derive(Default)]
+struct Listener {
+    server: Option<Server>,
+    advertisement: Option<Advertisement>,
+    error: Option<String>,
+}
+
+impl Listener {
+    fn restart(&mut self, enabled: bool, port: u16, start: impl FnOnce() -> io::Result<Server>) {
+        // closes the old port before binding the new one
+        self.advertisement = None;
+        self.server = None;
+        self.error = None;
+        if !enabled {
+            return;
+        }
+        match start() {
+            Ok(server) => self.server = Some(server),
+            Err(e) => {
+                tracing::error!("Couldn't listen on port {port}: {e}");
+                self.error = Some(format!("Couldn't listen on port {port}: {e}"));
+            }
+        }
+    }
+
+    /// Announce the server, or withdraw it with `None`.
+    fn advertise(&mut self, mdns: Option<&mut Mdns>, scheme: &str, label: &str) {
+        self.advertisement = None;
+        let (Some(mdns), Some(server)) = (mdns, &self.server) else {
+            return;
+        };
+        match mdns.advertise(scheme, label, server.addr.port()) {
+            Ok(advertisement) => self.advertisement = Some(advertisement),
+            Err(e) => {
+                tracing::warn!("Couldn't advertise the {label} server: {e}");
+                self.error = Some(format!("Couldn't advertise over mDNS: {e}"));
+            }
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, scheme: &str) {
+        if let Some(server) = &self.server {
+            ui.label(format!("Listening on {scheme}://{}", server.addr));
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::LIGHT_RED, error.as_str());
+        }
+    }
+}
+
+impl Remote {
+    /// `ctx` is woken whenever a request arrives.
+    pub fn new(mut settings: RemoteSettings, ctx: egui::Context) -> Self {
+        if settings.token.is_empty() {
+            settings.token = new_token();
+        }
+        let (caller, calls) = Caller::new(ctx);
+        let mut remote = Self {
+            settings,
+            caller,
+            calls,
+            websocket: Listener::default(),
+            http: Listener::default(),
+            mdns: Mdns::default(),
+        };
+        remote.restart_websocket();
+        remote.restart_http();
+        remote
+    }
+
+    fn restart_websocket(&mut self) {
+        let RemoteSettings {
+            websocket,
+            websocket_port,
+            allow_lan,
+            ..
+        } = self.settings;
+        let addr = SocketAddr::new(bind_address(allow_lan), websocket_port);
+        let caller = self.caller.clone();
+        let access = Access::new(&self.settings.token);
+        self.websocket.restart(websocket, websocket_port, || {
+            websocket_server(addr, caller, access)
+        });
+        let mdns = self.settings.advertised().then_some(&mut self.mdns);
+        self.websocket.advertise(mdns, "ws", "WebSocket");
+    }
+
+    fn restart_http(&mut self) {
+        let RemoteSettings {
+            http,
+            http_port,
+            allow_lan,
+            ..
+        } = self.settings;
+        let addr = SocketAddr::new(bind_address(allow_lan), http_port);
+        let caller = self.caller.clone();
+        let access = Access::new(&self.settings.token);
+        self.http
+            .restart(http, http_port, || http::start(addr, caller, access));
+        let mdns = self.settings.advertised().then_some(&mut self.mdns);
+        self.http.advertise(mdns, "http", "HTTP");
+    }
+
+    /// For other ways in, such as MQTT.
+    pub fn caller(&self) -> Caller {
+        self.caller.clone()
+    }
+
+    /// Requests that arrived since the last call.
+    pub fn calls(&self) -> impl Iterator<Item = Call> + '_ {
+        self.calls.try_iter()
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut changed = ui
+            .checkbox(&mut self.settings.websocket, "WebSocket Server")
+            .changed();
+        changed |= port(ui, &mut self.settings.websocket_port);
+        if changed {
+            self.restart_websocket();
+        }
+        self.websocket.ui(ui, "ws");
+        ui.separator();
+        let mut changed = ui
+            .checkbox(&mut self.settings.http, "HTTP Server")
+            .changed();
+        changed |= port(ui, &mut self.settings.http_port);
+        if changed {
+            self.restart_http();
+        }
+        self.http.ui(ui, "http");
+        ui.separator();
+        if ui
+            .checkbox(&mut self.settings.allow_lan, "Allow Other Devices")
+            .on_hover_text("Listen on the network, not only on this computer")
+            .changed()
+        {
+            self.restart_websocket();
+            self.restart_http();
+        }
+        if self.settings.allow_lan {
+            ui.label("Other devices pair with this token:");
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(&self.settings.token).monospace());
+                if ui
+                    .small_button("New")
+                    .on_hover_text("Unpair every device")
+                    .clicked()
+                {
+                    self.settings.token = new_token();
+                    self.restart_websocket();
+                    self.restart_http();
+                }
+            });
+        }
+        ui.separator();
+        let advertise =
+            egui::Checkbox::new(&mut self.settings.advertise, "Advertise on the Network");
+        if ui
+            .add_enabled(self.settings.allow_lan, advertise)
+            .on_hover_text("Let companion apps find the servers over mDNS")
+            .on_disabled_hover_text("Only other devices need finding the servers")
+            .changed()
+        {
+            let advertise = self.settings.advertised();
+            self.websocket
+                .advertise(advertise.then_some(&mut self.mdns), "ws", "WebSocket");
+            self.http
+                .advertise(advertise.then_some(&mut self.mdns), "http", "HTTP");
+        }
+    }
+}
+
+fn port(ui: &mut egui::Ui, port: &mut u16) -> bool {
+    ui.horizontal(|ui| {
+        ui.label("Port");
+        ui.add(egui::DragValue::new(port).range(1024..=65535))
+            .changed()
+    })
+    .inner
+}
+
+/// A listening server. Dropping it closes the port and every connection.
+pub struct Server {
+    pub addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Run `serve` on its own thread until the server is dropped. `serve`
+    /// must return soon after its flag is set.
+    pub fn spawn(
+        name: &str,
+        addr: SocketAddr,
+        serve: impl FnOnce(Arc<AtomicBool>) + Send + 'static,
+    ) -> io::Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::Builder::new()
+            .name(name.into())
+            .spawn(move || serve(stopped))?;
+        Ok(Self {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // the listener closes when the thread ends
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Listen for WebSocket clients on `addr`.
+fn websocket_server(addr: SocketAddr, caller: Caller, access: Access) -> io::Result<Server> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+    let server = Server::spawn("websocket", addr, move |stop| {
+        accept(listener, caller, access, stop)
+    })?;
+    tracing::info!("WebSocket control listening on {addr}");
+    Ok(server)
+}
+
+fn accept(listener: TcpListener, caller: Caller, access: Access, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let (caller, access, stop) = (caller.clone(), access.clone(), stop.clone());
+                let spawned = thread::Builder::new()
+                    .name(format!("websocket {peer}"))
+                    .spawn(move || serve(stream, peer, caller, &access, stop));
+                if let Err(e) = spawned {
+                    tracing::warn!("Couldn't serve {peer}: {e}");
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL),
+            Err(e) => {
+                tracing::warn!("WebSocket accept failed: {e}");
+                thread::sleep(POLL);
+            }
+        }
+    }
+}
+
+/// Checks the handshake against the `Access` before a client is let in.
+struct Admit<'a> {
+    peer: SocketAddr,
+    access: &'a Access,
+}
+
+impl Callback for Admit<'_> {
+    fn on_request(
+        self,
+        request: &Handshake,
+        accepted: Accepted,
+    ) -> Result<Accepted, ErrorResponse> {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let url = request.uri().to_string();
+        let token = presented_token(header("authorization"), &url);
+        let Some(reason) = self.access.refusal(self.peer.ip(), header("origin"), token) else {
+            return Ok(accepted);
+        };
+        tracing::warn!("Refused WebSocket client {}: {reason}", self.peer);
+        let mut refused = ErrorResponse::new(Some(reason.to_string()));
+        *refused.status_mut() = StatusCode::FORBIDDEN;
+        Err(refused)
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    peer: SocketAddr,
+    caller: Caller,
+    access: &Access,
+    stop: Arc<AtomicBool>,
+) {
+    // the handshake blocks; the timeout afterwards lets the loop see `stop`
+    if let Err(e) = stream.set_nonblocking(false) {
+        tracing::warn!("WebSocket {peer}: {e}");
+        return;
+    }
+    let mut socket = match tungstenite::accept_hdr(stream, Admit { peer, access }) {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::warn!("WebSocket handshake with {peer} failed: {e}");
+            return;
+        }
+    };
+    if let Err(e) = socket.get_ref().set_read_timeout(Some(POLL)) {
+        tracing::warn!("WebSocket {peer}: {e}");
+        return;
+    }
+    tracing::info!("WebSocket client connected: {peer}");
+    while !stop.load(Ordering::Relaxed) {
+        let message = match socket.read() {
+            Ok(message) => message,
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                continue;
+            }
+            Err(_) => break,
+        };
+        let response = match message {
+            Message::Text(text) => match Request::parse(text.as_str()) {
+                Ok(request) => caller.call(request),
+                Err(e) => Response::error(e),
+            },
+            Message::Close(_) => break,
+            // pings are answered by tungstenite
+            _ => continue,
+        };
+        if !reply(&mut socket, &response) {
+            break;
+        }
+    }
+    let _ = socket.close(None);
+    tracing::info!("WebSocket client disconnected: {peer}");
+}
+
+/// Send `response`; false once the connection is gone.
+fn reply(socket: &mut WebSocket<TcpStream>, response: &Response) -> bool {
+    socket.send(Message::text(response.to_json())).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn other_sites_and_unpaired_devices_are_refused() {
+        let access = Access::new("secret");
+        let local = IpAddr::from(Ipv4Addr::LOCALHOST);
+        let other = IpAddr::from([192, 168, 1, 20]);
+        assert_eq!(access.refusal(local, None, None), None);
+        assert_eq!(
+            access.refusal(local, Some("http://localhost:8766"), None),
+            None
+        );
+        assert_eq!(access.refusal(local, Some("http://[::1]:3000"), None), None);
+        assert!(
+            access
+                .refusal(local, Some("https://example.com"), None)
+                .is_some()
+        );
+        assert!(
+            access
+                .refusal(local, Some("http://localhost.example.com"), None)
+                .is_some()
+        );
+        assert!(access.refusal(local, Some("null"), None).is_some());
+        assert!(access.refusal(other, None, None).is_some());
+        assert!(access.refusal(other, None, Some("guess")).is_some());
+        assert_eq!(access.refusal(other, None, Some("secret")), None);
+    }
+
+    #[test]
+    fn reads_the_token_from_the_header_or_the_query() {
+        assert_eq!(presented_token(Some("Bearer abc"), "/"), Some("abc"));
+        assert_eq!(presented_token(None, "/?mode=x&token=abc"), Some("abc"));
+        assert_eq!(presented_token(Some("Basic abc"), "/"), None);
+        assert_eq!(new_token().len(), 32);
+    }
+}
@@ -0,0 +1,147 @@
+//! LED goggles on a serial port; the wire format is in
+//! [`dreamachine_core::lights::serial`].
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use dreamachine_core::lights::Sample;
+use dreamachine_core::lights::serial::Format;
+use eframe::egui::{self, Color32};
+use serde::{Deserialize, Serialize};
+use serialport::SerialPort;
+
+use crate::lights::{Device, Sink};
+
+const BAUD_RATES: [u32; 6] = [9600, 19200, 38400, 57600, 115200, 230400];
+/// Bits on the wire for each byte: start, eight data and stop.
+const BITS_PER_BYTE: f32 = 10.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialSettings {
+    pub enabled: bool,
+    /// Such as `/dev/ttyUSB0` or `COM3`.
+    pub port: String,
+    pub baud: u32,
+    pub format: Format,
+    /// Frames per second; the flicker is only as sharp as this allows.
+    pub rate: f32,
+}
+
+impl Default for SerialSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: String::new(),
+            baud: 115200,
+            format: Format::default(),
+            rate: 200.0,
+        }
+    }
+}
+
+impl SerialSettings {
+    /// Whether the baud rate can carry frames this often.
+    fn fits(&self) -> bool {
+        self.format.frame_len() as f32 * BITS_PER_BYTE * self.rate <= self.baud as f32
+    }
+}
+
+impl Device for SerialSettings {
+    type Sink = Serial;
+
+    const NAME: &'static str = "Serial";
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("serial_settings").show(ui, |ui| {
+            ui.label("Port");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.port);
+                egui::ComboBox::from_id_salt("serial_ports")
+                    .selected_text("Detected")
+                    .show_ui(ui, |ui| match serialport::available_ports() {
+                        Ok(ports) if ports.is_empty() => {
+                            ui.label("No serial ports");
+                        }
+                        Ok(ports) => {
+                            for port in ports {
+                                ui.selectable_value(
+                                    &mut self.port,
+                                    port.port_name.clone(),
+                                    port.port_name,
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(Color32::LIGHT_RED, e.to_string());
+                        }
+                    });
+            });
+            ui.end_row();
+            ui.label("Baud");
+            egui::ComboBox::from_id_salt("serial_baud")
+                .selected_text(self.baud.to_string())
+                .show_ui(ui, |ui| {
+                    for baud in BAUD_RATES {
+                        ui.selectable_value(&mut self.baud, baud, baud.to_string());
+                    }
+                });
+            ui.end_row();
+            ui.label("Frames");
+            egui::ComboBox::from_id_salt("serial_format")
+                .selected_text(self.format.name())
+                .show_ui(ui, |ui| {
+                    for format in Format::ALL {
+                        ui.selectable_value(&mut self.format, format, format.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("Rate");
+            ui.add(
+                egui::DragValue::new(&mut self.rate)
+                    .range(1.0..=1000.0)
+                    .suffix(" fps"),
+            );
+            ui.end_row();
+        });
+        if !self.fits() {
+            ui.colored_label(
+                Color32::LIGHT_RED,
+                "Too many frames for this baud rate; frames will lag",
+            );
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.rate.clamp(1.0, 1000.0))
+    }
+
+    fn connect(&self) -> io::Result<Serial> {
+        let port = self.port.trim();
+        if port.is_empty() {
+            return Err(io::Error::other("no port"));
+        }
+        let port = serialport::new(port, self.baud)
+            .timeout(Duration::from_millis(100))
+            .open()?;
+        Ok(Serial {
+            port,
+            format: self.format,
+        })
+    }
+}
+
+pub struct Serial {
+    port: Box<dyn SerialPort>,
+    format: Format,
+}
+
+impl Sink for Serial {
+    fn show(&mut self, sample: &Sample) -> io::Result<()> {
+        self.port.write_all(&self.format.frame(sample))
+    }
+}
@@ -0,0 +1,148 @@
+//! User settings saved between launches.
+
+use dreamachine_core::audio::AudioSettings;
+use dreamachine_core::midi::MidiMap;
+use dreamachine_core::{Envelope, Grading, Waveform};
+use eframe::egui::{Color32, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::lights::LightSettings;
+use crate::mqtt::MqttSettings;
+use crate::remote::RemoteSettings;
+
+pub const SETTINGS_KEY: &str = "settings";
+pub const USER_PRESETS_KEY: &str = "user_presets";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Mode name, as returned by `VisualMode::name`.
+    pub mode: String,
+    pub frequency_hz: f32,
+    pub waveform: Waveform,
+    pub envelope: Envelope,
+    pub on_color: Color32,
+    pub off_color: Color32,
+    pub beam_width_norm: f32,
+    /// Seconds to crossfade between modes; 0 cuts straight over.
+    pub crossfade_secs: f32,
+    /// Seconds of black with a countdown between Start and the first flash.
+    pub countdown_secs: f32,
+    /// Seconds to ramp up from black once flashing starts.
+    pub soft_start_secs: f32,
+    /// Longest continuous stimulation before a forced rest; `None` for no
+    /// limit.
+    pub stimulation_limit_mins: Option<f32>,
+    pub rest_mins: f32,
+    /// Stop a session left running this long past its planned length with
+    /// no input. `None` never stops.
+    pub idle_grace_mins: Option<f32>,
+    pub frequency_guard: FrequencyGuard,
+    /// Keep to frequencies that are a whole number of frames per cycle.
+    pub snap_to_refresh: bool,
+    pub risk_policy: RiskPolicy,
+    /// Ask for notes and ratings after each session.
+    pub journal_prompt: bool,
+    pub grading: Grading,
+    pub fullscreen: bool,
+    pub borderless: bool,
+    pub always_on_top: bool,
+    pub kiosk: bool,
+    /// Name of the monitor to show the stimulus on.
+    pub display: Option<String>,
+    /// Outer position and inner size of the window when last windowed.
+    pub window: Option<Rect>,
+    pub separate_controls: bool,
+    pub audio: AudioSettings,
+    pub midi: MidiMap,
+    pub remote: RemoteSettings,
+    pub mqtt: MqttSettings,
+    pub lights: LightSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            mode: "Sweep".into(),
+            frequency_hz: 10.0,
+            waveform: Waveform::default(),
+            envelope: Envelope::default(),
+            on_color: Color32::WHITE,
+            off_color: Color32::BLACK,
+            beam_width_norm: 0.4,
+            crossfade_secs: 2.0,
+            countdown_secs: 10.0,
+            soft_start_secs: 30.0,
+            stimulation_limit_mins: Some(30.0),
+            rest_mins: 10.0,
+            idle_grace_mins: Some(10.0),
+            frequency_guard: FrequencyGuard::default(),
+            snap_to_refresh: false,
+            risk_policy: RiskPolicy::default(),
+            journal_prompt: true,
+            grading: Grading::default(),
+            fullscreen: false,
+            borderless: false,
+            always_on_top: false,
+            kiosk: false,
+            display: None,
+            window: None,
+            separate_controls: false,
+            audio: AudioSettings::default(),
+            midi: MidiMap::default(),
+            remote: RemoteSettings::default(),
+            mqtt: MqttSettings::default(),
+            lights: LightSettings::default(),
+        }
+    }
+}
+
+/// The frequency range the app keeps to unless unlocked, applied inside the
+/// limits from the config file.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrequencyGuard {
+    pub min_hz: f32,
+    pub max_hz: f32,
+    /// Allow everything the config file does.
+    pub unlocked: bool,
+}
+
+impl Default for FrequencyGuard {
+    fn default() -> Self {
+        Self {
+            min_hz: 1.0,
+            max_hz: 30.0,
+            unlocked: false,
+        }
+    }
+}
+
+/// What to do when starting a stimulus that the flash-risk analysis says
+/// exceeds the broadcast guidelines.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum RiskPolicy {
+    Off,
+    #[default]
+    Warn,
+    Block,
+}
+
+impl RiskPolicy {
+    pub const ALL: [RiskPolicy; 3] = [RiskPolicy::Off, RiskPolicy::Warn, RiskPolicy::Block];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RiskPolicy::Off => "Don't check",
+            RiskPolicy::Warn => "Warn",
+            RiskPolicy::Block => "Refuse to start",
+        }
+    }
+}
+
+/// A named snapshot of the settings. The first nine are bound to keys 1–9.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserPreset {
+    pub name: String,
+    pub settings: Settings,
+}
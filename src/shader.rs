@@ -0,0 +1,402 @@
+//! Modes drawn by WGSL fragment shaders on the GPU, through an egui paint
+//! callback.
+//!
+//! Only available with the wgpu renderer. The callbacks bypass egui's shape
+//! post-processing, so the prelude grades the picture itself from the
+//! uniforms, and additive layer blending doesn't apply. The painter opacity
+//! does, which keeps crossfades working, and the session fade is painted
+//! over them like over any other mode.
+//!
+//! Besides the bundled shaders, every `.wgsl` file in the shaders folder
+//! next to the config file becomes a mode named after the file. It defines
+//! `fn mode(uv: vec2<f32>) -> vec3<f32>`, returning the colour at `uv`
+//! (`0..1` across the screen, y down), and can read the uniforms `u.time`,
+//! `u.frequency`, `u.phase`, `u.level`, `u.show_white`, `u.on_color`,
+//! `u.off_color` and `u.resolution` along with the helpers in
+//! `shaders/prelude.wgsl`. Saving the file reloads it; one that fails to
+//! compile leaves the last working version running.
+
+use std::collections::HashMap;
+use std::fs;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use dreamachine_core::{ModeParams, VisualMode};
+use eframe::egui::{self, Color32, Painter, Rect, Ui};
+use eframe::egui_wgpu::{self, CallbackResources, CallbackTrait, RenderState, wgpu};
+
+use crate::config;
+
+const PRELUDE: &str = include_str!("shaders/prelude.wgsl");
+
+/// Shaders bundled with the app: mode name and WGSL source.
+const BUILTIN: [(&str, &str); 3] = [
+    (
+        "Gradient Sweep (GPU)",
+        include_str!("shaders/gradient.wgsl"),
+    ),
+    ("Plasma (GPU)", include_str!("shaders/plasma.wgsl")),
+    ("Spiral (GPU)", include_str!("shaders/spiral.wgsl")),
+];
+
+/// Floats in the `Uniforms` struct of the prelude.
+const UNIFORM_FLOATS: usize = 24;
+const UNIFORM_SIZE: u64 = (UNIFORM_FLOATS * 4) as u64;
+/// Uniform blocks live at this stride in one buffer, the minimum dynamic
+/// offset alignment wgpu guarantees.
+const SLOT_STRIDE: u64 = 256;
+/// How many shader callbacks one frame can hold: crossfades, layers and
+/// extra windows each take one.
+const SLOTS: u64 = 16;
+/// How often custom shader files are checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Set up the GPU resources shared by every shader mode.
+pub fn init(render_state: &RenderState) {
+    let device = &render_state.device;
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("shader_mode"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: NonZeroU64::new(UNIFORM_SIZE),
+            },
+            count: None,
+        }],
+    });
+    let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("shader_mode"),
+        size: SLOT_STRIDE * SLOTS,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("shader_mode"),
+        layout: &bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &uniforms,
+                offset: 0,
+                size: NonZeroU64::new(UNIFORM_SIZE),
+            }),
+        }],
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("shader_mode"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    render_state
+        .renderer
+        .write()
+        .callback_resources
+        .insert(Resources {
+            format: render_state.target_format,
+            layout,
+            uniforms,
+            bind_group,
+            pipelines: HashMap::new(),
+            next_slot: 0,
+        });
+}
+
+/// The bundled shader modes, to append to the mode registry after [`init`].
+pub fn modes() -> Vec<Box<dyn VisualMode>> {
+    BUILTIN
+        .iter()
+        .map(|&(name, source)| {
+            Box::new(ShaderMode {
+                name,
+                source: source.into(),
+                version: 0,
+                file: None,
+                error: None,
+            }) as Box<dyn VisualMode>
+        })
+        .collect()
+}
+
+/// A mode for every `.wgsl` file in the shaders folder, by file name.
+pub fn user_modes() -> Vec<Box<dyn VisualMode>> {
+    let Some(dir) = config::shaders_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wgsl"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().into_owned();
+            let mut mode = ShaderMode {
+                // modes are created once per launch, so this leaks a name per file
+                name: Box::leak(stem.into_boxed_str()),
+                source: "".into(),
+                version: 0,
+                file: Some(ShaderFile {
+                    path,
+                    modified: None,
+                    checked: Instant::now(),
+                }),
+                error: None,
+            };
+            mode.reload();
+            Some(Box::new(mode) as Box<dyn VisualMode>)
+        })
+        .collect()
+}
+
+/// Check `source` the way wgpu would, which would otherwise panic on an
+/// invalid shader.
+fn validate(source: &str) -> Result<(), String> {
+    let full = format!("{PRELUDE}\n{source}");
+    let module = naga::front::wgsl::parse_str(&full).map_err(|e| e.emit_to_string(&full))?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|e| e.emit_to_string(&full))?;
+    Ok(())
+}
+
+struct Resources {
+    format: wgpu::TextureFormat,
+    layout: wgpu::PipelineLayout,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // compiled on first use, by mode name, with the source version
+    pipelines: HashMap<&'static str, (u64, wgpu::RenderPipeline)>,
+    next_slot: u64,
+}
+
+impl Resources {
+    fn pipeline(&mut self, device: &wgpu::Device, name: &'static str, source: &str, version: u64) {
+        if self
+            .pipelines
+            .get(name)
+            .is_some_and(|(built, _)| *built == version)
+        {
+            return;
+        }
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(name),
+            source: wgpu::ShaderSource::Wgsl(format!("{PRELUDE}\n{source}").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(name),
+            layout: Some(&self.layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: self.format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        self.pipelines.insert(name, (version, pipeline));
+    }
+}
+
+pub struct ShaderMode {
+    name: &'static str,
+    source: Arc<str>,
+    // bumped on every reload, so the pipeline gets rebuilt
+    version: u64,
+    // custom shaders only
+    file: Option<ShaderFile>,
+    error: Option<String>,
+}
+
+struct ShaderFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checked: Instant,
+}
+
+impl ShaderMode {
+    /// Pick up changes to the shader file, keeping the last working source
+    /// if the new one doesn't compile.
+    fn reload(&mut self) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        file.checked = Instant::now();
+        let modified = fs::metadata(&file.path).and_then(|m| m.modified()).ok();
+        if modified == file.modified {
+            return;
+        }
+        file.modified = modified;
+        match read(&file.path) {
+            Ok(source) => {
+                tracing::info!("Loaded shader {}", file.path.display());
+                self.source = source.into();
+                self.version += 1;
+                self.error = None;
+            }
+            Err(err) => {
+                tracing::warn!("Shader {}: {}", file.path.display(), err);
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    validate(&source)?;
+    Ok(source)
+}
+
+impl VisualMode for ShaderMode {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn paint(&mut self, painter: &Painter, rect: Rect, t: f32, params: &ModeParams) {
+        if self
+            .file
+            .as_ref()
+            .is_some_and(|f| f.checked.elapsed() >= RELOAD_INTERVAL)
+        {
+            self.reload();
+        }
+        if self.source.is_empty() {
+            // never compiled
+            painter.rect_filled(rect, 0.0, params.off_color);
+            return;
+        }
+        let rgba = |c: Color32| c.to_array().map(|v| v as f32 / 255.0);
+        let [on, off] = [rgba(params.on_color), rgba(params.off_color)];
+        let grading = &params.grading;
+        let tint = grading.tint();
+        let uniforms = [
+            on[0],
+            on[1],
+            on[2],
+            on[3],
+            off[0],
+            off[1],
+            off[2],
+            off[3],
+            rect.width(),
+            rect.height(),
+            t,
+            params.frequency_hz,
+            params.phase,
+            params.level,
+            params.beam_width_norm,
+            params.sweep_speed,
+            painter.opacity(),
+            if params.show_white { 1.0 } else { 0.0 },
+            grading.brightness,
+            grading.contrast,
+            tint[0],
+            tint[1],
+            tint[2],
+            grading.gamma,
+        ];
+        painter.add(egui_wgpu::Callback::new_paint_callback(
+            rect,
+            Callback {
+                name: self.name,
+                source: self.source.clone(),
+                version: self.version,
+                uniforms,
+                slot: AtomicU64::new(0),
+            },
+        ));
+    }
+
+    fn renders_offline(&self) -> bool {
+        false
+    }
+
+    fn settings_ui(&mut self, ui: &mut Ui) {
+        let Some(file) = &self.file else {
+            ui.label("This mode has no settings.");
+            return;
+        };
+        ui.label(file.path.display().to_string());
+        ui.label("Saving the file reloads it.");
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::LIGHT_RED, "Failed to compile:");
+            ui.label(egui::RichText::new(error).monospace().small());
+        }
+    }
+}
+
+struct Callback {
+    name: &'static str,
+    source: Arc<str>,
+    version: u64,
+    uniforms: [f32; UNIFORM_FLOATS],
+    // where `prepare` put the uniforms, for `paint`
+    slot: AtomicU64,
+}
+
+impl CallbackTrait for Callback {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _screen_descriptor: &egui_wgpu::ScreenDescriptor,
+        _egui_encoder: &mut wgpu::CommandEncoder,
+        callback_resources: &mut CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let Some(resources) = callback_resources.get_mut::<Resources>() else {
+            return Vec::new();
+        };
+        resources.pipeline(device, self.name, &self.source, self.version);
+        let slot = resources.next_slot;
+        resources.next_slot = (slot + 1) % SLOTS;
+        self.slot.store(slot, Ordering::Relaxed);
+        let bytes: Vec<u8> = self.uniforms.iter().flat_map(|f| f.to_ne_bytes()).collect();
+        queue.write_buffer(&resources.uniforms, slot * SLOT_STRIDE, &bytes);
+        Vec::new()
+    }
+
+    fn paint(
+        &self,
+        _info: eframe::egui::PaintCallbackInfo,
+        render_pass: &mut wgpu::RenderPass<'static>,
+        callback_resources: &CallbackResources,
+    ) {
+        let Some(resources) = callback_resources.get::<Resources>() else {
+            return;
+        };
+        let Some((_, pipeline)) = resources.pipelines.get(self.name) else {
+            return;
+        };
+        let offset = (self.slot.load(Ordering::Relaxed) * SLOT_STRIDE) as u32;
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &resources.bind_group, &[offset]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
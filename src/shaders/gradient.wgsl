@@ -0,0 +1,9 @@
+// A soft-edged beam sweeping left to right with a smooth falloff, the
+// per-pixel counterpart of the Sweep mode.
+fn mode(uv: vec2<f32>) -> vec3<f32> {
+    let period = 1.0 + u.beam_width;
+    let center = fract(u.time * u.sweep_speed / period) * period - u.beam_width * 0.5;
+    let d = abs(uv.x - center) / max(u.beam_width * 0.5, 0.001);
+    let beam = 1.0 - smoothstep(0.0, 1.0, d);
+    return mix_colors(beam * beam * (3.0 - 2.0 * beam));
+}
@@ -0,0 +1,14 @@
+// Sum-of-sines plasma, evaluated per pixel, with its brightness modulated
+// by the flicker.
+fn mode(uv: vec2<f32>) -> vec3<f32> {
+    let p = centered(uv) * 2.0;
+    let t = u.time * 0.2;
+    let c = p + 0.5 * vec2<f32>(sin(t * 0.3 * TAU), cos(t * 0.2 * TAU));
+    let v = sin(p.x * TAU * 0.5 + t * TAU)
+        + sin((p.y * 0.5 + t * 0.7) * TAU * 0.5)
+        + sin((p.x + p.y + t * 0.5) * TAU * 0.35)
+        + sin(length(c) * TAU * 0.5 - t * TAU);
+    let value = (v / 4.0 + 1.0) * 0.5;
+    let gain = 0.4 + 0.6 * u.level;
+    return hsv(fract(value + t * 0.1), 0.8, gain);
+}
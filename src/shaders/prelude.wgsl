@@ -0,0 +1,75 @@
+// Shared by every shader mode: the uniforms, a full-screen triangle and a
+// few helpers. A mode supplies `fn mode(uv: vec2<f32>) -> vec3<f32>`.
+
+struct Uniforms {
+    on_color: vec4<f32>,
+    off_color: vec4<f32>,
+    // of the painted rect, in points
+    resolution: vec2<f32>,
+    // seconds since the mode was selected
+    time: f32,
+    frequency: f32,
+    // position within the flicker cycle, 0..1
+    phase: f32,
+    // waveform brightness, 0..1
+    level: f32,
+    beam_width: f32,
+    sweep_speed: f32,
+    opacity: f32,
+    show_white: f32,
+    // the picture grading, applied after `mode`
+    brightness: f32,
+    contrast: f32,
+    tint: vec3<f32>,
+    gamma: f32,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+
+const TAU: f32 = 6.283185307179586;
+
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    // 0..1 across the rect, y down
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOut {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    var out: VertexOut;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    let rgb = grade(clamp(mode(in.uv), vec3<f32>(0.0), vec3<f32>(1.0)));
+    // premultiplied, so the painter opacity crossfades modes
+    return vec4<f32>(rgb * u.opacity, u.opacity);
+}
+
+// The same brightness, contrast, gamma and tint `Grading::apply` gives
+// egui's shapes.
+fn grade(rgb: vec3<f32>) -> vec3<f32> {
+    let v = clamp((rgb - vec3<f32>(0.5)) * u.contrast + vec3<f32>(0.5), vec3<f32>(0.0), vec3<f32>(1.0));
+    return clamp(pow(v, vec3<f32>(1.0 / u.gamma)) * u.brightness * u.tint, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Blend from the off colour at 0 to the on colour at 1.
+fn mix_colors(level: f32) -> vec3<f32> {
+    return mix(u.off_color.rgb, u.on_color.rgb, clamp(level, 0.0, 1.0));
+}
+
+// Position with the rect centre at the origin, in units of half its height.
+fn centered(uv: vec2<f32>) -> vec2<f32> {
+    let aspect = u.resolution.x / max(u.resolution.y, 1.0);
+    return vec2<f32>((uv.x * 2.0 - 1.0) * aspect, uv.y * 2.0 - 1.0);
+}
+
+fn hsv(h: f32, s: f32, v: f32) -> vec3<f32> {
+    let k = vec3<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0);
+    let p = abs(fract(vec3<f32>(h) + k) * 6.0 - vec3<f32>(3.0));
+    return v * mix(vec3<f32>(1.0), clamp(p - vec3<f32>(1.0), vec3<f32>(0.0), vec3<f32>(1.0)), s);
+}
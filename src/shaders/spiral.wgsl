@@ -0,0 +1,14 @@
+// Four logarithmic spiral arms with anti-aliased edges. A fixed point sees
+// one edge pass per arm and rotation, so the flicker matches the frequency.
+const ARMS: f32 = 4.0;
+
+fn mode(uv: vec2<f32>) -> vec3<f32> {
+    let p = centered(uv);
+    let rotation = fract(u.time * u.frequency / ARMS);
+    let angle = atan2(p.y, p.x) / TAU + log(max(length(p), 0.001)) * 0.5;
+    let stripe = fract((angle - rotation) * ARMS);
+    let edge = fwidth(stripe) + 0.001;
+    let arm = smoothstep(0.25 - edge, 0.25 + edge, stripe)
+        - smoothstep(0.75 - edge, 0.75 + edge, stripe);
+    return mix_colors(arm);
+}
@@ -0,0 +1,149 @@
+//! Statistics over the session history: time per week, the most used modes
+//! and frequencies, streaks, and the journal.
+
+use std::time::Duration;
+
+use chrono::Local;
+use dreamachine_core::history::History;
+use eframe::egui::{self, Align2, Color32, FontId, Rect, Sense, Vec2};
+
+/// Weeks in the chart.
+const WEEKS: usize = 8;
+/// Entries in the mode and frequency lists.
+const TOP: usize = 5;
+
+pub fn show(ctx: &egui::Context, open: &mut bool, history: &History) {
+    egui::Window::new("Statistics")
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if history.records.is_empty() {
+                ui.label("No sessions yet. Sessions are recorded once they end.");
+                return;
+            }
+            let stats = history.stats(Local::now().date_naive(), WEEKS);
+            egui::Grid::new("stats_totals").show(ui, |ui| {
+                ui.label("Sessions");
+                ui.label(stats.sessions.to_string());
+                ui.end_row();
+                ui.label("Total time");
+                ui.label(hours(stats.total));
+                ui.end_row();
+                ui.label("Current streak");
+                ui.label(days(stats.current_streak));
+                ui.end_row();
+                ui.label("Longest streak");
+                ui.label(days(stats.longest_streak));
+                ui.end_row();
+            });
+            ui.separator();
+            ui.label("Time per week");
+            weeks(ui, &stats.weeks);
+            ui.separator();
+            ui.columns(2, |columns| {
+                columns[0].label("Modes");
+                for (mode, time) in stats.modes.iter().take(TOP) {
+                    columns[0].label(format!("{mode} · {}", hours(*time)));
+                }
+                columns[1].label("Frequencies");
+                for (hz, time) in stats.frequencies.iter().take(TOP) {
+                    columns[1].label(format!("{hz} Hz · {}", hours(*time)));
+                }
+            });
+            ui.separator();
+            ui.collapsing("Journal", |ui| journal(ui, history));
+        });
+}
+
+/// Sessions with notes or ratings, newest first.
+fn journal(ui: &mut egui::Ui, history: &History) {
+    let entries: Vec<_> = history
+        .records
+        .iter()
+        .rev()
+        .filter_map(|record| Some((record, record.journal.as_ref()?)))
+        .collect();
+    if entries.is_empty() {
+        ui.label("Nothing written yet.");
+        return;
+    }
+    egui::ScrollArea::vertical()
+        .max_height(240.0)
+        .show(ui, |ui| {
+            for (record, journal) in entries {
+                let mut heading = record.started.format("%a %-d %b %Y, %H:%M").to_string();
+                if let Some(program) = &record.program {
+                    heading += &format!(" · {program}");
+                }
+                ui.strong(heading);
+                let ratings: Vec<String> = [
+                    ("Relaxation", journal.relaxation),
+                    ("Imagery", journal.imagery),
+                ]
+                .into_iter()
+                .filter_map(|(name, rating)| Some(format!("{name} {}/5", rating?)))
+                .collect();
+                if !ratings.is_empty() {
+                    ui.label(ratings.join(" · "));
+                }
+                if !journal.notes.is_empty() {
+                    ui.label(&journal.notes);
+                }
+                ui.add_space(6.0);
+            }
+        });
+}
+
+fn hours(time: Duration) -> String {
+    let mins = time.as_secs() / 60;
+    format!("{}:{:02} h", mins / 60, mins % 60)
+}
+
+fn days(n: u32) -> String {
+    if n == 1 {
+        "1 day".into()
+    } else {
+        format!("{n} days")
+    }
+}
+
+/// Bar per week, labelled with its Monday.
+fn weeks(ui: &mut egui::Ui, weeks: &[(chrono::NaiveDate, Duration)]) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(320.0, 100.0), Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, Color32::from_black_alpha(80));
+    let most = weeks
+        .iter()
+        .map(|(_, time)| time.as_secs_f32())
+        .fold(0.0, f32::max)
+        .max(1.0);
+    let width = rect.width() / weeks.len().max(1) as f32;
+    let label = 14.0;
+    let font = FontId::proportional(10.0);
+    for (i, (monday, time)) in weeks.iter().enumerate() {
+        let x = rect.left() + i as f32 * width;
+        let height = (rect.height() - 2.0 * label) * time.as_secs_f32() / most;
+        let bottom = rect.bottom() - label;
+        let bar = Rect::from_min_max(
+            egui::pos2(x + 3.0, bottom - height),
+            egui::pos2(x + width - 3.0, bottom),
+        );
+        painter.rect_filled(bar, 1.0, Color32::LIGHT_BLUE);
+        if !time.is_zero() {
+            painter.text(
+                egui::pos2(bar.center().x, bar.top() - 1.0),
+                Align2::CENTER_BOTTOM,
+                format!("{}m", time.as_secs() / 60),
+                font.clone(),
+                Color32::GRAY,
+            );
+        }
+        painter.text(
+            egui::pos2(bar.center().x, rect.bottom() - 1.0),
+            Align2::CENTER_BOTTOM,
+            monday.format("%-d %b"),
+            font.clone(),
+            Color32::GRAY,
+        );
+    }
+}
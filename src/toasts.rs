@@ -0,0 +1,45 @@
+//! Short-lived error notifications in the corner of the window.
+
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, Color32};
+
+/// How long a toast stays up unless dismissed.
+const LIFETIME: Duration = Duration::from_secs(8);
+
+#[derive(Default)]
+pub struct Toasts {
+    items: Vec<(String, Instant)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: impl Into<String>) {
+        self.items.push((message.into(), Instant::now()));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.items.retain(|(_, shown)| shown.elapsed() < LIFETIME);
+        if self.items.is_empty() {
+            return;
+        }
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_TOP, [-12.0, 36.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (i, (message, _)) in self.items.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(Color32::LIGHT_RED, message.as_str());
+                            if ui.small_button("×").clicked() {
+                                dismissed = Some(i);
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some(i) = dismissed {
+            self.items.remove(i);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! WLED controllers driving LED strips.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use dreamachine_core::lights::Sample;
+use dreamachine_core::lights::wled;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::lights::{Device, Sink};
+
+/// Seconds until a controller goes back to its own effects once frames stop.
+const TIMEOUT_SECS: u8 = 2;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WledSettings {
+    pub enabled: bool,
+    /// Host names or addresses of the controllers.
+    pub hosts: Vec<String>,
+    /// LEDs on each strip; all of them show the stimulus.
+    pub leds: u16,
+    /// Frames per second.
+    pub rate: f32,
+}
+
+impl Default for WledSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hosts: vec!["wled.local".into()],
+            leds: 30,
+            rate: 60.0,
+        }
+    }
+}
+
+impl Device for WledSettings {
+    type Sink = Wled;
+
+    const NAME: &'static str = "WLED";
+
+    fn enabled(&mut self) -> &mut bool {
+        &mut self.enabled
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Controllers");
+        let mut remove = None;
+        for (i, host) in self.hosts.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(host);
+                if ui.small_button("×").on_hover_text("Remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            self.hosts.remove(i);
+        }
+        if ui.button("Add Controller").clicked() {
+            self.hosts.push(String::new());
+        }
+        egui::Grid::new("wled_settings").show(ui, |ui| {
+            ui.label("LEDs");
+            ui.add(egui::DragValue::new(&mut self.leds).range(1..=4096));
+            ui.end_row();
+            ui.label("Rate");
+            ui.add(
+                egui::DragValue::new(&mut self.rate)
+                    .range(1.0..=120.0)
+                    .suffix(" fps"),
+            );
+            ui.end_row();
+        });
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.rate.clamp(1.0, 120.0))
+    }
+
+    fn connect(&self) -> io::Result<Wled> {
+        let mut targets = Vec::new();
+        for host in self
+            .hosts
+            .iter()
+            .map(|h| h.trim())
+            .filter(|h| !h.is_empty())
+        {
+            let addr = (host, wled::PORT)
+                .to_socket_addrs()?
+                .find(SocketAddr::is_ipv4)
+                .ok_or_else(|| io::Error::other(format!("{host} has no IPv4 address")))?;
+            targets.push(addr);
+        }
+        if targets.is_empty() {
+            return Err(io::Error::other("no controllers"));
+        }
+        Ok(Wled {
+            socket: UdpSocket::bind(("0.0.0.0", 0))?,
+            targets,
+            leds: self.leds as usize,
+        })
+    }
+}
+
+pub struct Wled {
+    socket: UdpSocket,
+    targets: Vec<SocketAddr>,
+    leds: usize,
+}
+
+impl Sink for Wled {
+    fn show(&mut self, sample: &Sample) -> io::Result<()> {
+        for packet in wled::packets(sample.rgb(), self.leds, TIMEOUT_SECS) {
+            for target in &self.targets {
+                self.socket.send_to(&packet, target)?;
+            }
+        }
+        Ok(())
+    }
+}